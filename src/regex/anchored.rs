@@ -0,0 +1,101 @@
+//! Anchored and full-match searches chosen at search time.
+//!
+//! Patterns compiled without the `y` flag start with an unanchored prefix
+//! (`split_goto_first`, `any`, `goto`) that retries the pattern at every
+//! position. Anchored searches start the engine past that prefix, so one
+//! compiled `Regex` serves searching, tokenizing and validation alike. A
+//! full match additionally rejects every `match` that is reached before the
+//! end of the input, so other alternatives are still tried.
+
+use super::{input, Captures, Flags, Match, Regex};
+
+/// Length of the unanchored prefix of a non-sticky pattern.
+const PREFIX_LEN: usize = 5 + 1 + 5;
+
+/// Where a search may start and end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Anchored {
+    /// The match may start anywhere at or after the search start
+    #[default]
+    No,
+    /// The match must start at the search start, as with the `y` flag
+    Start,
+    /// The match must start at the search start and end at the end of the
+    /// input
+    Both,
+}
+
+/// Bytecode position of the pattern itself, past the unanchored prefix.
+pub(crate) fn pattern_start(bytecode: &[u8]) -> usize {
+    let flags = Flags::from_bits(u16::from_le_bytes([bytecode[0], bytecode[1]]));
+    if flags.is_sticky() {
+        Regex::HEADER_LEN
+    } else {
+        Regex::HEADER_LEN + PREFIX_LEN
+    }
+}
+
+impl Regex {
+    /// Like `find_at`, but anchored as `anchored` asks.
+    pub fn find_at_anchored(&self, text: &str, start: usize, anchored: Anchored) -> Option<Match> {
+        self.find_at_anchored_bytes(text.as_bytes(), start, anchored)
+    }
+
+    pub(crate) fn find_at_anchored_bytes(&self, text: &[u8], start: usize, anchored: Anchored) -> Option<Match> {
+        if anchored == Anchored::No {
+            return self.find_at_bytes(text, start);
+        }
+        let slots = self.span_slots(text, start..text.len(), anchored)?;
+        Some(Match { start: slots[0]?, end: slots[1]? })
+    }
+
+    /// Like `captures_at`, but anchored as `anchored` asks.
+    pub fn captures_at_anchored<'t>(&self, text: &'t str, start: usize, anchored: Anchored) -> Option<Captures<'t>> {
+        let groups = self.captures_at_anchored_bytes(text.as_bytes(), start, anchored)?;
+        Some(self.make_captures(text, groups))
+    }
+
+    pub(crate) fn captures_at_anchored_bytes(&self, text: &[u8], start: usize, anchored: Anchored) -> Option<Vec<Option<(usize, usize)>>> {
+        if anchored == Anchored::No {
+            return self.captures_at_bytes(text, start);
+        }
+        let slots = self.span_slots(text, start..text.len(), anchored)?;
+        Some(input::groups(slots))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Anchored, Flags, Match, Regex, RegexBuilder};
+
+    #[test]
+    fn test_start_anchored() {
+        for re in [Regex::new(r"\d+").unwrap(), RegexBuilder::new(r"\d+").pike_vm(false).build().unwrap()] {
+            assert_eq!(re.find_at_anchored("ab12 3", 2, Anchored::Start), Some(Match { start: 2, end: 4 }));
+            assert_eq!(re.find_at_anchored("ab12 3", 1, Anchored::Start), None);
+            assert_eq!(re.find_at_anchored("ab12 3", 1, Anchored::No), Some(Match { start: 2, end: 4 }));
+            assert_eq!(re.find_at_anchored("ab12 3", 7, Anchored::Start), None);
+        }
+    }
+
+    #[test]
+    fn test_full_match_tries_other_alternatives() {
+        for re in [Regex::new("(a|ab)(c|bcd)?").unwrap(), Regex::new(r"(a|ab)(c|bcd)?(?=\w*)\1?").unwrap()] {
+            assert_eq!(re.find_at_anchored("abc", 0, Anchored::Start), re.find("abc"));
+            let caps = re.captures_at_anchored("abc", 0, Anchored::Both).unwrap();
+            assert_eq!((caps.get_str(1), caps.get_str(2)), (Some("ab"), Some("c")));
+            assert_eq!(re.find_at_anchored("abcx", 0, Anchored::Both), None);
+            assert_eq!(re.find_at_anchored("xabcd", 1, Anchored::Both), Some(Match { start: 1, end: 5 }));
+        }
+    }
+
+    #[test]
+    fn test_sticky_and_anchored_patterns() {
+        let sticky = Regex::with_flags("b+", Flags::parse("y").unwrap()).unwrap();
+        assert_eq!(sticky.find_at_anchored("abb", 1, Anchored::Both), Some(Match { start: 1, end: 3 }));
+        assert_eq!(sticky.find_at_anchored("abb", 0, Anchored::Start), None);
+        let re = Regex::new("^a|b$").unwrap();
+        assert_eq!(re.find_at_anchored("ab", 1, Anchored::Both), Some(Match { start: 1, end: 2 }));
+        assert_eq!(re.find_at_anchored("ab", 0, Anchored::Both), None);
+    }
+}
@@ -112,6 +112,11 @@ impl BitState {
         }
     }
 
+    #[inline(always)]
+    fn clear_bit(&mut self, bit: usize) {
+        self.words[bit / 64] &= !(1u64 << (bit % 64));
+    }
+
     #[inline(always)]
     fn clear(&mut self) {
         for w in &mut self.words {
@@ -318,6 +323,51 @@ impl BitVmProgram {
             }
         }
 
+        // Non-ASCII input is matched per UTF-8 lead byte, not per code point:
+        // every byte >= 0x80 is accepted by any state that could consume a
+        // non-ASCII char, and continuation bytes also keep the current states
+        // alive (see `is_continuation`). This keeps the byte-level NFA an
+        // over-approximation for multi-byte chars and invalid UTF-8 alike.
+        for (state_idx, &state_pc) in state_to_pc.iter().enumerate() {
+            let opcode = bytecode[state_pc];
+            let accepts_non_ascii = match opcode {
+                op::ANY | op::DOT | op::SPACE | op::NOT_SPACE => true,
+                // Case folding can map ASCII to non-ASCII (e.g. k / U+212A)
+                op::CHAR_I | op::RANGE_I => true,
+                op::CHAR => u16::from_le_bytes([bytecode[state_pc + 1], bytecode[state_pc + 2]]) >= 0x80,
+                op::CHAR32 => true,
+                op::RANGE => {
+                    let pair_count = u16::from_le_bytes([
+                        bytecode[state_pc + 1], bytecode[state_pc + 2]
+                    ]) as usize;
+                    (0..pair_count).any(|i| {
+                        let base = state_pc + 3 + i * 4;
+                        u16::from_le_bytes([bytecode[base + 2], bytecode[base + 3]]) >= 0x80
+                    })
+                }
+                op::RANGE32 => {
+                    let pair_count = u16::from_le_bytes([
+                        bytecode[state_pc + 1], bytecode[state_pc + 2]
+                    ]) as usize;
+                    (0..pair_count).any(|i| {
+                        let base = state_pc + 3 + i * 8 + 4;
+                        u32::from_le_bytes([
+                            bytecode[base], bytecode[base + 1],
+                            bytecode[base + 2], bytecode[base + 3],
+                        ]) >= 0x80
+                    })
+                }
+                _ => false,
+            };
+            for b in 0x80..256usize {
+                if accepts_non_ascii {
+                    char_masks[b].set(state_idx);
+                } else {
+                    char_masks[b].clear_bit(state_idx);
+                }
+            }
+        }
+
         // Build epsilon closures from each consuming state to the next consuming states
         let mut epsilon_closure = vec![BitState::new(num_states); num_states];
         for (state_idx, &state_pc) in state_to_pc.iter().enumerate() {
@@ -528,6 +578,9 @@ impl BitVmProgram {
                 }
             }
         }
+        if is_continuation(byte) {
+            next.or_assign(state);
+        }
         // Always keep the initial state alive (prefix loop for unanchored search)
         next.or_assign(&self.initial_state);
         next
@@ -575,7 +628,21 @@ impl BitVmProgram {
         }
         // Initialize curr from initial_state (copy, no alloc)
         curr.words.copy_from_slice(&self.initial_state.words);
-        self.find_match_end_inner(input, start_pos, curr, next)
+        self.find_match_end_inner(input, start_pos, curr, next, false)
+    }
+
+    /// Like `find_match_end_reuse`, but stops at the first position where any
+    /// match ends instead of extending it.
+    pub fn earliest_match_end_reuse(&self, input: &[u8], start_pos: usize, curr: &mut BitState, next: &mut BitState) -> Option<usize> {
+        let w = self.num_words;
+        if curr.words.len() != w {
+            curr.words.resize(w, 0);
+        }
+        if next.words.len() != w {
+            next.words.resize(w, 0);
+        }
+        curr.words.copy_from_slice(&self.initial_state.words);
+        self.find_match_end_inner(input, start_pos, curr, next, true)
     }
 
     /// Find the end position of the leftmost match starting at or after `start_pos`.
@@ -583,15 +650,18 @@ impl BitVmProgram {
     pub fn find_match_end(&self, input: &[u8], start_pos: usize) -> Option<usize> {
         let mut curr = self.initial_state.clone();
         let mut next = BitState::new(self.num_states);
-        self.find_match_end_inner(input, start_pos, &mut curr, &mut next)
+        self.find_match_end_inner(input, start_pos, &mut curr, &mut next, false)
     }
 
-    fn find_match_end_inner(&self, input: &[u8], start_pos: usize, curr: &mut BitState, next: &mut BitState) -> Option<usize> {
+    fn find_match_end_inner(&self, input: &[u8], start_pos: usize, curr: &mut BitState, next: &mut BitState, earliest: bool) -> Option<usize> {
         let w = self.num_words;
         let mut best_end: Option<usize> = None;
 
         if curr.any_set(&self.match_mask) {
             best_end = Some(start_pos);
+            if earliest {
+                return best_end;
+            }
         }
 
         let mut found_match = false;
@@ -643,10 +713,16 @@ impl BitVmProgram {
                 dense_seen &= dense_seen - 1;
                 next.or_assign(&self.dense_closures[id]);
             }
+            if is_continuation(byte as u8) {
+                next.or_assign(curr);
+            }
 
             if next.any_set(&self.match_mask) {
                 best_end = Some(at + 1);
                 found_match = true;
+                if earliest {
+                    break;
+                }
             }
 
             // In phase 2: if no non-initial threads remain, match is complete
@@ -805,6 +881,13 @@ pub fn instruction_size(bytecode: &[u8], pc: usize) -> usize {
     }
 }
 
+/// UTF-8 continuation byte. A multi-byte char is consumed by its lead byte;
+/// continuation bytes may either be skipped or consumed as a stray byte.
+#[inline(always)]
+fn is_continuation(byte: u8) -> bool {
+    byte & 0xC0 == 0x80
+}
+
 fn to_lower(c: u32) -> u32 {
     if c >= 'A' as u32 && c <= 'Z' as u32 { c + 32 } else { c }
 }
@@ -859,6 +942,18 @@ mod tests {
         assert!(!bit_has_match("a.c", "a\nc"));
     }
 
+    #[test]
+    fn test_non_ascii_input() {
+        // Multi-byte chars and invalid bytes must never be rejected
+        assert!(bit_has_match("a.b", "a\u{e9}b"));
+        assert!(bit_has_match("a[^x]b", "a\u{20ac}b"));
+        assert!(bit_has_match("\u{e9}", "caf\u{e9}"));
+        let bc = compiler::compile_regex("a.b", Flags::empty()).unwrap();
+        let prog = BitVmProgram::compile(&bc).unwrap();
+        assert!(prog.has_match(b"a\x80b"));
+        assert_eq!(prog.find_match_end(b"xa\xe2\x82\xacb", 0), Some(6));
+    }
+
     #[test]
     fn test_no_catastrophic() {
         // Pattern that causes exponential backtracking in backtrackers
@@ -0,0 +1,200 @@
+//! Per-regex engine limits and switches.
+//!
+//! `Regex::new` picks engines and search shortcuts automatically and uses
+//! fixed limits. `RegexBuilder` lets a call site override them, e.g. a lower
+//! backtrack budget for untrusted patterns, or no prefilters so every search
+//! takes the same path.
+
+use super::{interpreter, pikevm, Flags, Regex, Result, OPTIMIZATION_THRESHOLD};
+
+/// Engine configuration stored in each compiled `Regex`.
+#[derive(Debug, Clone)]
+pub(crate) struct Config {
+    /// Backtrack steps per `exec` before the interpreter gives up
+    pub(crate) backtrack_limit: usize,
+    /// Backtrack stack frames per `exec` before the interpreter gives up
+    pub(crate) backtrack_stack_limit: usize,
+    /// States the lazy DFA caches before stepping uncached
+    pub(crate) dfa_cache_size: usize,
+    /// Whether patterns without backreferences or lookaround use the Pike VM
+    pub(crate) pike_vm: bool,
+    /// Whether the bit-parallel VM is compiled for fast rejection
+    pub(crate) bit_vm: bool,
+    /// Whether large top-level alternations are split into sub-patterns
+    pub(crate) decompose_alternations: bool,
+    /// Whether literal and byte prefilters and specialized search strategies are used
+    pub(crate) prefilters: bool,
+    /// Inputs shorter than this (in bytes) skip the search strategies
+    pub(crate) small_input_threshold: usize,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            backtrack_limit: interpreter::MAX_BACKTRACK_STEPS,
+            backtrack_stack_limit: usize::MAX,
+            dfa_cache_size: pikevm::MAX_DFA_STATES,
+            pike_vm: true,
+            bit_vm: true,
+            decompose_alternations: true,
+            prefilters: true,
+            small_input_threshold: OPTIMIZATION_THRESHOLD,
+        }
+    }
+}
+
+/// Builds a `Regex` with non-default flags, limits or engine choices.
+///
+/// ```
+/// use quickjs_regex::{Flags, RegexBuilder};
+///
+/// let re = RegexBuilder::new(r"(a+)+\1")
+///     .flags(Flags::from_bits(Flags::IGNORE_CASE))
+///     .backtrack_limit(10_000)
+///     .build()
+///     .unwrap();
+/// assert!(re.is_match("xAa"));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RegexBuilder {
+    pattern: String,
+    flags: Flags,
+    config: Config,
+}
+
+impl RegexBuilder {
+    /// Start building a regex for `pattern`, with default settings.
+    pub fn new(pattern: &str) -> Self {
+        RegexBuilder {
+            pattern: pattern.to_string(),
+            flags: Flags::empty(),
+            config: Config::default(),
+        }
+    }
+
+    /// Set the flags (default: none).
+    pub fn flags(&mut self, flags: Flags) -> &mut Self {
+        self.flags = flags;
+        self
+    }
+
+    /// Maximum backtrack steps per match attempt of the backtracking
+    /// interpreter (default: 5,000,000). An attempt that runs out of steps
+    /// counts as no match at that position.
+    pub fn backtrack_limit(&mut self, steps: usize) -> &mut Self {
+        self.config.backtrack_limit = steps;
+        self
+    }
+
+    /// Maximum number of frames on the backtracking interpreter's stack
+    /// (default: unlimited). An attempt that outgrows it counts as no match
+    /// at that position, unless its current path matches.
+    pub fn backtrack_stack_limit(&mut self, frames: usize) -> &mut Self {
+        self.config.backtrack_stack_limit = frames;
+        self
+    }
+
+    /// Maximum number of states the lazy DFA caches (default: 65,536).
+    /// Past the limit, scanning continues without caching new states.
+    pub fn dfa_cache_size(&mut self, states: usize) -> &mut Self {
+        self.config.dfa_cache_size = states;
+        self
+    }
+
+    /// Use the Pike VM for patterns it supports (default: true). When false,
+    /// every search runs on the backtracking interpreter.
+    pub fn pike_vm(&mut self, yes: bool) -> &mut Self {
+        self.config.pike_vm = yes;
+        self
+    }
+
+    /// Compile the bit-parallel VM used to reject non-matching inputs
+    /// quickly (default: true).
+    pub fn bit_vm(&mut self, yes: bool) -> &mut Self {
+        self.config.bit_vm = yes;
+        self
+    }
+
+    /// Split large top-level alternations into separately compiled
+    /// branches behind a shared literal automaton (default: true).
+    pub fn decompose_alternations(&mut self, yes: bool) -> &mut Self {
+        self.config.decompose_alternations = yes;
+        self
+    }
+
+    /// Use literal and byte prefilters and the specialized search strategies
+    /// (default: true). When false, searches try every position in turn.
+    pub fn prefilters(&mut self, yes: bool) -> &mut Self {
+        self.config.prefilters = yes;
+        self
+    }
+
+    /// Inputs shorter than this many bytes are searched position by position
+    /// without consulting the search strategy (default: 32).
+    pub fn small_input_threshold(&mut self, bytes: usize) -> &mut Self {
+        self.config.small_input_threshold = bytes;
+        self
+    }
+
+    /// Compile the regex.
+    pub fn build(&self) -> Result<Regex> {
+        Regex::with_config(&self.pattern, self.flags, self.config.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Flags, Regex, RegexBuilder};
+
+    #[test]
+    fn test_options_do_not_change_results() {
+        let text = "user@example.com, 2024-05-17 and x@y.org 42";
+        let patterns = [r"\w+@\w+\.\w+", r"\d{4}-\d{2}-\d{2}", r"(\w)\1", r"(?<=@)\w+", "org|com|net", "$|^bab", r"(?:x){0,2}?|a"];
+        for pattern in patterns {
+            let re = Regex::new(pattern).unwrap();
+            let expected: Vec<_> = re.find_iter(text).collect();
+            let variants = [
+                RegexBuilder::new(pattern).pike_vm(false).build().unwrap(),
+                RegexBuilder::new(pattern).bit_vm(false).prefilters(false).build().unwrap(),
+                RegexBuilder::new(pattern).dfa_cache_size(1).small_input_threshold(0).build().unwrap(),
+            ];
+            for variant in &variants {
+                assert_eq!(variant.find_iter(text).collect::<Vec<_>>(), expected, "{}", pattern);
+                assert_eq!(variant.captures(text).map(|c| c.get(0)), re.captures(text).map(|c| c.get(0)));
+            }
+        }
+    }
+
+    #[test]
+    fn test_backtrack_limit() {
+        // (\w+) backtracks one char at a time until \1 fits: ~200 steps
+        let text = "a".repeat(400);
+        let pattern = r"^(\w+)\1$";
+        assert_eq!(Regex::new(pattern).unwrap().find(&text).map(|m| m.end), Some(400));
+        let limited = RegexBuilder::new(pattern).backtrack_limit(50).build().unwrap();
+        assert!(limited.find(&text).is_none());
+        assert!(limited.captures(&text).is_none());
+        let mut locs = limited.capture_locations();
+        assert!(limited.captures_read(&mut locs, &text).is_none());
+        assert!(limited.is_match("aa"));
+    }
+
+    #[test]
+    fn test_decomposition_switch() {
+        let words = ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot",
+                     "golf", "hotel", "india", "juliet", "kilo", "lima"];
+        let pattern = words.join("|");
+        assert!(Regex::new(&pattern).unwrap().decomposition_coverage().is_some());
+        let re = RegexBuilder::new(&pattern).decompose_alternations(false).build().unwrap();
+        assert!(re.decomposition_coverage().is_none());
+        assert_eq!(re.count_matches("echo kilo lima xray"), 3);
+    }
+
+    #[test]
+    fn test_flags() {
+        let re = RegexBuilder::new("abc").flags(Flags::parse("gi").unwrap()).build().unwrap();
+        assert_eq!(re.flags(), Flags::parse("gi").unwrap());
+        assert!(re.is_match("xABC"));
+        assert!(RegexBuilder::new("(").build().is_err());
+    }
+}
@@ -0,0 +1,325 @@
+//! Byte-oriented regex API for haystacks that are not valid UTF-8.
+//!
+//! All offsets are byte offsets into the haystack. Valid UTF-8 sequences are
+//! matched as chars, exactly like the `&str` API, and encoded surrogates
+//! (WTF-8) as the surrogate code point. Each byte that is not part of a valid
+//! sequence is matched as one char of its own:
+//!
+//! - in `u` mode it reads as U+FFFD, so only `.`, negated classes, `\W`, `\S`
+//!   and the like can match it;
+//! - otherwise it reads as the byte value (U+0080..U+00FF), so `[\x80-\xff]`
+//!   or `\xff` can match raw bytes.
+
+use std::borrow::Cow;
+use std::io::Read;
+
+use super::{Anchored, Flags, MatchIterator, PushMatcher, Result, StreamSearcher};
+
+/// A compiled regular expression for searching byte slices.
+pub struct Regex {
+    inner: super::Regex,
+}
+
+impl Regex {
+    /// Compile a new regular expression
+    pub fn new(pattern: &str) -> Result<Self> {
+        Self::with_flags(pattern, Flags::empty())
+    }
+
+    /// Compile a new regular expression with flags
+    pub fn with_flags(pattern: &str, flags: Flags) -> Result<Self> {
+        let inner = super::Regex::with_flags(pattern, flags)?.into_bytes_safe();
+        Ok(Regex { inner })
+    }
+
+    /// Test if the pattern matches anywhere in the haystack
+    pub fn is_match(&self, haystack: &[u8]) -> bool {
+        self.inner.is_match_bytes(haystack)
+    }
+
+    /// Find the first match in the haystack
+    pub fn find(&self, haystack: &[u8]) -> Option<Match> {
+        self.inner.find_bytes(haystack).map(Match::from)
+    }
+
+    /// Find a match starting at or after the given byte offset.
+    pub fn find_at(&self, haystack: &[u8], start: usize) -> Option<Match> {
+        self.inner.find_at_bytes(haystack, start).map(Match::from)
+    }
+
+    /// Like `find_at`, but anchored as `anchored` asks.
+    pub fn find_at_anchored(&self, haystack: &[u8], start: usize, anchored: Anchored) -> Option<Match> {
+        self.inner.find_at_anchored_bytes(haystack, start, anchored).map(Match::from)
+    }
+
+    /// End of the earliest match: the first position at which some match
+    /// ends.
+    pub fn shortest_match(&self, haystack: &[u8]) -> Option<usize> {
+        self.inner.shortest_match_at_bytes(haystack, 0, None)
+    }
+
+    /// Like `shortest_match`, for matches starting at or after `start`.
+    pub fn shortest_match_at(&self, haystack: &[u8], start: usize) -> Option<usize> {
+        self.inner.shortest_match_at_bytes(haystack, start, None)
+    }
+
+    /// Find all non-overlapping matches.
+    pub fn find_iter<'r, 'h>(&'r self, haystack: &'h [u8]) -> Matches<'r, 'h> {
+        Matches { inner: self.inner.find_iter_bytes(haystack) }
+    }
+
+    /// Iterate over the matches starting at every position, including ones
+    /// that overlap. Each is the leftmost-first match at its start.
+    pub fn find_overlapping_iter<'r, 'h>(&'r self, haystack: &'h [u8]) -> OverlappingMatches<'r, 'h> {
+        OverlappingMatches { inner: self.inner.find_overlapping_iter_bytes(haystack) }
+    }
+
+    /// Search a stream chunk by chunk, yielding matches with offsets from
+    /// the start of the stream. See `StreamSearcher` for the limits.
+    pub fn stream<R: Read>(&self, reader: R) -> StreamSearcher<'_, R> {
+        StreamSearcher::new(&self.inner, reader)
+    }
+
+    /// Matcher that is fed the haystack piece by piece. Fails for patterns
+    /// the Pike VM cannot run (backreferences and lookaround).
+    pub fn push_matcher(&self) -> Result<PushMatcher<'_>> {
+        PushMatcher::new(&self.inner)
+    }
+
+    /// Count all non-overlapping matches.
+    pub fn count_matches(&self, haystack: &[u8]) -> usize {
+        self.inner.count_matches_bytes(haystack)
+    }
+
+    /// Get capture groups from the first match.
+    pub fn captures<'h>(&self, haystack: &'h [u8]) -> Option<Captures<'h>> {
+        self.captures_at(haystack, 0)
+    }
+
+    /// Get capture groups from a match starting at or after the given byte offset.
+    pub fn captures_at<'h>(&self, haystack: &'h [u8], start: usize) -> Option<Captures<'h>> {
+        let groups = self.inner.captures_at_bytes(haystack, start)?;
+        Some(Captures { haystack: Cow::Borrowed(haystack), groups })
+    }
+
+    /// Like `captures_at`, but anchored as `anchored` asks.
+    pub fn captures_at_anchored<'h>(&self, haystack: &'h [u8], start: usize, anchored: Anchored) -> Option<Captures<'h>> {
+        let groups = self.inner.captures_at_anchored_bytes(haystack, start, anchored)?;
+        Some(Captures { haystack: Cow::Borrowed(haystack), groups })
+    }
+
+    /// Get the number of capture groups (including group 0)
+    pub fn capture_count(&self) -> usize {
+        self.inner.capture_count()
+    }
+
+    /// Get the flags
+    pub fn flags(&self) -> Flags {
+        self.inner.flags()
+    }
+
+    /// Get the original pattern
+    pub fn pattern(&self) -> &str {
+        self.inner.pattern()
+    }
+}
+
+impl std::fmt::Debug for Regex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Regex")
+            .field("pattern", &self.inner.pattern())
+            .field("flags", &self.inner.flags())
+            .finish()
+    }
+}
+
+impl std::fmt::Display for Regex {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.inner, f)
+    }
+}
+
+/// A match result with start and end byte positions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Match {
+    /// Start byte offset (inclusive)
+    pub start: usize,
+    /// End byte offset (exclusive)
+    pub end: usize,
+}
+
+impl Match {
+    /// Get the matched bytes from the original haystack
+    pub fn as_bytes<'a>(&self, haystack: &'a [u8]) -> &'a [u8] {
+        &haystack[self.start..self.end]
+    }
+
+    /// Get the length of the match in bytes
+    pub fn len(&self) -> usize {
+        self.end - self.start
+    }
+
+    /// Check if the match is empty
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+impl From<super::Match> for Match {
+    fn from(m: super::Match) -> Self {
+        Match { start: m.start, end: m.end }
+    }
+}
+
+/// Captured groups from a regex match on a byte slice.
+///
+/// Group 0 is always the entire match. Groups 1+ are the explicit
+/// capture groups in the pattern. The haystack is borrowed; use
+/// `into_owned` to keep the captures beyond its lifetime.
+#[derive(Debug, Clone)]
+pub struct Captures<'h> {
+    /// The original haystack
+    haystack: Cow<'h, [u8]>,
+    /// Pairs of (start, end) byte offsets for each group
+    /// None means the group didn't participate in the match
+    groups: Vec<Option<(usize, usize)>>,
+}
+
+impl<'h> Captures<'h> {
+    /// Copy the haystack so the captures no longer borrow it.
+    pub fn into_owned(self) -> Captures<'static> {
+        Captures { haystack: Cow::Owned(self.haystack.into_owned()), groups: self.groups }
+    }
+
+    /// Get the number of capture groups (including group 0).
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Check if there are no captures (should never be true for a valid match).
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Get a specific capture group by index.
+    pub fn get(&self, i: usize) -> Option<Match> {
+        self.groups.get(i).and_then(|opt| {
+            opt.map(|(start, end)| Match { start, end })
+        })
+    }
+
+    /// Get the bytes of a specific capture group.
+    pub fn get_bytes(&self, i: usize) -> Option<&[u8]> {
+        self.get(i).map(|m| &self.haystack[m.start..m.end])
+    }
+
+    /// Get the entire match (group 0).
+    pub fn entire_match(&self) -> Option<Match> {
+        self.get(0)
+    }
+
+    /// Iterate over all capture groups.
+    pub fn iter(&self) -> impl Iterator<Item = Option<Match>> + '_ {
+        self.groups.iter().map(|opt| {
+            opt.map(|(start, end)| Match { start, end })
+        })
+    }
+
+    /// Count the number of capture groups that actually matched (non-None).
+    pub fn count_matched(&self) -> usize {
+        self.groups.iter().filter(|g| g.is_some()).count()
+    }
+}
+
+/// An iterator over all non-overlapping matches in a byte slice.
+pub struct Matches<'r, 'h> {
+    inner: MatchIterator<'r, 'h>,
+}
+
+impl<'r, 'h> Iterator for Matches<'r, 'h> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        self.inner.next().map(Match::from)
+    }
+}
+
+/// An iterator over all matches in a byte slice, including overlapping ones.
+pub struct OverlappingMatches<'r, 'h> {
+    inner: super::OverlappingMatches<'r, 'h>,
+}
+
+impl<'r, 'h> Iterator for OverlappingMatches<'r, 'h> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        self.inner.next().map(Match::from)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_matches_like_str_api() {
+        let re = Regex::new(r"(\w+)@(\w+)").unwrap();
+        let caps = re.captures(b"mail: user@example").unwrap();
+        assert_eq!(caps.get_bytes(1), Some(&b"user"[..]));
+        assert_eq!(caps.get_bytes(2), Some(&b"example"[..]));
+        assert_eq!(re.find(b"x a@b"), Some(Match { start: 2, end: 5 }));
+    }
+
+    #[test]
+    fn test_invalid_utf8_is_one_char_per_byte() {
+        let re = Regex::new("a.b").unwrap();
+        assert_eq!(re.find(b"xa\xffb"), Some(Match { start: 1, end: 4 }));
+        assert!(!re.is_match(b"a\xff\xfeb"));
+        // Truncated sequence: lead byte and continuation are separate chars
+        let re = Regex::new("a..b").unwrap();
+        assert_eq!(re.find(b"a\xe2\x82b"), Some(Match { start: 0, end: 4 }));
+    }
+
+    #[test]
+    fn test_invalid_byte_value_non_unicode() {
+        let re = Regex::new(r"[\x80-\xff]+").unwrap();
+        assert_eq!(re.find(b"ab\xe9\xffcd"), Some(Match { start: 2, end: 4 }));
+        // A literal Latin-1 char also matches its lone byte
+        let re = Regex::new("caf\u{e9}").unwrap();
+        assert_eq!(re.find(b"caf\xe9!"), Some(Match { start: 0, end: 4 }));
+        assert_eq!(re.find("caf\u{e9}!".as_bytes()), Some(Match { start: 0, end: 5 }));
+    }
+
+    #[test]
+    fn test_invalid_byte_unicode_mode() {
+        let re = Regex::with_flags(r"[\x80-\xff]", Flags::from_bits(Flags::UNICODE)).unwrap();
+        assert!(!re.is_match(b"\xe9"));
+        let re = Regex::with_flags("\u{FFFD}", Flags::from_bits(Flags::UNICODE)).unwrap();
+        assert_eq!(re.find(b"ab\xe9"), Some(Match { start: 2, end: 3 }));
+        let re = Regex::with_flags(r"\S+", Flags::from_bits(Flags::UNICODE)).unwrap();
+        assert_eq!(re.find(b" a\xffb "), Some(Match { start: 1, end: 4 }));
+    }
+
+    #[test]
+    fn test_find_iter_advances_over_invalid_bytes() {
+        let re = Regex::new("").unwrap();
+        let ends: Vec<_> = re.find_iter(b"\xff\xe2\x82\xaca").map(|m| m.start).collect();
+        assert_eq!(ends, vec![0, 1, 4, 5]);
+        let re = Regex::new(r"\d+").unwrap();
+        assert_eq!(re.find_iter(b"1\xff22\xfe333").count(), 3);
+        assert_eq!(re.count_matches(b"1\xff22\xfe333"), 3);
+    }
+
+    #[test]
+    fn test_long_haystack_with_backref() {
+        let mut hay = vec![b'x'; 100];
+        hay.extend_from_slice(b"caf\xe9 (ab)\xff(ab) ");
+        hay.extend(vec![b'y'; 100]);
+        let re = Regex::new("caf\u{e9}").unwrap();
+        assert_eq!(re.find(&hay), Some(Match { start: 100, end: 104 }));
+        let re = Regex::new(r"\((\w+)\)\xff\(\1\)").unwrap();
+        let caps = re.captures_at(&hay, 10).unwrap();
+        assert_eq!(caps.get(0), Some(Match { start: 105, end: 114 }));
+        assert_eq!(caps.get_bytes(1), Some(&b"ab"[..]));
+    }
+}
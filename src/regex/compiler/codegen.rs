@@ -9,6 +9,8 @@ pub struct CodeGenerator {
     flags: Flags,
     capture_count: u32,
     register_count: u8,
+    /// Match astral chars of the pattern as their two UTF-16 code units
+    code_units: bool,
 }
 
 impl CodeGenerator {
@@ -18,9 +20,17 @@ impl CodeGenerator {
             flags,
             capture_count,
             register_count: 0,
+            code_units: false,
         }
     }
 
+    /// Emit astral chars in literals and classes as the surrogate code units
+    /// they are written with, for non-u searches over UTF-16 text.
+    pub fn code_units(mut self) -> Self {
+        self.code_units = true;
+        self
+    }
+
     pub fn compile(&mut self, ast: &Node) -> Result<()> {
         // Emit prefix for non-sticky patterns: try matching at each position
         if !self.flags.contains(Flags::STICKY) {
@@ -89,6 +99,12 @@ impl CodeGenerator {
 
     fn compile_literal(&mut self, c: char) -> Result<()> {
         let code = c as u32;
+        if self.code_units && code > 0xFFFF {
+            let (lead, trail) = surrogates(code);
+            self.builder.emit_op_u16(OpCode::Char, lead as u16);
+            self.builder.emit_op_u16(OpCode::Char, trail as u16);
+            return Ok(());
+        }
         if code <= 0xFFFF {
             if self.flags.contains(Flags::IGNORE_CASE) {
                 self.builder.emit_op_u16(OpCode::CharI, code as u16);
@@ -141,6 +157,9 @@ impl CodeGenerator {
         if has_builtins {
             return self.compile_class_with_builtins(ranges, negated);
         }
+        if self.code_units {
+            pairs = code_unit_pairs(&pairs);
+        }
 
         if negated {
             // Negate the ranges: compute complement over [0, 0x10FFFF]
@@ -242,7 +261,15 @@ impl CodeGenerator {
             ClassRange::Range(lo, hi) => {
                 let lo = *lo as u32;
                 let hi = *hi as u32;
-                if lo <= 0xFFFF && hi <= 0xFFFF {
+                if self.code_units && hi > 0xFFFF {
+                    let pairs = code_unit_pairs(&[(lo, hi)]);
+                    self.builder.emit_op(OpCode::Range);
+                    self.builder.push_u16(pairs.len() as u16);
+                    for (lo, hi) in pairs {
+                        self.builder.push_u16(lo as u16);
+                        self.builder.push_u16(hi as u16);
+                    }
+                } else if lo <= 0xFFFF && hi <= 0xFFFF {
                     self.builder.emit_op(OpCode::Range);
                     self.builder.push_u16(1);
                     self.builder.push_u16(lo as u16);
@@ -428,6 +455,7 @@ impl CodeGenerator {
     /// Count the number of fixed-width characters in a node (for lookbehind).
     fn count_fixed_chars(&self, node: &Node) -> u32 {
         match node {
+            Node::Literal(c) if self.code_units && *c > '\u{FFFF}' => 2,
             Node::Literal(_) | Node::Dot | Node::Builtin(_) => 1,
             Node::Class { .. } => 1,
             Node::Concat(nodes) => nodes.iter().map(|n| self.count_fixed_chars(n)).sum(),
@@ -755,3 +783,36 @@ impl CodeGenerator {
         Ok(())
     }
 }
+
+/// Lead and trail surrogate of an astral code point.
+fn surrogates(c: u32) -> (u32, u32) {
+    let c = c - 0x10000;
+    (0xD800 + (c >> 10), 0xDC00 + (c & 0x3FF))
+}
+
+/// Class ranges with their astral part replaced by surrogate code units:
+/// the lead surrogates of the range, and the trail surrogates its ends
+/// can have. Sorted, with overlaps merged.
+fn code_unit_pairs(pairs: &[(u32, u32)]) -> Vec<(u32, u32)> {
+    let mut units = Vec::with_capacity(pairs.len());
+    for &(lo, hi) in pairs {
+        if lo <= 0xFFFF {
+            units.push((lo, hi.min(0xFFFF)));
+        }
+        if hi > 0xFFFF {
+            let (first_lead, first_trail) = surrogates(lo.max(0x10000));
+            let (last_lead, last_trail) = surrogates(hi);
+            units.push((first_lead, last_lead));
+            units.push(if first_lead == last_lead { (first_trail, last_trail) } else { (0xDC00, 0xDFFF) });
+        }
+    }
+    units.sort_unstable();
+    let mut merged: Vec<(u32, u32)> = Vec::with_capacity(units.len());
+    for (lo, hi) in units {
+        match merged.last_mut() {
+            Some(last) if lo <= last.1 + 1 => last.1 = last.1.max(hi),
+            _ => merged.push((lo, hi)),
+        }
+    }
+    merged
+}
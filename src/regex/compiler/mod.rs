@@ -39,6 +39,17 @@ pub type Result<T> = std::result::Result<T, CompilerError>;
 
 /// Compile a regex pattern to bytecode (pure Rust, full JS syntax)
 pub fn compile_regex(pattern: &str, flags: Flags) -> Result<Vec<u8>> {
+    compile(pattern, flags, false)
+}
+
+/// Compile a pattern for non-unicode searches over UTF-16 text, where every
+/// surrogate is a char of its own: astral chars in the pattern match as the
+/// two code units they are written with.
+pub fn compile_code_units(pattern: &str, flags: Flags) -> Result<Vec<u8>> {
+    compile(pattern, flags, true)
+}
+
+fn compile(pattern: &str, flags: Flags, code_units: bool) -> Result<Vec<u8>> {
     // Detect inline flags (?i), (?m), (?s) anywhere in pattern and promote to global flags.
     // This handles PCRE-style patterns like "(?i)foo|(?i)bar" where each branch uses (?i).
     let mut final_flags = flags;
@@ -49,10 +60,83 @@ pub fn compile_regex(pattern: &str, flags: Flags) -> Result<Vec<u8>> {
     let ast = parser::parse(pattern, final_flags)?;
     let capture_count = parser::count_captures(pattern, final_flags)?;
     let mut codegen = CodeGenerator::new(final_flags, capture_count);
+    if code_units {
+        codegen = codegen.code_units();
+    }
     codegen.compile(&ast)?;
     Ok(codegen.into_bytecode())
 }
 
+/// A pattern compiled to run over reversed input.
+pub struct Reversed {
+    /// Bytecode of the reversed pattern, without captures
+    pub bytecode: Vec<u8>,
+    /// Literal text every match of the pattern ends with (may be empty)
+    pub suffix: String,
+}
+
+/// Compile the pattern read backwards, for finding matches by scanning the
+/// input from its end. Returns None for patterns that look around or refer
+/// back, which can't be reversed.
+pub fn compile_reversed(pattern: &str, flags: Flags) -> Result<Option<Reversed>> {
+    let mut final_flags = flags;
+    if pattern.contains("(?i") { final_flags.insert(Flags::IGNORE_CASE); }
+    if pattern.contains("(?m") { final_flags.insert(Flags::MULTILINE); }
+    if pattern.contains("(?s") { final_flags.insert(Flags::DOT_ALL); }
+    // The scan tries every position of the reversed input
+    final_flags.remove(Flags::STICKY);
+
+    let ast = parser::parse(pattern, final_flags)?;
+    let Some(reversed) = reverse_node(&ast) else { return Ok(None) };
+    let mut codegen = CodeGenerator::new(final_flags, 0);
+    codegen.compile(&reversed)?;
+    let suffix = if final_flags.is_ignore_case() { String::new() } else { literal_suffix(&ast) };
+    Ok(Some(Reversed { bytecode: codegen.into_bytecode(), suffix }))
+}
+
+/// Literal chars at the end of every match of `node`, skipping zero-width
+/// assertions between them.
+fn literal_suffix(node: &parser::Node) -> String {
+    use parser::Node;
+    match node {
+        Node::Literal(c) => c.to_string(),
+        Node::Capture { sub, .. } | Node::Group(sub) => literal_suffix(sub),
+        Node::Concat(nodes) => {
+            let mut suffix: Vec<char> = nodes.iter().rev()
+                .filter(|n| !matches!(n, Node::Anchor(_) | Node::WordBoundary { .. }))
+                .map_while(|n| match n {
+                    Node::Literal(c) => Some(*c),
+                    _ => None,
+                })
+                .collect();
+            suffix.reverse();
+            suffix.into_iter().collect()
+        }
+        _ => String::new(),
+    }
+}
+
+/// The node matching the reversed strings of `node`: concatenations run
+/// backwards and `^` and `$` trade places.
+fn reverse_node(node: &parser::Node) -> Option<parser::Node> {
+    use parser::{AnchorKind, Node};
+    Some(match node {
+        Node::Anchor(AnchorKind::Start) => Node::Anchor(AnchorKind::End),
+        Node::Anchor(AnchorKind::End) => Node::Anchor(AnchorKind::Start),
+        Node::BackRef(_) | Node::Lookahead { .. } | Node::Lookbehind { .. } => return None,
+        Node::Capture { sub, .. } | Node::Group(sub) => Node::Group(Box::new(reverse_node(sub)?)),
+        Node::Repeat { sub, min, max, greedy } => Node::Repeat {
+            sub: Box::new(reverse_node(sub)?),
+            min: *min,
+            max: *max,
+            greedy: *greedy,
+        },
+        Node::Concat(nodes) => Node::Concat(nodes.iter().rev().map(reverse_node).collect::<Option<_>>()?),
+        Node::Alternation(alts) => Node::Alternation(alts.iter().map(reverse_node).collect::<Option<_>>()?),
+        _ => node.clone(),
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -23,10 +23,11 @@ fn lre_check_stack_overflow(_opaque: *mut std::ffi::c_void, _alloca_size: size_t
     0 // No stack overflow check needed - Rust handles this
 }
 
-/// Check for execution timeout. Returns 0 (no timeout) by default.
+/// Check for execution timeout. Returns 1 once the current search's
+/// `Interrupt` has fired.
 #[inline]
 fn lre_check_timeout(_opaque: *mut std::ffi::c_void) -> i32 {
-    0 // No timeout by default
+    super::interrupt::should_stop() as i32
 }
 
 /// Reallocate memory. Uses libc realloc.
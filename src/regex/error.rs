@@ -2,7 +2,9 @@
 
 use std::fmt;
 
-/// Error during regex compilation
+use super::Engine;
+
+/// Error during regex compilation or engine selection
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Error {
     /// Invalid pattern syntax
@@ -31,6 +33,13 @@ pub enum Error {
     OutOfMemory,
     /// Internal error (should not happen)
     Internal(String),
+    /// The requested engine cannot run this pattern or search
+    UnsupportedEngine(Engine),
+    /// Data passed to `Regex::from_bytes` is not a regex saved by `to_bytes`
+    /// of this format version
+    Deserialize(String),
+    /// Bytecode loaded by `Regex::from_bytes` failed verification
+    InvalidBytecode(BytecodeError),
 }
 
 impl fmt::Display for Error {
@@ -49,6 +58,9 @@ impl fmt::Display for Error {
             Error::InvalidUnicodeProperty(msg) => write!(f, "invalid unicode property: {}", msg),
             Error::OutOfMemory => write!(f, "out of memory"),
             Error::Internal(msg) => write!(f, "internal error: {}", msg),
+            Error::UnsupportedEngine(engine) => write!(f, "engine {:?} does not support this search", engine),
+            Error::Deserialize(msg) => write!(f, "invalid serialized regex: {}", msg),
+            Error::InvalidBytecode(err) => write!(f, "invalid bytecode: {}", err),
         }
     }
 }
@@ -58,6 +70,89 @@ impl std::error::Error for Error {}
 /// Result type for regex operations
 pub type Result<T> = std::result::Result<T, Error>;
 
+/// Why a fallible search (`try_find` and friends) gave up without
+/// deciding whether there is a match
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MatchError {
+    /// The backtracking interpreter used up its step budget in the attempt
+    /// starting at `offset`
+    StepLimit { offset: usize },
+    /// The backtracking stack outgrew its limit in the attempt starting at
+    /// `offset`
+    MemoryLimit { offset: usize },
+    /// The search's `Interrupt` fired (deadline passed, cancel flag set or
+    /// poll callback returned true)
+    Timeout,
+}
+
+impl fmt::Display for MatchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MatchError::StepLimit { offset } => write!(f, "backtrack limit exceeded at offset {}", offset),
+            MatchError::MemoryLimit { offset } => write!(f, "backtrack stack limit exceeded at offset {}", offset),
+            MatchError::Timeout => write!(f, "search interrupted"),
+        }
+    }
+}
+
+impl std::error::Error for MatchError {}
+
+/// Why bytecode was rejected by the verifier. `pc` is the offset of the
+/// offending instruction from the start of the bytecode, header included.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BytecodeError {
+    /// Shorter than the header, or the header's body length disagrees with
+    /// the bytecode length
+    Length { len: usize },
+    /// Flag bits that no flag uses
+    UnknownFlags { bits: u16 },
+    /// No capture slots for group 0, which every match saves
+    NoCaptures,
+    /// Not an opcode this engine knows
+    UnknownOpcode { pc: usize, opcode: u8 },
+    /// The instruction runs past the end of the body
+    Truncated { pc: usize },
+    /// A jump lands outside the body, inside an instruction, or across a
+    /// lookaround boundary
+    JumpTarget { pc: usize, target: i64 },
+    /// A capture group at or past the header's capture count
+    CaptureIndex { pc: usize, index: u8 },
+    /// A register at or past the header's register count
+    RegisterIndex { pc: usize, index: u8 },
+    /// A lookaround body that overlaps another or doesn't end with its
+    /// own match instruction, or a lookaround match outside one
+    Lookaround { pc: usize },
+    /// An operand out of its range (an empty back reference, a span with
+    /// `min > max`)
+    Operand { pc: usize },
+    /// Execution can run past the last instruction
+    FallsOffEnd { pc: usize },
+    /// A cycle of instructions that neither consume input nor count down,
+    /// so a search can go round it forever
+    EmptyLoop { pc: usize },
+}
+
+impl fmt::Display for BytecodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BytecodeError::Length { len } => write!(f, "length {} does not match the header", len),
+            BytecodeError::UnknownFlags { bits } => write!(f, "unknown flag bits {:#x}", bits),
+            BytecodeError::NoCaptures => write!(f, "capture count is zero"),
+            BytecodeError::UnknownOpcode { pc, opcode } => write!(f, "unknown opcode {} at {}", opcode, pc),
+            BytecodeError::Truncated { pc } => write!(f, "truncated instruction at {}", pc),
+            BytecodeError::JumpTarget { pc, target } => write!(f, "bad jump target {} at {}", target, pc),
+            BytecodeError::CaptureIndex { pc, index } => write!(f, "capture group {} out of range at {}", index, pc),
+            BytecodeError::RegisterIndex { pc, index } => write!(f, "register {} out of range at {}", index, pc),
+            BytecodeError::Lookaround { pc } => write!(f, "badly nested lookaround at {}", pc),
+            BytecodeError::Operand { pc } => write!(f, "operand out of range at {}", pc),
+            BytecodeError::FallsOffEnd { pc } => write!(f, "execution runs past the end after {}", pc),
+            BytecodeError::EmptyLoop { pc } => write!(f, "loop that can run without advancing at {}", pc),
+        }
+    }
+}
+
+impl std::error::Error for BytecodeError {}
+
 /// Execution result codes
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ExecResult {
@@ -99,6 +194,13 @@ mod tests {
         assert_eq!(err.to_string(), "syntax error: unexpected token");
     }
 
+    #[test]
+    fn test_match_error_display() {
+        let err = MatchError::StepLimit { offset: 3 };
+        assert_eq!(err.to_string(), "backtrack limit exceeded at offset 3");
+        assert_eq!(MatchError::Timeout.to_string(), "search interrupted");
+    }
+
     #[test]
     fn test_exec_result() {
         assert!(ExecResult::Match.is_match());
@@ -0,0 +1,228 @@
+//! Running a search on one specific engine.
+//!
+//! `find` and `captures` pick an engine (and prefilters) from the pattern.
+//! The `*_with_engine` methods instead run the whole search on the engine
+//! asked for, with no prefilters or fallbacks, so disagreements between
+//! engines can be reproduced directly.
+
+use super::{bitvm, engine, interpreter, pikevm, utf8_char_len, Captures, Error, Match, Regex, Result};
+
+/// An execution engine that can be forced with `Regex::find_with_engine`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Engine {
+    /// Backtracking interpreter; runs every pattern
+    Backtracking,
+    /// Pike VM (thread lists, linear time); no backreferences or lookaround
+    PikeVm,
+    /// Bit-parallel VM; finds only where a match ends (the longest match
+    /// from the leftmost start, not the JS one) and ignores assertions.
+    /// No backreferences or lookaround
+    BitVm,
+    /// Lazy DFA for the match end, then a bounded Pike VM run for the
+    /// start and groups; no backreferences or lookaround
+    LazyDfa,
+    /// The transpiled QuickJS C engine; treats the haystack as one byte per
+    /// char and does not know the fused span instructions
+    CEngine,
+}
+
+impl Engine {
+    /// Every engine, for looping over them in differential tests.
+    pub const ALL: [Engine; 5] = [
+        Engine::Backtracking,
+        Engine::PikeVm,
+        Engine::BitVm,
+        Engine::LazyDfa,
+        Engine::CEngine,
+    ];
+}
+
+impl Regex {
+    /// Check if `engine` can run this pattern.
+    pub fn supports_engine(&self, engine: Engine) -> bool {
+        let bytecode = self.bytecode_slice();
+        match engine {
+            Engine::Backtracking => true,
+            Engine::PikeVm | Engine::LazyDfa => !has_opcode(bytecode, is_backtracking_only),
+            Engine::BitVm => self.bit_vm_match_end(&[], 0).is_some(),
+            Engine::CEngine => !has_opcode(bytecode, |op| op > 44),
+        }
+    }
+
+    /// Find the first match at or after `start` using only `engine`.
+    ///
+    /// For `Engine::BitVm` only `end` is computed; `start` of the returned
+    /// match is the search start.
+    pub fn find_with_engine(&self, text: &str, start: usize, engine: Engine) -> Result<Option<Match>> {
+        if !self.supports_engine(engine) {
+            return Err(Error::UnsupportedEngine(engine));
+        }
+        if start > text.len() {
+            return Ok(None);
+        }
+        if engine == Engine::BitVm {
+            let end = self.bit_vm_match_end(text.as_bytes(), start).flatten();
+            return Ok(end.map(|end| Match { start, end }));
+        }
+        Ok(self.slots_with_engine(text.as_bytes(), start, engine).and_then(|slots| {
+            match (slots.first().copied().flatten(), slots.get(1).copied().flatten()) {
+                (Some(start), Some(end)) => Some(Match { start, end }),
+                _ => None,
+            }
+        }))
+    }
+
+    /// Captures of the first match at or after `start` using only `engine`.
+    /// Not available for `Engine::BitVm`, which does not track groups.
+    pub fn captures_with_engine<'t>(&self, text: &'t str, start: usize, engine: Engine) -> Result<Option<Captures<'t>>> {
+        if engine == Engine::BitVm || !self.supports_engine(engine) {
+            return Err(Error::UnsupportedEngine(engine));
+        }
+        if start > text.len() {
+            return Ok(None);
+        }
+        Ok(self.slots_with_engine(text.as_bytes(), start, engine).map(|slots| {
+            let groups = slots.chunks(2)
+                .map(|pair| match pair {
+                    [Some(s), Some(e)] => Some((*s, *e)),
+                    _ => None,
+                })
+                .collect();
+            self.make_captures(text, groups)
+        }))
+    }
+
+    /// Run the bit VM, compiling it if the regex has none. Returns None if
+    /// the pattern cannot be compiled for it.
+    fn bit_vm_match_end(&self, text: &[u8], start: usize) -> Option<Option<usize>> {
+        if let Some(ref prog) = self.bit_program {
+            return Some(prog.find_match_end(text, start));
+        }
+        let bytecode = self.bytecode_slice();
+        if has_opcode(bytecode, is_backtracking_only) {
+            return None;
+        }
+        bitvm::BitVmProgram::compile(bytecode).map(|prog| prog.find_match_end(text, start))
+    }
+
+    /// Capture slots (start/end pairs) of the first match, from one engine.
+    fn slots_with_engine(&self, text: &[u8], start: usize, engine: Engine) -> Option<Vec<Option<usize>>> {
+        let bytecode = self.bytecode_slice();
+        match engine {
+            Engine::Backtracking => {
+                let mut ctx = self.exec_context(bytecode, text, Default::default());
+                let mut pos = start;
+                loop {
+                    if let interpreter::ExecResult::Match = ctx.exec(pos) {
+                        return Some(ctx.captures.clone());
+                    }
+                    if pos >= text.len() {
+                        return None;
+                    }
+                    pos += utf8_char_len(text, pos);
+                    ctx.reset();
+                }
+            }
+            Engine::PikeVm => match pikevm::PikeVm::new(bytecode, text).exec(start) {
+                pikevm::PikeResult::Match(caps) => Some(caps),
+                pikevm::PikeResult::NoMatch => None,
+            },
+            Engine::LazyDfa => self.pike_scanner(text).captures_next(start),
+            Engine::CEngine => {
+                let mut captures: Vec<*mut u8> = vec![std::ptr::null_mut(); self.capture_count() * 2];
+                let ret = engine::lre_exec(
+                    captures.as_mut_ptr(),
+                    self.bytecode,
+                    text.as_ptr(),
+                    start as i32,
+                    text.len() as i32,
+                    0,
+                    std::ptr::null_mut(),
+                );
+                (ret == 1).then(|| {
+                    captures.iter()
+                        .map(|&p| (!p.is_null()).then(|| unsafe { p.offset_from(text.as_ptr()) as usize }))
+                        .collect()
+                })
+            }
+            Engine::BitVm => None,
+        }
+    }
+}
+
+/// Backreferences and lookaround, which only the backtracking engines run.
+fn is_backtracking_only(op: u8) -> bool {
+    matches!(op, 32..=35 | 40 | 41)
+}
+
+/// Check if any instruction in the bytecode body has an opcode matching `pred`.
+fn has_opcode(bytecode: &[u8], pred: impl Fn(u8) -> bool) -> bool {
+    let end = Regex::HEADER_LEN + u32::from_le_bytes([bytecode[4], bytecode[5], bytecode[6], bytecode[7]]) as usize;
+    let mut pc = Regex::HEADER_LEN;
+    while pc < end && pc < bytecode.len() {
+        if pred(bytecode[pc]) {
+            return true;
+        }
+        pc += bitvm::instruction_size(bytecode, pc);
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Engine, Error, Match, Regex};
+
+    #[test]
+    fn test_engines_agree() {
+        let cases = [
+            (r"\d+", "ab 123 cd"),
+            (r"(\w+)@(\w+)", "mail: joe@host!"),
+            (r"a|ab", "xab"),
+            (r"(a)|(b)", "cb"),
+            ("x", "yyy"),
+        ];
+        for (pattern, text) in cases {
+            let re = Regex::new(pattern).unwrap();
+            let expected = re.captures(text);
+            for engine in Engine::ALL {
+                if !re.supports_engine(engine) {
+                    continue;
+                }
+                let found = re.find_with_engine(text, 0, engine).unwrap();
+                let expected_match = expected.as_ref().and_then(|c| c.get(0));
+                if engine == Engine::BitVm {
+                    assert_eq!(found.is_some(), expected_match.is_some(), "{} {:?}", pattern, engine);
+                    continue;
+                }
+                assert_eq!(found, expected_match, "{} {:?}", pattern, engine);
+                let caps = re.captures_with_engine(text, 0, engine).unwrap();
+                assert_eq!(caps.map(|c| c.iter().collect::<Vec<_>>()),
+                           expected.as_ref().map(|c| c.iter().collect::<Vec<_>>()), "{} {:?}", pattern, engine);
+            }
+        }
+    }
+
+    #[test]
+    fn test_unsupported_engines() {
+        let re = Regex::new(r"(a)\1").unwrap();
+        assert!(re.supports_engine(Engine::Backtracking) && re.supports_engine(Engine::CEngine));
+        for engine in [Engine::PikeVm, Engine::BitVm, Engine::LazyDfa] {
+            assert!(!re.supports_engine(engine));
+            assert_eq!(re.find_with_engine("aa", 0, engine), Err(Error::UnsupportedEngine(engine)));
+        }
+        assert_eq!(re.find_with_engine("xaa", 0, Engine::Backtracking), Ok(Some(Match { start: 1, end: 3 })));
+        assert_eq!(re.find_with_engine("xaa", 0, Engine::CEngine), Ok(Some(Match { start: 1, end: 3 })));
+
+        let re = Regex::new("a.{0,3}b").unwrap();
+        assert!(re.captures_with_engine("ab", 0, Engine::BitVm).is_err());
+    }
+
+    #[test]
+    fn test_start_offsets() {
+        let re = Regex::new("a").unwrap();
+        for engine in [Engine::Backtracking, Engine::PikeVm, Engine::LazyDfa] {
+            assert_eq!(re.find_with_engine("aba", 1, engine), Ok(Some(Match { start: 2, end: 3 })));
+            assert_eq!(re.find_with_engine("aba", 4, engine), Ok(None));
+        }
+    }
+}
@@ -0,0 +1,126 @@
+//! Searches that report when the backtracking interpreter gave up.
+//!
+//! When an attempt runs into the backtrack step limit or the stack limit,
+//! the interpreter reports no match at that position and the search moves
+//! on. `find` and friends therefore can't tell a real non-match from an
+//! undecided one. The `try_*` methods run the same search but return a
+//! `MatchError` if any attempt before the result gave up, since the true
+//! leftmost match may have been at that position.
+//!
+//! Attempts record that they gave up in a thread-local flag, which each
+//! `try_*` call clears before searching and checks afterwards. Interrupted
+//! searches (see `interrupt`) are recorded the same way.
+
+use std::cell::Cell;
+
+use super::{interpreter, Captures, Match, MatchError, Regex};
+
+thread_local! {
+    /// First attempt that gave up during the current search
+    static GAVE_UP: Cell<Option<MatchError>> = const { Cell::new(None) };
+}
+
+/// Record that the attempt at `offset` gave up on `limit`, if it did.
+pub(crate) fn note_limit(limit: Option<interpreter::Limit>, offset: usize) {
+    let Some(limit) = limit else { return };
+    let err = match limit {
+        interpreter::Limit::Steps => MatchError::StepLimit { offset },
+        interpreter::Limit::Stack => MatchError::MemoryLimit { offset },
+        interpreter::Limit::Interrupted => MatchError::Timeout,
+    };
+    note(err);
+}
+
+/// Record that the current search gave up with `err`, unless it already did.
+pub(crate) fn note(err: MatchError) {
+    GAVE_UP.with(|cell| {
+        if cell.get().is_none() {
+            cell.set(Some(err));
+        }
+    });
+}
+
+/// Run a search, failing if any attempt in it gave up.
+pub(crate) fn guarded<T>(search: impl FnOnce() -> T) -> Result<T, MatchError> {
+    let outer = GAVE_UP.with(|cell| cell.take());
+    let result = search();
+    let gave_up = GAVE_UP.with(|cell| cell.replace(outer));
+    match gave_up {
+        Some(err) => Err(err),
+        None => Ok(result),
+    }
+}
+
+impl Regex {
+    /// Like `is_match`, but fails instead of reporting no match when the
+    /// search gave up.
+    pub fn try_is_match(&self, text: &str) -> Result<bool, MatchError> {
+        guarded(|| self.is_match(text))
+    }
+
+    /// Like `find`, but fails instead of guessing when the search gave up.
+    pub fn try_find(&self, text: &str) -> Result<Option<Match>, MatchError> {
+        self.try_find_at(text, 0)
+    }
+
+    /// Like `find_at`, but fails instead of guessing when the search gave up.
+    pub fn try_find_at(&self, text: &str, start: usize) -> Result<Option<Match>, MatchError> {
+        guarded(|| self.find_at(text, start))
+    }
+
+    /// Like `captures`, but fails instead of guessing when the search gave up.
+    pub fn try_captures<'t>(&self, text: &'t str) -> Result<Option<Captures<'t>>, MatchError> {
+        self.try_captures_at(text, 0)
+    }
+
+    /// Like `captures_at`, but fails instead of guessing when the search
+    /// gave up.
+    pub fn try_captures_at<'t>(&self, text: &'t str, start: usize) -> Result<Option<Captures<'t>>, MatchError> {
+        guarded(|| self.captures_at(text, start))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{MatchError, Regex, RegexBuilder};
+
+    #[test]
+    fn test_step_limit_is_reported() {
+        let text = "a".repeat(400);
+        let pattern = r"^(\w+)\1$";
+        let limited = RegexBuilder::new(pattern).backtrack_limit(50).build().unwrap();
+        assert!(!limited.is_match(&text));
+        assert_eq!(limited.try_is_match(&text), Err(MatchError::StepLimit { offset: 0 }));
+        assert_eq!(limited.try_find(&text), Err(MatchError::StepLimit { offset: 0 }));
+        assert!(limited.try_captures(&text).is_err());
+        // Short inputs stay within the budget
+        assert_eq!(limited.try_find("aa").unwrap().map(|m| m.end), Some(2));
+        assert!(limited.try_find("ab").unwrap().is_none());
+    }
+
+    #[test]
+    fn test_later_match_after_giving_up_is_an_error() {
+        // The attempt at 0 gives up; the match at 401 must not be reported
+        let text = format!("{} bb", "a".repeat(400));
+        let limited = RegexBuilder::new(r"(\w+)\1\b").backtrack_limit(50).build().unwrap();
+        assert!(limited.find(&text).is_some());
+        assert_eq!(limited.try_find(&text), Err(MatchError::StepLimit { offset: 0 }));
+    }
+
+    #[test]
+    fn test_stack_limit_is_reported() {
+        let text = format!("{}c", "ab".repeat(100));
+        let limited = RegexBuilder::new(r"^(?:(a)|b)*\1d").backtrack_stack_limit(16).build().unwrap();
+        assert_eq!(limited.try_is_match(&text), Err(MatchError::MemoryLimit { offset: 0 }));
+        // Short inputs stay within the limit
+        assert_eq!(limited.try_find("abad"), Ok(None));
+    }
+
+    #[test]
+    fn test_default_limits_and_pike_vm_never_fail() {
+        let re = Regex::new(r"(a|b)*c").unwrap();
+        assert_eq!(re.try_find("ababx"), Ok(None));
+        let re = Regex::new(r"(a)\1").unwrap();
+        assert_eq!(re.try_find("xaa").unwrap().map(|m| m.start), Some(1));
+    }
+}
@@ -24,7 +24,7 @@ impl Flags {
     pub const UNICODE: u16 = 1 << 4;
     /// Sticky mode (y flag)
     pub const STICKY: u16 = 1 << 5;
-    /// Indices mode (d flag) - unused by engine, just recorded
+    /// Indices mode (d flag) - not used by the engine; fills in `Captures::indices`
     pub const INDICES: u16 = 1 << 6;
     /// Named groups are present
     pub const NAMED_GROUPS: u16 = 1 << 7;
@@ -91,6 +91,11 @@ impl Flags {
         self.contains(Self::STICKY)
     }
 
+    /// Check if indices flag is set
+    pub const fn has_indices(self) -> bool {
+        self.contains(Self::INDICES)
+    }
+
     /// Check if unicode sets flag is set
     pub const fn is_unicode_sets(self) -> bool {
         self.contains(Self::UNICODE_SETS)
@@ -126,8 +131,12 @@ impl Flags {
     }
 }
 
+/// Flags in JavaScript's canonical order (`dgimsuvy`).
 impl fmt::Display for Flags {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.has_indices() {
+            write!(f, "d")?;
+        }
         if self.is_global() {
             write!(f, "g")?;
         }
@@ -149,9 +158,6 @@ impl fmt::Display for Flags {
         if self.is_sticky() {
             write!(f, "y")?;
         }
-        if self.contains(Self::INDICES) {
-            write!(f, "d")?;
-        }
         Ok(())
     }
 }
@@ -215,6 +221,8 @@ mod tests {
     fn test_display() {
         let flags = Flags::parse("gim").unwrap();
         assert_eq!(flags.to_string(), "gim");
+        let flags = Flags::parse("yvdg").unwrap();
+        assert_eq!(flags.to_string(), "dgvy");
     }
 
     #[test]
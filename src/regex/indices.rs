@@ -0,0 +1,122 @@
+//! Match indices for the `d` (hasIndices) flag.
+//!
+//! With `d` set, a JavaScript match result carries an `indices` array of
+//! `[start, end]` pairs, one per group, and `indices.groups` for named
+//! groups. `MatchIndices` is that data, taken from the spans the engine
+//! already reported: byte offsets for `Captures`, code unit indices for
+//! `Utf16Captures`.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::{Captures, Match};
+
+/// The `indices` of a match made with the `d` flag.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MatchIndices {
+    /// Span of each group; None for groups that did not participate
+    spans: Vec<Option<Match>>,
+    /// Group names by group number, shared with the regex
+    names: Arc<[Option<String>]>,
+}
+
+impl MatchIndices {
+    pub(crate) fn new(groups: &[Option<(usize, usize)>], names: Arc<[Option<String>]>) -> Self {
+        let spans = groups.iter()
+            .map(|g| g.map(|(start, end)| Match { start, end }))
+            .collect();
+        MatchIndices { spans, names }
+    }
+
+    /// Number of entries (including group 0).
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Check if there are no entries (never true for a valid match).
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// `indices[i]`: span of group `i`, `None` if it did not participate.
+    pub fn get(&self, i: usize) -> Option<Match> {
+        self.spans.get(i).copied().flatten()
+    }
+
+    /// Iterate over `indices` in group order.
+    pub fn iter(&self) -> impl Iterator<Item = Option<Match>> + '_ {
+        self.spans.iter().copied()
+    }
+
+    /// `indices.groups[name]`: span of the named group that participated.
+    pub fn name(&self, name: &str) -> Option<Match> {
+        self.names.iter().enumerate()
+            .filter(|(_, n)| n.as_deref() == Some(name))
+            .find_map(|(i, _)| self.get(i))
+    }
+
+    /// `indices.groups`: spans by group name, or `None` if the pattern has
+    /// no named groups (where JavaScript gives `undefined`).
+    pub fn groups(&self) -> Option<HashMap<&str, Option<Match>>> {
+        if !self.names.iter().any(Option::is_some) {
+            return None;
+        }
+        let mut map = HashMap::new();
+        for (i, name) in self.names.iter().enumerate() {
+            if let Some(name) = name {
+                let entry = map.entry(name.as_str()).or_insert(None);
+                if let Some(m) = self.get(i) {
+                    *entry = Some(m);
+                }
+            }
+        }
+        Some(map)
+    }
+}
+
+impl Captures<'_> {
+    /// Match indices in byte offsets, or `None` unless the regex has the
+    /// `d` flag.
+    pub fn indices(&self) -> Option<MatchIndices> {
+        self.has_indices.then(|| MatchIndices::new(&self.groups, self.names.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Flags, JsRegExp, Match, Regex};
+
+    #[test]
+    fn test_indices_only_with_d_flag() {
+        let re = Regex::new("(a)").unwrap();
+        assert!(re.captures("xa").unwrap().indices().is_none());
+        let re = Regex::with_flags("(a)(b)?", Flags::from_bits(Flags::INDICES)).unwrap();
+        let indices = re.captures("xa").unwrap().indices().unwrap();
+        assert_eq!(indices.len(), 3);
+        assert_eq!(indices.get(0), Some(Match { start: 1, end: 2 }));
+        assert_eq!(indices.get(2), None);
+        assert!(indices.groups().is_none());
+    }
+
+    #[test]
+    fn test_named_group_indices() {
+        let mut re = JsRegExp::new(r"(?<word>\w+) (?<num>\d+)?", "dg").unwrap();
+        let text = "ab \u{e9} cd 42";
+        let indices = re.exec(text).unwrap().indices().unwrap();
+        assert_eq!(indices.name("word"), Some(Match { start: 0, end: 2 }));
+        let groups = indices.groups().unwrap();
+        assert_eq!(groups["num"], None);
+        let indices = re.exec(text).unwrap().indices().unwrap();
+        assert_eq!(indices.name("word"), Some(Match { start: 6, end: 8 }));
+        assert_eq!(indices.name("num"), Some(Match { start: 9, end: 11 }));
+    }
+
+    #[test]
+    fn test_utf16_indices() {
+        let text: Vec<u16> = "\u{1F600}ab".encode_utf16().collect();
+        let re = Regex::with_flags("(?<b>b)", Flags::parse("du").unwrap()).unwrap();
+        let indices = re.captures_at_utf16(&text, 0).unwrap().indices().unwrap();
+        assert_eq!(indices.get(0), Some(Match { start: 3, end: 4 }));
+        assert_eq!(indices.groups().unwrap()["b"], Some(Match { start: 3, end: 4 }));
+    }
+}
@@ -0,0 +1,233 @@
+//! Searches bounded to a span of the haystack.
+//!
+//! Slicing the haystack to bound a search makes `^`, `$`, `\b` and
+//! lookbehind see edges that aren't there. An `Input` keeps the whole
+//! haystack next to the span, and the engines only accept matches that end
+//! inside it, while assertions still look at the text around it. Unanchored
+//! searches run the usual `find_at` first; only when its match runs past
+//! the span is the search repeated from that match's start with the end
+//! bound in place.
+
+use std::ops::Range;
+
+use super::{fallible, interpreter, pikevm, utf8_char_len, Anchored, Captures, Match, Regex};
+
+/// A search over part of a haystack, with the anchoring it asks for.
+#[derive(Debug, Clone)]
+pub struct Input<'h> {
+    haystack: &'h str,
+    span: Range<usize>,
+    anchored: Anchored,
+}
+
+impl<'h> Input<'h> {
+    /// Search all of `haystack`, unanchored.
+    pub fn new(haystack: &'h str) -> Self {
+        Input { haystack, span: 0..haystack.len(), anchored: Anchored::No }
+    }
+
+    /// Only find matches inside `span`. Anchored searches start at its
+    /// start, and `Anchored::Both` ends at its end.
+    ///
+    /// Panics if `span` is not a char boundary range of the haystack.
+    pub fn span(mut self, span: Range<usize>) -> Self {
+        assert!(
+            span.start <= span.end && self.haystack.is_char_boundary(span.start) && self.haystack.is_char_boundary(span.end),
+            "invalid span {:?} for a haystack of length {}",
+            span,
+            self.haystack.len()
+        );
+        self.span = span;
+        self
+    }
+
+    /// Anchor matches as `anchored` asks, relative to the span.
+    pub fn anchored(mut self, anchored: Anchored) -> Self {
+        self.anchored = anchored;
+        self
+    }
+
+    /// The whole haystack, span or not.
+    pub fn haystack(&self) -> &'h str {
+        self.haystack
+    }
+
+    /// The span matches must lie in. Named `get_span` because `span` sets it.
+    pub fn get_span(&self) -> Range<usize> {
+        self.span.clone()
+    }
+
+    /// The anchoring asked for. Named `get_anchored` because `anchored` sets it.
+    pub fn get_anchored(&self) -> Anchored {
+        self.anchored
+    }
+}
+
+impl Regex {
+    /// Find the first match inside the span of `input`. Assertions see the
+    /// whole haystack.
+    ///
+    /// ```
+    /// use quickjs_regex::{Input, Regex};
+    ///
+    /// let re = Regex::new(r"\bfoo\b").unwrap();
+    /// assert_eq!(re.search(&Input::new("foobar").span(0..3)), None);
+    /// assert_eq!(re.find(&"foobar"[0..3]).map(|m| m.end), Some(3));
+    /// ```
+    pub fn search(&self, input: &Input<'_>) -> Option<Match> {
+        let (text, span) = (input.haystack.as_bytes(), input.get_span());
+        let mut start = span.start;
+        if input.anchored == Anchored::No {
+            // The usual search decides unless its match runs past the span
+            let m = self.find_at_bytes(text, start)?;
+            if m.end <= span.end {
+                return Some(m);
+            }
+            start = m.start;
+        }
+        let slots = self.span_slots(text, start..span.end, input.anchored)?;
+        Some(Match { start: slots[0]?, end: slots[1]? })
+    }
+
+    /// Like `search`, with capture groups.
+    pub fn search_captures<'h>(&self, input: &Input<'h>) -> Option<Captures<'h>> {
+        let (text, span) = (input.haystack.as_bytes(), input.get_span());
+        let mut start = span.start;
+        if input.anchored == Anchored::No {
+            let groups = self.captures_at_bytes(text, start)?;
+            let (m_start, m_end) = groups[0]?;
+            if m_end <= span.end {
+                return Some(self.make_captures(input.haystack, groups));
+            }
+            start = m_start;
+        }
+        let slots = self.span_slots(text, start..span.end, input.anchored)?;
+        Some(self.make_captures(input.haystack, groups(slots)))
+    }
+
+    /// Capture slots of the first match that starts and ends in `span`,
+    /// anchored as `anchored` asks.
+    pub(crate) fn span_slots(&self, text: &[u8], span: Range<usize>, anchored: Anchored) -> Option<Vec<Option<usize>>> {
+        if span.start > span.end || span.end > text.len() {
+            return None;
+        }
+        let ends = match anchored {
+            Anchored::Both => span.end..=span.end,
+            _ => span.start..=span.end,
+        };
+        let bytecode = self.bytecode_slice();
+        if self.use_pike_vm {
+            let mut vm = pikevm::PikeVm::new(bytecode, text).match_ends(ends);
+            if anchored != Anchored::No {
+                vm = vm.anchored();
+            }
+            return match vm.exec(span.start) {
+                pikevm::PikeResult::Match(caps) => Some(caps),
+                pikevm::PikeResult::NoMatch => None,
+            };
+        }
+        // One anchored run per start, so the backtracking limit applies to
+        // each start on its own
+        let single_start = anchored != Anchored::No || self.flags().is_sticky();
+        let mut ctx = self.exec_context(bytecode, text, Default::default());
+        ctx.set_anchored();
+        ctx.set_match_ends(ends);
+        let mut start = span.start;
+        loop {
+            ctx.reset();
+            match ctx.exec(start) {
+                interpreter::ExecResult::Match => return Some(ctx.captures.clone()),
+                interpreter::ExecResult::NoMatch => fallible::note_limit(ctx.limit_hit(), start),
+            }
+            if single_start || start >= span.end {
+                return None;
+            }
+            start += utf8_char_len(text, start);
+        }
+    }
+}
+
+/// Pair up capture slots into groups.
+pub(crate) fn groups(slots: Vec<Option<usize>>) -> Vec<Option<(usize, usize)>> {
+    slots.chunks(2)
+        .map(|pair| match pair {
+            [Some(s), Some(e)] => Some((*s, *e)),
+            _ => None,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Anchored, Flags, Input, Match, Regex, RegexBuilder};
+
+    fn both_engines(pattern: &str) -> [Regex; 2] {
+        [Regex::new(pattern).unwrap(), RegexBuilder::new(pattern).pike_vm(false).build().unwrap()]
+    }
+
+    fn search(re: &Regex, text: &str, span: std::ops::Range<usize>, anchored: Anchored) -> Option<(usize, usize)> {
+        re.search(&Input::new(text).span(span).anchored(anchored)).map(|m| (m.start, m.end))
+    }
+
+    #[test]
+    fn test_assertions_see_the_whole_haystack() {
+        let cases = [
+            (r"\bfoo\b", "foobar foo", 0..3, None),
+            (r"\bfoo\b", "foobar foo", 0..10, Some((7, 10))),
+            ("^b", "ab", 1..2, None),
+            ("a$", "ab", 0..1, None),
+            ("(?<=a)b", "ab", 1..2, Some((1, 2))),
+            (r"b(?=c)", "abc", 0..2, Some((1, 2))),
+        ];
+        for (pattern, text, span, expected) in cases {
+            for re in both_engines(pattern) {
+                assert_eq!(search(&re, text, span.clone(), Anchored::No), expected, "{} {:?}", pattern, span);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matches_stay_in_the_span() {
+        for re in both_engines(r"\d+") {
+            assert_eq!(search(&re, "12 345 6", 3..5, Anchored::No), Some((3, 5)));
+            assert_eq!(search(&re, "12 345 6", 1..5, Anchored::No), Some((1, 2)));
+            assert_eq!(search(&re, "12 345 6", 4..4, Anchored::No), None);
+        }
+        // The match found without the bound ends too late, a shorter one fits
+        for re in both_engines("ab|a") {
+            assert_eq!(search(&re, "xab", 0..2, Anchored::No), Some((1, 2)));
+        }
+        for re in both_engines(r"(\w)\w*\1") {
+            assert!(re.search_captures(&Input::new("abab cbc").span(0..2)).is_none());
+            let caps = re.search_captures(&Input::new("abab cbc").span(1..8)).unwrap();
+            assert_eq!((caps.get_str(0), caps.get_str(1)), (Some("bab"), Some("b")));
+        }
+    }
+
+    #[test]
+    fn test_anchored_spans() {
+        for re in both_engines("a+") {
+            assert_eq!(search(&re, "baaab", 1..3, Anchored::Start), Some((1, 3)));
+            assert_eq!(search(&re, "baaab", 0..3, Anchored::Start), None);
+            assert_eq!(search(&re, "baaab", 2..4, Anchored::Both), Some((2, 4)));
+            assert_eq!(search(&re, "baaab", 2..5, Anchored::Both), None);
+        }
+        let sticky = Regex::with_flags("b", Flags::parse("y").unwrap()).unwrap();
+        assert_eq!(search(&sticky, "ab", 0..2, Anchored::No), None);
+        assert_eq!(search(&sticky, "ab", 1..2, Anchored::No), Some((1, 2)));
+    }
+
+    #[test]
+    fn test_default_input_is_find() {
+        let re = Regex::new(r"\w+").unwrap();
+        let input = Input::new("  hi there");
+        assert_eq!(input.get_span(), 0..10);
+        assert_eq!(re.search(&input), Some(Match { start: 2, end: 4 }));
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid span")]
+    fn test_span_inside_a_char_panics() {
+        let _ = Input::new("\u{e9}").span(0..1);
+    }
+}
@@ -8,6 +8,10 @@
 //! - Bounds check elimination in release mode
 //! - Unicode-aware \w and \b when UNICODE flag is set
 
+use std::ops::RangeInclusive;
+
+use super::interrupt::Ticker;
+
 /// Check if a Unicode codepoint is ID_Continue (for \w and \b in Unicode mode).
 /// ID_Continue includes: letters, digits, underscore, combining marks, connector punctuation.
 #[inline(always)]
@@ -242,13 +246,35 @@ pub enum ExecResult {
     NoMatch,
 }
 
+/// Limit that made an `exec` give up (it then reports `NoMatch`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Limit {
+    /// More backtrack steps than the backtrack limit
+    Steps,
+    /// More stack frames than the stack limit
+    Stack,
+    /// The search's `Interrupt` fired
+    Interrupted,
+}
+
 // ============================================================================
 // Execution Context
 // ============================================================================
 
 /// Maximum number of backtrack steps before giving up (prevents exponential blowup).
 /// Set high enough for complex patterns on large haystacks, but prevents true catastrophic cases.
-const MAX_BACKTRACK_STEPS: usize = 5_000_000;
+pub(crate) const MAX_BACKTRACK_STEPS: usize = 5_000_000;
+
+/// Heap buffers of an `ExecContext`, kept between searches so repeated
+/// executions (on any input) don't reallocate.
+#[derive(Default)]
+pub struct ExecBuffers {
+    captures: Vec<Option<usize>>,
+    registers: Vec<usize>,
+    stack: Vec<StackFrame>,
+    capture_saves: Vec<(u32, Option<usize>)>,
+    register_saves: Vec<(u32, usize)>,
+}
 
 pub struct ExecContext<'a> {
     input: &'a [u8],
@@ -265,6 +291,12 @@ pub struct ExecContext<'a> {
     // Backtracking stack
     stack: Vec<StackFrame>,
     backtrack_count: usize,
+    backtrack_limit: usize,
+    stack_limit: usize,
+    /// Set when the current attempt ran into a limit
+    limit_hit: Option<Limit>,
+    /// Steps until the search's interrupt is consulted
+    ticker: Ticker,
 
     // Save stacks for backtracking (packed for cache efficiency)
     capture_saves: Vec<(u32, Option<usize>)>,  // u32 index is enough
@@ -272,11 +304,24 @@ pub struct ExecContext<'a> {
 
     // Unicode mode flag - affects \w and \b behavior
     unicode_mode: bool,
+
+    /// Where `exec` starts in the bytecode (past the unanchored prefix
+    /// when anchored)
+    start_pc: usize,
+    /// Positions a match may end at
+    match_ends: RangeInclusive<usize>,
 }
 
 impl<'a> ExecContext<'a> {
     #[inline]
     pub fn new(bytecode: &'a [u8], input: &'a [u8]) -> Self {
+        Self::with_buffers(bytecode, input, ExecBuffers::default())
+    }
+
+    /// Create a context that reuses the buffers of an earlier one.
+    #[inline]
+    pub fn with_buffers(bytecode: &'a [u8], input: &'a [u8], buffers: ExecBuffers) -> Self {
+        let ExecBuffers { mut captures, mut registers, mut stack, mut capture_saves, mut register_saves } = buffers;
         let capture_count = bytecode[RE_HEADER_CAPTURE_COUNT] as usize;
         let register_count = bytecode[RE_HEADER_REGISTER_COUNT] as usize;
 
@@ -288,18 +333,73 @@ impl<'a> ExecContext<'a> {
         let bc_len = bytecode.len();
         let estimated_stack = (bc_len / 8).max(32).min(256);
 
+        captures.clear();
+        captures.resize(capture_count * 2, None);
+        registers.clear();
+        registers.resize(register_count, 0);
+        stack.clear();
+        stack.reserve(estimated_stack);
+        capture_saves.clear();
+        capture_saves.reserve(estimated_stack);
+        register_saves.clear();
+        register_saves.reserve(estimated_stack / 2);
+
         ExecContext {
             input,
             input_len: input.len(),
             bytecode,
-            captures: vec![None; capture_count * 2],
+            captures,
             capture_count,
-            registers: vec![0; register_count],
-            stack: Vec::with_capacity(estimated_stack),
+            registers,
+            stack,
             backtrack_count: 0,
-            capture_saves: Vec::with_capacity(estimated_stack),
-            register_saves: Vec::with_capacity(estimated_stack / 2),
+            backtrack_limit: MAX_BACKTRACK_STEPS,
+            stack_limit: usize::MAX,
+            limit_hit: None,
+            ticker: Ticker::new(),
+            capture_saves,
+            register_saves,
             unicode_mode,
+            start_pc: RE_HEADER_LEN,
+            match_ends: 0..=usize::MAX,
+        }
+    }
+
+    /// Set the number of backtrack steps an `exec` may take before giving up.
+    pub fn set_backtrack_limit(&mut self, limit: usize) {
+        self.backtrack_limit = limit;
+    }
+
+    /// Set the number of backtrack stack frames an `exec` may hold. Past it
+    /// the attempt finishes its current path but no longer backtracks.
+    pub fn set_stack_limit(&mut self, limit: usize) {
+        self.stack_limit = limit;
+    }
+
+    /// Make `exec` try only its start position.
+    pub fn set_anchored(&mut self) {
+        self.start_pc = super::anchored::pattern_start(self.bytecode);
+    }
+
+    /// Only accept matches that end in `ends`; other paths are backtracked.
+    pub fn set_match_ends(&mut self, ends: RangeInclusive<usize>) {
+        self.match_ends = ends;
+    }
+
+    /// The limit the last `exec` gave up on, if any. A `NoMatch` with a
+    /// limit hit means the attempt was not decided.
+    pub fn limit_hit(&self) -> Option<Limit> {
+        self.limit_hit
+    }
+
+    /// Take back the buffers for reuse with `with_buffers`.
+    pub fn into_buffers(self) -> ExecBuffers {
+        ExecBuffers {
+            captures: self.captures,
+            registers: self.registers,
+            stack: self.stack,
+            capture_saves: self.capture_saves,
+            register_saves: self.register_saves,
         }
     }
 
@@ -310,6 +410,7 @@ impl<'a> ExecContext<'a> {
         self.registers.fill(0);
         self.stack.clear();
         self.backtrack_count = 0;
+        self.limit_hit = None;
         self.capture_saves.clear();
         self.register_saves.clear();
     }
@@ -367,12 +468,7 @@ impl<'a> ExecContext<'a> {
         if pos == 0 { return 0; }
         let b = self.input[pos - 1];
         if b < 0x80 { return 1; }
-        // Walk back through UTF-8 continuation bytes
-        let mut back = 1;
-        while back < 4 && pos > back && (self.input[pos - 1 - back] & 0xC0) == 0x80 {
-            back += 1;
-        }
-        back + 1
+        super::util::decode_utf8_before(self.input, pos, self.unicode_mode).1
     }
 
     fn next_char(&self, pos: usize) -> Option<(u32, usize)> {
@@ -394,37 +490,8 @@ impl<'a> ExecContext<'a> {
     #[cold]
     #[inline(never)]
     fn next_char_multibyte(&self, pos: usize, b0: u8) -> Option<(u32, usize)> {
-        // 2-byte
-        if b0 < 0xE0 {
-            if pos + 1 >= self.input_len {
-                return Some((b0 as u32, pos + 1));
-            }
-            let b1 = unsafe { *self.input.get_unchecked(pos + 1) };
-            let cp = ((b0 as u32 & 0x1F) << 6) | (b1 as u32 & 0x3F);
-            return Some((cp, pos + 2));
-        }
-
-        // 3-byte
-        if b0 < 0xF0 {
-            if pos + 2 >= self.input_len {
-                return Some((b0 as u32, pos + 1));
-            }
-            let b1 = unsafe { *self.input.get_unchecked(pos + 1) };
-            let b2 = unsafe { *self.input.get_unchecked(pos + 2) };
-            let cp = ((b0 as u32 & 0x0F) << 12) | ((b1 as u32 & 0x3F) << 6) | (b2 as u32 & 0x3F);
-            return Some((cp, pos + 3));
-        }
-
-        // 4-byte
-        if pos + 3 >= self.input_len {
-            return Some((b0 as u32, pos + 1));
-        }
-        let b1 = unsafe { *self.input.get_unchecked(pos + 1) };
-        let b2 = unsafe { *self.input.get_unchecked(pos + 2) };
-        let b3 = unsafe { *self.input.get_unchecked(pos + 3) };
-        let cp = ((b0 as u32 & 0x07) << 18) | ((b1 as u32 & 0x3F) << 12) |
-                 ((b2 as u32 & 0x3F) << 6) | (b3 as u32 & 0x3F);
-        Some((cp, pos + 4))
+        let (cp, len) = super::util::decode_utf8_at(self.input, pos, self.unicode_mode);
+        Some((cp, pos + len))
     }
 
     /// Get previous char (for lookbehind, word boundary)
@@ -446,12 +513,8 @@ impl<'a> ExecContext<'a> {
     #[cold]
     #[inline(never)]
     fn prev_char_multibyte(&self, pos: usize) -> Option<(u32, usize)> {
-        // Walk back through continuation bytes
-        let mut start = pos - 1;
-        while start > 0 && (unsafe { *self.input.get_unchecked(start) } & 0xC0) == 0x80 {
-            start -= 1;
-        }
-        self.next_char(start)
+        let (cp, len) = super::util::decode_utf8_before(self.input, pos, self.unicode_mode);
+        Some((cp, pos - len))
     }
 
     /// Move back one character position
@@ -467,11 +530,7 @@ impl<'a> ExecContext<'a> {
             return Some(pos - 1);
         }
 
-        let mut p = pos - 1;
-        while p > 0 && (unsafe { *self.input.get_unchecked(p) } & 0xC0) == 0x80 {
-            p -= 1;
-        }
-        Some(p)
+        Some(pos - super::util::decode_utf8_before(self.input, pos, self.unicode_mode).1)
     }
 
     // ========================================================================
@@ -480,6 +539,9 @@ impl<'a> ExecContext<'a> {
 
     #[inline(always)]
     fn push_state(&mut self, pc: usize, pos: usize, state_type: StateType) {
+        if self.stack.len() >= self.stack_limit {
+            self.limit_hit.get_or_insert(Limit::Stack);
+        }
         self.stack.push(StackFrame {
             pc,
             pos,
@@ -533,7 +595,13 @@ impl<'a> ExecContext<'a> {
     #[inline]
     fn backtrack(&mut self) -> Option<(usize, usize)> {
         self.backtrack_count += 1;
-        if self.backtrack_count > MAX_BACKTRACK_STEPS {
+        if self.backtrack_count > self.backtrack_limit {
+            self.limit_hit.get_or_insert(Limit::Steps);
+        }
+        if self.ticker.tick() {
+            self.limit_hit.get_or_insert(Limit::Interrupted);
+        }
+        if self.limit_hit.is_some() {
             self.stack.clear();
             return None;
         }
@@ -586,9 +654,14 @@ impl<'a> ExecContext<'a> {
     // ========================================================================
 
     pub fn exec(&mut self, start_pos: usize) -> ExecResult {
-        let mut pc = RE_HEADER_LEN;
+        let mut pc = self.start_pc;
         let mut pos = start_pos;
 
+        if super::interrupt::fired() {
+            self.limit_hit = Some(Limit::Interrupted);
+            return ExecResult::NoMatch;
+        }
+
         // Main dispatch loop
         loop {
             let opcode = self.read_u8(pc);
@@ -669,6 +742,12 @@ impl<'a> ExecContext<'a> {
                 // MATCH (16) - Success!
                 // ============================================================
                 op::MATCH => {
+                    if !self.match_ends.contains(&pos) {
+                        if let Some((p, s)) = self.backtrack() {
+                            pc = p; pos = s; continue;
+                        }
+                        return ExecResult::NoMatch;
+                    }
                     return ExecResult::Match;
                 }
 
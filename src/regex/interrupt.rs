@@ -0,0 +1,324 @@
+//! Deadlines and cancellation for searches.
+//!
+//! An `Interrupt` bundles a deadline, a cancel flag and a poll callback.
+//! While an interruptible search runs, it is installed in a thread-local
+//! slot. The backtracker, the Pike VM and the lazy DFA each count their
+//! steps with a `Ticker`, which looks at the slot once per search and then
+//! consults the interrupt every `CHECK_INTERVAL` steps. Once it has fired,
+//! every engine gives up at its next step (the backtracker already on
+//! entry, so the remaining positions are skipped cheaply) and the search
+//! reports `MatchError::Timeout`.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::{fallible, Captures, CapturesIterator, Match, MatchError, MatchIterator, Regex};
+
+/// Engine steps between two looks at the clock, flag and callback.
+pub(crate) const CHECK_INTERVAL: u32 = 1024;
+
+thread_local! {
+    /// Interrupt of the search running on this thread, if any
+    static ACTIVE: RefCell<Option<Interrupt>> = const { RefCell::new(None) };
+    /// Whether the active interrupt has fired
+    static FIRED: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Conditions that stop a search early: a deadline, a cancel flag and a
+/// poll callback. Any one of them firing stops the search.
+///
+/// ```
+/// use std::time::Duration;
+/// use quickjs_regex::{Interrupt, MatchError, Regex};
+///
+/// let re = Regex::new(r"^(a+)+\1b").unwrap();
+/// let text = "a".repeat(30);
+/// let interrupt = Interrupt::new().timeout(Duration::from_millis(10));
+/// assert_eq!(re.find_interruptible(&text, &interrupt), Err(MatchError::Timeout));
+/// ```
+#[derive(Clone, Default)]
+pub struct Interrupt {
+    deadline: Option<Instant>,
+    cancel: Option<Arc<AtomicBool>>,
+    poll: Option<Arc<dyn Fn() -> bool + Send + Sync>>,
+}
+
+impl Interrupt {
+    /// An interrupt that never fires until conditions are added.
+    pub fn new() -> Self {
+        Interrupt::default()
+    }
+
+    /// Stop searches once `deadline` has passed.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Stop searches `timeout` from now.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+
+    /// Stop searches once `flag` is set, e.g. from another thread.
+    pub fn cancel_flag(mut self, flag: Arc<AtomicBool>) -> Self {
+        self.cancel = Some(flag);
+        self
+    }
+
+    /// Stop searches once `poll` returns true. It is called every few
+    /// thousand engine steps, so it should be cheap.
+    pub fn poll(mut self, poll: impl Fn() -> bool + Send + Sync + 'static) -> Self {
+        self.poll = Some(Arc::new(poll));
+        self
+    }
+
+    /// Check if any of the conditions holds.
+    pub fn is_fired(&self) -> bool {
+        self.deadline.is_some_and(|deadline| Instant::now() >= deadline)
+            || self.cancel.as_ref().is_some_and(|flag| flag.load(Ordering::Relaxed))
+            || self.poll.as_ref().is_some_and(|poll| poll())
+    }
+}
+
+impl fmt::Debug for Interrupt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Interrupt")
+            .field("deadline", &self.deadline)
+            .field("cancel", &self.cancel)
+            .field("poll", &self.poll.is_some())
+            .finish()
+    }
+}
+
+/// Step counter for one search. Whether an interrupt is installed is read
+/// once, when the counter is made; without one, counting a step is a
+/// single branch.
+pub(crate) struct Ticker {
+    active: bool,
+    /// Steps left before the next check
+    left: u32,
+}
+
+impl Ticker {
+    pub(crate) fn new() -> Self {
+        let active = ACTIVE.with(|active| active.borrow().is_some());
+        // A fired interrupt stops the search at its first step
+        let left = if fired() { 1 } else { CHECK_INTERVAL };
+        Ticker { active, left }
+    }
+
+    /// Count one engine step. Returns true if the search must stop.
+    #[inline]
+    pub(crate) fn tick(&mut self) -> bool {
+        if !self.active {
+            return false;
+        }
+        self.left -= 1;
+        if self.left > 0 {
+            return false;
+        }
+        let stop = should_stop();
+        // Once fired, every step stops
+        self.left = if stop { 1 } else { CHECK_INTERVAL };
+        stop
+    }
+}
+
+/// Check the active interrupt now. Returns true if the search must stop.
+pub(crate) fn should_stop() -> bool {
+    let fired = FIRED.with(Cell::get)
+        || ACTIVE.with(|active| active.borrow().as_ref().is_some_and(Interrupt::is_fired));
+    if fired {
+        FIRED.with(|cell| cell.set(true));
+        fallible::note(MatchError::Timeout);
+    }
+    fired
+}
+
+/// Check if the active interrupt has fired, without consulting it again.
+#[inline]
+pub(crate) fn fired() -> bool {
+    FIRED.with(Cell::get)
+}
+
+/// Restores the outer search's interrupt when an interruptible search ends,
+/// even by panicking.
+struct Installed {
+    outer: Option<Interrupt>,
+    outer_fired: bool,
+}
+
+impl Drop for Installed {
+    fn drop(&mut self) {
+        ACTIVE.with(|active| *active.borrow_mut() = self.outer.take());
+        FIRED.with(|cell| cell.set(self.outer_fired));
+    }
+}
+
+/// Run a search with `interrupt` installed, failing if it fired or if any
+/// attempt gave up.
+fn interruptible<T>(interrupt: &Interrupt, search: impl FnOnce() -> T) -> Result<T, MatchError> {
+    if interrupt.is_fired() {
+        return Err(MatchError::Timeout);
+    }
+    let _installed = Installed {
+        outer: ACTIVE.with(|active| active.borrow_mut().replace(interrupt.clone())),
+        outer_fired: FIRED.with(|cell| cell.replace(false)),
+    };
+    fallible::guarded(search)
+}
+
+impl Regex {
+    /// Like `try_find`, but also stops with `MatchError::Timeout` once
+    /// `interrupt` fires.
+    pub fn find_interruptible(&self, text: &str, interrupt: &Interrupt) -> Result<Option<Match>, MatchError> {
+        self.find_at_interruptible(text, 0, interrupt)
+    }
+
+    /// Like `try_find_at`, but also stops with `MatchError::Timeout` once
+    /// `interrupt` fires.
+    pub fn find_at_interruptible(&self, text: &str, start: usize, interrupt: &Interrupt) -> Result<Option<Match>, MatchError> {
+        interruptible(interrupt, || self.find_at(text, start))
+    }
+
+    /// Like `try_captures`, but also stops with `MatchError::Timeout` once
+    /// `interrupt` fires.
+    pub fn captures_interruptible<'t>(&self, text: &'t str, interrupt: &Interrupt) -> Result<Option<Captures<'t>>, MatchError> {
+        self.captures_at_interruptible(text, 0, interrupt)
+    }
+
+    /// Like `try_captures_at`, but also stops with `MatchError::Timeout`
+    /// once `interrupt` fires.
+    pub fn captures_at_interruptible<'t>(&self, text: &'t str, start: usize, interrupt: &Interrupt) -> Result<Option<Captures<'t>>, MatchError> {
+        interruptible(interrupt, || self.captures_at(text, start))
+    }
+
+    /// Like `find_iter`, but each item is an error if the search for it was
+    /// interrupted or gave up. Iteration ends after the first error.
+    pub fn find_iter_interruptible<'r, 't>(&'r self, text: &'t str, interrupt: Interrupt) -> InterruptibleMatches<'r, 't> {
+        InterruptibleMatches { inner: Some(self.find_iter(text)), interrupt }
+    }
+
+    /// Like `captures_iter`, but each item is an error if the search for it
+    /// was interrupted or gave up. Iteration ends after the first error.
+    pub fn captures_iter_interruptible<'r, 't>(&'r self, text: &'t str, interrupt: Interrupt) -> InterruptibleCaptures<'r, 't> {
+        InterruptibleCaptures { inner: Some(self.captures_iter(text)), interrupt }
+    }
+}
+
+/// Iterator returned by `Regex::find_iter_interruptible`.
+pub struct InterruptibleMatches<'r, 't> {
+    inner: Option<MatchIterator<'r, 't>>,
+    interrupt: Interrupt,
+}
+
+impl<'r, 't> Iterator for InterruptibleMatches<'r, 't> {
+    type Item = Result<Match, MatchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inner = self.inner.as_mut()?;
+        let next = interruptible(&self.interrupt, || inner.next());
+        if next.is_err() {
+            self.inner = None;
+        }
+        next.transpose()
+    }
+}
+
+/// Iterator returned by `Regex::captures_iter_interruptible`.
+pub struct InterruptibleCaptures<'r, 't> {
+    inner: Option<CapturesIterator<'r, 't>>,
+    interrupt: Interrupt,
+}
+
+impl<'r, 't> Iterator for InterruptibleCaptures<'r, 't> {
+    type Item = Result<Captures<'t>, MatchError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let inner = self.inner.as_mut()?;
+        let next = interruptible(&self.interrupt, || inner.next());
+        if next.is_err() {
+            self.inner = None;
+        }
+        next.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::time::{Duration, Instant};
+
+    use super::CHECK_INTERVAL;
+    use crate::regex::{Interrupt, Match, MatchError, Regex, RegexBuilder};
+
+    #[test]
+    fn test_deadline_stops_backtracking() {
+        let re = Regex::new(r"^(a+)+\1b").unwrap();
+        let text = "a".repeat(30);
+        let started = Instant::now();
+        let interrupt = Interrupt::new().timeout(Duration::from_millis(20));
+        assert_eq!(re.find_interruptible(&text, &interrupt), Err(MatchError::Timeout));
+        assert_eq!(re.captures_interruptible(&text, &interrupt).map(|c| c.is_some()), Err(MatchError::Timeout));
+        assert!(started.elapsed() < Duration::from_secs(5));
+        // Later searches on the thread are not affected
+        assert_eq!(re.find("aab").map(|m| m.end), Some(3));
+    }
+
+    #[test]
+    fn test_cancel_flag_stops_pike_vm_and_dfa() {
+        // The match spans the haystack, so the engines scan all of it
+        let text = "ab".repeat(50_000) + "c";
+        assert!(text.len() > CHECK_INTERVAL as usize * 4);
+        for re in [
+            Regex::new(r"(a|b)*c").unwrap(),
+            RegexBuilder::new(r"(a|b)*c").prefilters(false).bit_vm(false).build().unwrap(),
+        ] {
+            assert!(re.use_pike_vm);
+            let flag = Arc::new(AtomicBool::new(false));
+            let found = re.find_interruptible(&text, &Interrupt::new().cancel_flag(flag.clone()));
+            assert_eq!(found, Ok(Some(Match { start: 0, end: text.len() })));
+            // Set the flag at the third check, well into the search; the
+            // fourth sees it without polling again
+            let checks = Arc::new(AtomicUsize::new(0));
+            let counter = checks.clone();
+            let interrupt = Interrupt::new().cancel_flag(flag.clone()).poll(move || {
+                if counter.fetch_add(1, Ordering::Relaxed) == 2 {
+                    flag.store(true, Ordering::Relaxed);
+                }
+                false
+            });
+            assert_eq!(re.find_interruptible(&text, &interrupt), Err(MatchError::Timeout));
+            assert_eq!(checks.load(Ordering::Relaxed), 3);
+        }
+    }
+
+    #[test]
+    fn test_poll_fires_mid_search() {
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = calls.clone();
+        let interrupt = Interrupt::new().poll(move || counter.fetch_add(1, Ordering::Relaxed) >= 2);
+        let re = RegexBuilder::new(r"(\w+)\1x").pike_vm(false).build().unwrap();
+        let text = "ab".repeat(2_000);
+        assert_eq!(re.find_interruptible(&text, &interrupt), Err(MatchError::Timeout));
+        assert!(calls.load(Ordering::Relaxed) >= 3);
+    }
+
+    #[test]
+    fn test_iterators_stop_after_error() {
+        let re = Regex::new(r"\d+").unwrap();
+        let text = "1 22 333";
+        let found: Vec<_> = re.find_iter_interruptible(text, Interrupt::new()).collect();
+        assert_eq!(found, re.find_iter(text).map(Ok).collect::<Vec<_>>());
+
+        let flag = Arc::new(AtomicBool::new(true));
+        let mut iter = re.captures_iter_interruptible(text, Interrupt::new().cancel_flag(flag));
+        assert_eq!(iter.next().map(|c| c.err()), Some(Some(MatchError::Timeout)));
+        assert!(iter.next().is_none());
+    }
+}
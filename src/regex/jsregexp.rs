@@ -0,0 +1,306 @@
+//! A stateful `RegExp` object with JavaScript `lastIndex` semantics.
+//!
+//! `exec` and `test` follow RegExpBuiltinExec: with the `g` or `y` flag the
+//! search starts at `lastIndex` and `lastIndex` is set to the end of the
+//! match, or reset to 0 when there is none; without either flag the search
+//! starts at 0 and `lastIndex` is left alone. A sticky regex only matches
+//! exactly at `lastIndex`.
+//!
+//! `lastIndex` is an offset in the units of the haystack: bytes for `&str`
+//! (`exec`, `test`) and code units for UTF-16 (`exec_utf16`, `test_utf16`).
+
+use super::{Captures, Error, Flags, Regex, Result, Utf16Captures};
+
+/// A compiled regex plus the mutable `lastIndex` of a JavaScript RegExp.
+#[derive(Debug)]
+pub struct JsRegExp {
+    regex: Regex,
+    last_index: usize,
+}
+
+impl JsRegExp {
+    /// Compile a pattern with a JavaScript flags string, like
+    /// `new RegExp(pattern, flags)`.
+    pub fn new(pattern: &str, flags: &str) -> Result<Self> {
+        let flags = Flags::parse(flags).map_err(|e| Error::InvalidFlag(e.0))?;
+        Ok(Self::from_regex(Regex::with_flags(pattern, flags)?))
+    }
+
+    /// Wrap an already compiled regex, with `lastIndex` 0.
+    pub fn from_regex(regex: Regex) -> Self {
+        JsRegExp { regex, last_index: 0 }
+    }
+
+    /// The underlying regex.
+    pub fn regex(&self) -> &Regex {
+        &self.regex
+    }
+
+    /// Current `lastIndex`.
+    pub fn last_index(&self) -> usize {
+        self.last_index
+    }
+
+    /// Set `lastIndex`.
+    pub fn set_last_index(&mut self, last_index: usize) {
+        self.last_index = last_index;
+    }
+
+    /// `RegExp.prototype.exec`: captures of the next match, updating `lastIndex`.
+    pub fn exec<'t>(&mut self, text: &'t str) -> Option<Captures<'t>> {
+        let start = self.search_start(text.len())?;
+        let start = floor_char_boundary(text, start);
+        let caps = self.regex.captures_at(text, start);
+        let span = caps.as_ref().and_then(|c| c.get(0)).map(|m| (m.start, m.end));
+        if self.finish(start, span) { caps } else { None }
+    }
+
+    /// `RegExp.prototype.test`: whether there is a next match, updating `lastIndex`.
+    pub fn test(&mut self, text: &str) -> bool {
+        let Some(start) = self.search_start(text.len()) else { return false };
+        let start = floor_char_boundary(text, start);
+        let span = self.regex.find_at(text, start).map(|m| (m.start, m.end));
+        self.finish(start, span)
+    }
+
+    /// `exec` on a UTF-16 haystack; `lastIndex` is in code units.
+    pub fn exec_utf16(&mut self, text: &[u16]) -> Option<Utf16Captures> {
+        let start = self.search_start(text.len())?;
+        let start = self.code_point_start(text, start);
+        let caps = self.regex.captures_at_utf16(text, start);
+        let span = caps.as_ref().and_then(|c| c.get(0)).map(|m| (m.start, m.end));
+        if self.finish(start, span) { caps } else { None }
+    }
+
+    /// `test` on a UTF-16 haystack; `lastIndex` is in code units.
+    pub fn test_utf16(&mut self, text: &[u16]) -> bool {
+        let Some(start) = self.search_start(text.len()) else { return false };
+        let start = self.code_point_start(text, start);
+        let span = self.regex.find_at_utf16(text, start).map(|m| (m.start, m.end));
+        self.finish(start, span)
+    }
+
+    /// Where the search starts, or None (after resetting `lastIndex`) if
+    /// `lastIndex` is past the end of the input.
+    fn search_start(&mut self, len: usize) -> Option<usize> {
+        if !self.updates_last_index() {
+            return Some(0);
+        }
+        if self.last_index > len {
+            self.last_index = 0;
+            return None;
+        }
+        Some(self.last_index)
+    }
+
+    /// Apply the search result to `lastIndex`. Returns whether it is a match:
+    /// a sticky regex rejects matches that do not begin at `start`.
+    fn finish(&mut self, start: usize, span: Option<(usize, usize)>) -> bool {
+        let span = span.filter(|&(s, _)| !self.sticky() || s == start);
+        if self.updates_last_index() {
+            self.last_index = span.map_or(0, |(_, e)| e);
+        }
+        span.is_some()
+    }
+
+    /// In u/v mode an index inside a surrogate pair refers to the whole
+    /// code point, so the match may begin at the lead surrogate.
+    fn code_point_start(&self, text: &[u16], start: usize) -> usize {
+        let in_pair = start > 0
+            && start < text.len()
+            && (0xD800..0xDC00).contains(&text[start - 1])
+            && (0xDC00..0xE000).contains(&text[start]);
+        if in_pair && self.regex.flags().is_unicode() { start - 1 } else { start }
+    }
+
+    fn updates_last_index(&self) -> bool {
+        self.global() || self.sticky()
+    }
+
+    /// `RegExp.prototype.source`: the pattern, escaped so that
+    /// `/${source}/${flags}` is a valid literal.
+    pub fn source(&self) -> String {
+        escape_source(self.regex.pattern())
+    }
+
+    /// `RegExp.prototype.flags`: the flags in canonical order (`dgimsuvy`).
+    pub fn flags(&self) -> String {
+        self.regex.flags().to_string()
+    }
+
+    /// `hasIndices` (d flag)
+    pub fn has_indices(&self) -> bool {
+        self.regex.flags().has_indices()
+    }
+
+    /// `global` (g flag)
+    pub fn global(&self) -> bool {
+        self.regex.flags().is_global()
+    }
+
+    /// `ignoreCase` (i flag)
+    pub fn ignore_case(&self) -> bool {
+        self.regex.flags().is_ignore_case()
+    }
+
+    /// `multiline` (m flag)
+    pub fn multiline(&self) -> bool {
+        self.regex.flags().is_multiline()
+    }
+
+    /// `dotAll` (s flag)
+    pub fn dot_all(&self) -> bool {
+        self.regex.flags().is_dot_all()
+    }
+
+    /// `unicode` (u flag; false for v, which implies Unicode mode internally)
+    pub fn unicode(&self) -> bool {
+        let flags = self.regex.flags();
+        flags.is_unicode() && !flags.is_unicode_sets()
+    }
+
+    /// `unicodeSets` (v flag)
+    pub fn unicode_sets(&self) -> bool {
+        self.regex.flags().is_unicode_sets()
+    }
+
+    /// `sticky` (y flag)
+    pub fn sticky(&self) -> bool {
+        self.regex.flags().is_sticky()
+    }
+}
+
+impl std::fmt::Display for JsRegExp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "/{}/{}", self.source(), self.flags())
+    }
+}
+
+/// Largest char boundary at or before `i`.
+fn floor_char_boundary(text: &str, mut i: usize) -> usize {
+    while !text.is_char_boundary(i) {
+        i -= 1;
+    }
+    i
+}
+
+/// EscapeRegExpPattern: escape `/` outside classes and line terminators;
+/// an empty pattern becomes `(?:)`.
+fn escape_source(pattern: &str) -> String {
+    if pattern.is_empty() {
+        return "(?:)".to_string();
+    }
+    let mut out = String::with_capacity(pattern.len());
+    let mut in_class = false;
+    let mut escaped = false;
+    for c in pattern.chars() {
+        let line_terminator = match c {
+            '\n' => Some("n"),
+            '\r' => Some("r"),
+            '\u{2028}' => Some("u2028"),
+            '\u{2029}' => Some("u2029"),
+            _ => None,
+        };
+        if let Some(esc) = line_terminator {
+            if !escaped {
+                out.push('\\');
+            }
+            out.push_str(esc);
+            escaped = false;
+            continue;
+        }
+        if escaped {
+            out.push(c);
+            escaped = false;
+            continue;
+        }
+        match c {
+            '\\' => escaped = true,
+            '[' => in_class = true,
+            ']' => in_class = false,
+            '/' if !in_class => out.push('\\'),
+            _ => {}
+        }
+        out.push(c);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_global_exec_advances_last_index() {
+        let mut re = JsRegExp::new(r"\d+", "g").unwrap();
+        let text = "a1 b22 c333";
+        let found: Vec<_> = std::iter::from_fn(|| re.exec(text))
+            .map(|c| c.get_str(0).unwrap().to_string())
+            .collect();
+        assert_eq!(found, ["1", "22", "333"]);
+        assert_eq!(re.last_index(), 0);
+        assert!(re.test(text));
+        assert_eq!(re.last_index(), 2);
+    }
+
+    #[test]
+    fn test_non_global_ignores_last_index() {
+        let mut re = JsRegExp::new("b", "").unwrap();
+        re.set_last_index(5);
+        assert!(re.test("abc"));
+        assert_eq!(re.last_index(), 5);
+        assert!(!re.test("xyz"));
+        assert_eq!(re.last_index(), 5);
+    }
+
+    #[test]
+    fn test_sticky_matches_only_at_last_index() {
+        let mut re = JsRegExp::new("b", "y").unwrap();
+        assert!(!re.test("ab"));
+        assert_eq!(re.last_index(), 0);
+        re.set_last_index(1);
+        assert!(re.test("ab"));
+        assert_eq!(re.last_index(), 2);
+        assert!(!re.test("ab"));
+        assert_eq!(re.last_index(), 0);
+    }
+
+    #[test]
+    fn test_last_index_past_end_resets() {
+        let mut re = JsRegExp::new("", "g").unwrap();
+        re.set_last_index(3);
+        assert!(re.test("abc"));
+        assert_eq!(re.last_index(), 3);
+        re.set_last_index(4);
+        assert!(!re.test("abc"));
+        assert_eq!(re.last_index(), 0);
+    }
+
+    #[test]
+    fn test_utf16_last_index_in_surrogate_pair() {
+        let text: Vec<u16> = "\u{1F600}".encode_utf16().collect();
+        let mut re = JsRegExp::new(".", "gu").unwrap();
+        re.set_last_index(1);
+        let caps = re.exec_utf16(&text).unwrap();
+        assert_eq!(caps.get(0).map(|m| (m.start, m.end)), Some((0, 2)));
+        assert_eq!(re.last_index(), 2);
+
+        let mut re = JsRegExp::new(".", "g").unwrap();
+        re.set_last_index(1);
+        assert!(re.test_utf16(&text));
+        assert_eq!(re.last_index(), 2);
+    }
+
+    #[test]
+    fn test_source_and_flags() {
+        let re = JsRegExp::new("a/b[/]\\/c\n", "ysgd").unwrap();
+        assert_eq!(re.source(), "a\\/b[/]\\/c\\n");
+        assert_eq!(re.flags(), "dgsy");
+        assert_eq!(re.to_string(), "/a\\/b[/]\\/c\\n/dgsy");
+        assert_eq!(JsRegExp::new("", "").unwrap().source(), "(?:)");
+        let re = JsRegExp::new("x", "v").unwrap();
+        assert!(re.unicode_sets() && !re.unicode());
+        assert_eq!(re.flags(), "v");
+        assert!(matches!(JsRegExp::new("x", "gg"), Err(Error::InvalidFlag('g'))));
+    }
+}
@@ -0,0 +1,157 @@
+//! Caller-owned capture slots for allocation-free capture extraction.
+//!
+//! `captures_at` allocates its result and engine state on every call.
+//! `captures_read_at` instead fills a `CaptureLocations` that also keeps the
+//! Pike VM scratch and the backtracking interpreter's stacks, so a hot loop
+//! only allocates while those buffers are still growing.
+
+use super::{fallible, interpreter, pikevm, utf8_char_len, Match, Regex};
+
+/// Reusable capture slots plus the engine buffers used to fill them.
+///
+/// Create one with `Regex::capture_locations` and pass it to
+/// `Regex::captures_read_at`.
+pub struct CaptureLocations {
+    /// Start/end slot pairs, one pair per group
+    slots: Vec<Option<usize>>,
+    /// Pike VM buffers (also holds the Wide NFA states used for rejection)
+    scratch: pikevm::Scratch,
+    /// Backtracking interpreter buffers
+    backtrack: interpreter::ExecBuffers,
+}
+
+impl CaptureLocations {
+    /// Number of capture groups (including group 0).
+    pub fn len(&self) -> usize {
+        self.slots.len() / 2
+    }
+
+    /// Check if there are no groups (never true for locations from a regex).
+    pub fn is_empty(&self) -> bool {
+        self.slots.is_empty()
+    }
+
+    /// Span of group `i` from the last successful read, if it participated.
+    pub fn get(&self, i: usize) -> Option<Match> {
+        match (self.slots.get(i * 2).copied().flatten(), self.slots.get(i * 2 + 1).copied().flatten()) {
+            (Some(start), Some(end)) => Some(Match { start, end }),
+            _ => None,
+        }
+    }
+}
+
+impl std::fmt::Debug for CaptureLocations {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CaptureLocations")
+            .field("slots", &self.slots)
+            .finish()
+    }
+}
+
+impl Regex {
+    /// Create capture slots (and engine buffers) for `captures_read_at`.
+    pub fn capture_locations(&self) -> CaptureLocations {
+        CaptureLocations {
+            slots: vec![None; self.capture_count() * 2],
+            scratch: self.create_scratch(),
+            backtrack: interpreter::ExecBuffers::default(),
+        }
+    }
+
+    /// Like `captures`, but writes the groups into `locs`.
+    pub fn captures_read(&self, locs: &mut CaptureLocations, text: &str) -> Option<Match> {
+        self.captures_read_at(locs, text, 0)
+    }
+
+    /// Like `captures_at`, but writes the groups into `locs` instead of
+    /// allocating. Returns the overall match; on no match all slots are `None`.
+    pub fn captures_read_at(&self, locs: &mut CaptureLocations, text: &str, start: usize) -> Option<Match> {
+        if self.read_at_bytes(locs, text.as_bytes(), start) {
+            locs.get(0)
+        } else {
+            locs.slots.clear();
+            locs.slots.resize(self.capture_count() * 2, None);
+            None
+        }
+    }
+
+    fn read_at_bytes(&self, locs: &mut CaptureLocations, text: &[u8], start: usize) -> bool {
+        if start > text.len() {
+            return false;
+        }
+        let bytecode = self.bytecode_slice();
+
+        if self.use_pike_vm {
+            let vm = pikevm::PikeVm::new(bytecode, text);
+            return vm.exec_with_scratch_into(&mut locs.scratch, start, &mut locs.slots);
+        }
+
+        // Bit VM fast rejection, on the scratch's buffers
+        if let Some(ref prog) = self.bit_program {
+            let scratch = &mut locs.scratch;
+            if prog.find_match_end_reuse(text, start, &mut scratch.wide_curr, &mut scratch.wide_next).is_none() {
+                return false;
+            }
+        }
+
+        // Backtracking interpreter (for patterns with backreferences)
+        let buffers = std::mem::take(&mut locs.backtrack);
+        let mut ctx = self.exec_context(bytecode, text, buffers);
+        let mut matched = false;
+        let mut pos = start;
+        while pos <= text.len() {
+            pos = self.next_candidate(text, pos);
+            if pos > text.len() { break; }
+
+            if let interpreter::ExecResult::Match = ctx.exec(pos) {
+                locs.slots.clear();
+                locs.slots.extend_from_slice(&ctx.captures);
+                matched = true;
+                break;
+            }
+            fallible::note_limit(ctx.limit_hit(), pos);
+            if pos < text.len() {
+                pos += utf8_char_len(text, pos);
+            } else {
+                break;
+            }
+            ctx.reset();
+        }
+        locs.backtrack = ctx.into_buffers();
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::Regex;
+
+    #[test]
+    fn test_read_matches_captures_at() {
+        let lines = ["GET /index.html 200", "POST /api 404", "bad line", "PUT /x 201", ""];
+        for pattern in [r"(\w+) (/\S*) (\d+)", r"(\w+) (/\S*)(?: (2)(\d+))?", r"(\w)(\w)\2? (/)"] {
+            let re = Regex::new(pattern).unwrap();
+            let mut locs = re.capture_locations();
+            assert_eq!(locs.len(), re.capture_count());
+            for line in lines {
+                for start in [0, 3] {
+                    let expected = re.captures_at(line, start);
+                    let found = re.captures_read_at(&mut locs, line, start);
+                    assert_eq!(found, expected.as_ref().and_then(|c| c.get(0)), "{} on {:?}", pattern, line);
+                    for i in 0..locs.len() {
+                        assert_eq!(locs.get(i), expected.as_ref().and_then(|c| c.get(i)));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_read_past_end() {
+        let re = Regex::new("(a)?").unwrap();
+        let mut locs = re.capture_locations();
+        assert!(re.captures_read_at(&mut locs, "a", 2).is_none());
+        assert_eq!(locs.get(0), None);
+        assert_eq!(re.captures_read(&mut locs, "a").map(|m| m.end), Some(1));
+    }
+}
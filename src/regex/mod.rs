@@ -37,6 +37,69 @@ pub mod pikevm;
 // Bit-parallel VM: wide-word interpreter for same bytecode (O(N/64) per byte)
 pub mod bitvm;
 
+// Byte-slice API for haystacks that may not be valid UTF-8
+pub mod bytes;
+
+// UTF-16 haystacks (transcoded to WTF-8 for the engines)
+mod utf16;
+
+// String.prototype.replace-style substitution
+mod replace;
+
+// String.prototype.split (Symbol.split) semantics
+mod split;
+
+// Stateful RegExp object with lastIndex
+mod jsregexp;
+
+// Match indices for the d flag
+mod indices;
+
+// Reusable capture slots (captures_read_at)
+mod locations;
+
+// Per-regex engine limits and switches
+mod builder;
+
+// Many patterns filtered by one literal scan
+mod set;
+
+// Searches forced onto one engine (differential testing)
+mod executor;
+
+// try_find and friends: searches that report giving up
+mod fallible;
+
+// Deadlines and cancellation for searches
+mod interrupt;
+
+// Chunked search over io::Read sources
+mod stream;
+
+// Matching input fed piece by piece
+mod push;
+
+// Anchored and full-match searches
+mod anchored;
+
+// Overlapping match iteration
+mod overlapping;
+
+// Searching for the last match
+mod reverse;
+
+// Searches bounded to a span that still see the whole haystack
+mod input;
+
+// Earliest match ends (shortest_match)
+mod shortest;
+
+// Saving compiled regexes and loading them back
+mod serialize;
+
+// Structural checks on bytecode loaded from outside the compiler
+mod verify;
+
 // Legacy C engine modules — only needed for benchmark comparison via find_at_c_engine()
 #[allow(dead_code)]
 mod unicode;
@@ -45,11 +108,29 @@ pub(crate) mod engine;
 
 pub use opcodes::OpCode;
 pub use flags::{Flags, InvalidFlag};
-pub use error::{Error, Result, ExecResult};
+pub use error::{BytecodeError, Error, MatchError, Result, ExecResult};
 pub use pikevm::Scratch;
-
+pub use utf16::{Utf16Captures, Utf16Haystack};
+pub use replace::Replacer;
+pub use jsregexp::JsRegExp;
+pub use indices::MatchIndices;
+pub use locations::CaptureLocations;
+pub use builder::RegexBuilder;
+pub use set::{RegexSet, SetMatches};
+pub use executor::Engine;
+pub use interrupt::{Interrupt, InterruptibleCaptures, InterruptibleMatches};
+pub use stream::{StreamMatch, StreamSearcher};
+pub use push::{PushEvent, PushMatcher};
+pub use anchored::Anchored;
+pub use overlapping::OverlappingMatches;
+pub use input::Input;
+pub use verify::verify_bytecode;
+
+use builder::Config;
+use std::borrow::Cow;
 use std::ptr;
 use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
 
 use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
 use memchr::{memchr, memchr2, memchr3, memmem};
@@ -405,6 +486,13 @@ fn find_first_digit_simd(bytes: &[u8]) -> Option<usize> {
     bytes.iter().position(|&b| b.wrapping_sub(b'0') <= 9)
 }
 
+/// Byte length of the char at `pos`; each invalid UTF-8 byte counts as one char.
+#[inline]
+fn utf8_char_len(bytes: &[u8], pos: usize) -> usize {
+    if pos >= bytes.len() { return 1; }
+    util::decode_utf8_at(bytes, pos, false).1
+}
+
 // Threshold for using optimizations (bytes)
 const OPTIMIZATION_THRESHOLD: usize = 32;
 
@@ -780,8 +868,24 @@ pub struct Regex {
     sub_patterns: Vec<Regex>,
     /// Shared AC automaton across all sub-pattern literals.
     sub_ac: Option<AhoCorasick>,
+    /// Literals of `sub_ac` by AC pattern index (kept for `to_bytes`).
+    sub_ac_literals: Vec<Vec<u8>>,
     /// Maps AC pattern index → one or more sub-pattern indices.
     ac_to_sub: Vec<Vec<usize>>,
+    /// Whether the pattern has non-ASCII literal chars. Byte-level literal
+    /// prefilters search for their UTF-8 encoding, which misses the same chars
+    /// decoded from invalid UTF-8 (see `bytes`).
+    non_ascii_literals: bool,
+    /// Capture group names by group number (None for unnamed groups and group 0)
+    group_names: Arc<[Option<String>]>,
+    /// Engine limits and switches (see `RegexBuilder`)
+    config: Config,
+    /// Reversed pattern for `rfind`, compiled on first use (None if the
+    /// pattern can't be reversed)
+    reverse_search: OnceLock<Option<reverse::ReverseSearch>>,
+    /// Pattern compiled for non-u searches over UTF-16 text, on first use
+    /// (None when this regex serves: no astral chars, or u mode)
+    utf16_search: OnceLock<Option<Box<Regex>>>,
 }
 
 /// Coverage details for decomposed large alternations.
@@ -818,6 +922,17 @@ impl Regex {
 
     /// Compile a new regular expression with flags (pure Rust)
     pub fn with_flags(pattern: &str, flags: Flags) -> Result<Self> {
+        Self::with_config(pattern, flags, Config::default())
+    }
+
+    /// Compile with engine limits and switches from a `RegexBuilder`.
+    pub(crate) fn with_config(pattern: &str, flags: Flags, config: Config) -> Result<Self> {
+        Self::compile(pattern, flags, config, false)
+    }
+
+    /// Compile, with astral chars matching as two code units if `code_units`
+    /// (see `compiler::compile_code_units`).
+    fn compile(pattern: &str, flags: Flags, config: Config, code_units: bool) -> Result<Self> {
         let (processed_pattern, extracted_flags) = extract_inline_flags(pattern);
         let mut final_flags = flags;
         final_flags.insert(extracted_flags.bits());
@@ -826,7 +941,8 @@ impl Regex {
         let ast = compiler::parser::parse(&processed_pattern, final_flags)
             .map_err(|e| Error::Syntax(e.to_string()))?;
 
-        let mut bytecode_vec = compiler::compile_regex(&processed_pattern, final_flags)
+        let compile = if code_units { compiler::compile_code_units } else { compiler::compile_regex };
+        let mut bytecode_vec = compile(&processed_pattern, final_flags)
             .map_err(|e| Error::Syntax(e.to_string()))?;
 
         let bytecode_ptr = bytecode_vec.as_mut_ptr();
@@ -838,28 +954,7 @@ impl Regex {
         let sel_prefilter = selective::derive_prefilter(&info);
 
         // Build Aho-Corasick or memmem prefilter objects
-        let (ac_prefilter, memmem_prefilter) = match &sel_prefilter {
-            selective::Prefilter::AhoCorasickStart(patterns)
-            | selective::Prefilter::AhoCorasickInner { patterns, .. }
-                if patterns.len() >= 2 =>
-            {
-                let ac = AhoCorasickBuilder::new()
-                    .match_kind(MatchKind::LeftmostFirst)
-                    .build(patterns)
-                    .ok();
-                (ac, None)
-            }
-            selective::Prefilter::MemmemStart(needle)
-            | selective::Prefilter::MemmemInner { needle, .. }
-                if needle.len() >= 2 =>
-            {
-                let boxed: Box<[u8]> = needle.clone().into_boxed_slice();
-                let leaked: &'static [u8] = Box::leak(boxed);
-                let finder = memmem::Finder::new(leaked);
-                (None, Some(finder))
-            }
-            _ => (None, None),
-        };
+        let (ac_prefilter, memmem_prefilter) = prefilter_searchers(&sel_prefilter);
 
         // Use Pike VM for patterns without backreferences or lookaround (guaranteed linear time).
         // Pike VM already handles Unicode word chars via is_alphanumeric().
@@ -908,18 +1003,59 @@ impl Regex {
             memmem_prefilter,
             sub_patterns: Vec::new(),
             sub_ac: None,
+            sub_ac_literals: Vec::new(),
             ac_to_sub: Vec::new(),
+            non_ascii_literals: has_non_ascii_literal(&ast),
+            group_names: collect_group_names(&ast).into(),
+            config,
+            reverse_search: OnceLock::new(),
+            utf16_search: OnceLock::new(),
         };
+        regex.apply_config();
 
         // Decompose large top-level alternations into sub-patterns.
         // Each branch gets its own small Regex for cheap per-candidate verification.
         // Use final_flags so decomposition keeps the same effective global flags
         // as the combined regex (e.g. a leading (?i) on the full pattern).
-        regex.try_decompose_alternation(pattern, final_flags);
+        if regex.config.decompose_alternations {
+            regex.try_decompose_alternation(pattern, final_flags);
+        }
 
         Ok(regex)
     }
 
+    /// Turn off the engines and search shortcuts the config disables.
+    fn apply_config(&mut self) {
+        if !self.config.pike_vm {
+            self.use_pike_vm = false;
+        }
+        if !self.config.bit_vm {
+            self.bit_program = None;
+        }
+        if !self.config.prefilters {
+            self.strategy = SearchStrategy::None;
+            self.selective_prefilter = selective::Prefilter::None;
+            self.ac_prefilter = None;
+            self.memmem_prefilter = None;
+        }
+    }
+
+    /// Drop the byte-literal search shortcuts when they can disagree with the
+    /// engines on invalid UTF-8 input. Used by `bytes::Regex`.
+    pub(crate) fn into_bytes_safe(mut self) -> Self {
+        if self.non_ascii_literals {
+            self.strategy = SearchStrategy::None;
+            self.selective_prefilter = selective::Prefilter::None;
+            self.ac_prefilter = None;
+            self.memmem_prefilter = None;
+            self.sub_patterns.clear();
+            self.sub_ac = None;
+            self.sub_ac_literals.clear();
+            self.ac_to_sub.clear();
+        }
+        self
+    }
+
     /// Alias for with_flags — kept for API compatibility
     pub fn with_flags_pure_rust(pattern: &str, flags: Flags) -> Result<Self> {
         Self::with_flags(pattern, flags)
@@ -927,27 +1063,35 @@ impl Regex {
 
     /// Test if the pattern matches anywhere in the text
     pub fn is_match(&self, text: &str) -> bool {
+        self.is_match_bytes(text.as_bytes())
+    }
+
+    pub(crate) fn is_match_bytes(&self, text: &[u8]) -> bool {
         // Bit VM fast REJECTION: if bit VM says no match, definitely no match.
         // If it says yes, verify (bit VM ignores assertions so has false positives).
         if let Some(ref prog) = self.bit_program {
-            if !prog.has_match(text.as_bytes()) {
+            if !prog.has_match(text) {
                 return false;
             }
         }
-        self.find(text).is_some()
+        self.find_bytes(text).is_some()
     }
 
     /// Find the first match in the text
     ///
     /// Uses optimized search strategies based on pattern analysis.
     pub fn find(&self, text: &str) -> Option<Match> {
+        self.find_bytes(text.as_bytes())
+    }
+
+    pub(crate) fn find_bytes(&self, text: &[u8]) -> Option<Match> {
         let len = text.len();
 
         // For short inputs, just use the engine directly
-        if len < OPTIMIZATION_THRESHOLD {
+        if len < self.config.small_input_threshold {
             return self.try_match_at(text, 0).or_else(|| {
                 // Advance by first char's UTF-8 length (not 1 byte!)
-                let first_char_len = text.chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+                let first_char_len = utf8_char_len(text, 0);
                 self.find_at_linear(text, first_char_len)
             });
         }
@@ -1015,39 +1159,39 @@ impl Regex {
 
             // === PURE FAST PATHS - No interpreter needed! ===
             SearchStrategy::PureDigitPlus => {
-                find_digit_run(text.as_bytes(), 0)
+                find_digit_run(text, 0)
             }
 
             SearchStrategy::PureLowerPlus => {
-                find_lower_run(text.as_bytes(), 0)
+                find_lower_run(text, 0)
             }
 
             SearchStrategy::PureUpperPlus => {
-                find_upper_run(text.as_bytes(), 0)
+                find_upper_run(text, 0)
             }
 
             SearchStrategy::PureAlphaPlus => {
-                find_alpha_run(text.as_bytes(), 0)
+                find_alpha_run(text, 0)
             }
 
             SearchStrategy::PureAlnumPlus => {
-                find_alnum_run(text.as_bytes(), 0)
+                find_alnum_run(text, 0)
             }
 
             SearchStrategy::PureWordPlus => {
-                find_word_run(text.as_bytes(), 0)
+                find_word_run(text, 0)
             }
 
             SearchStrategy::QuotedString(quote) => {
-                find_quoted_string(text.as_bytes(), 0, *quote)
+                find_quoted_string(text, 0, *quote)
             }
 
             SearchStrategy::PureCapitalWord => {
-                find_capital_word(text.as_bytes(), 0)
+                find_capital_word(text, 0)
             }
 
             SearchStrategy::PureLowerSuffix(suffix) => {
-                find_lower_suffix(text.as_bytes(), 0, suffix)
+                find_lower_suffix(text, 0, suffix)
             }
 
             SearchStrategy::None => {
@@ -1058,8 +1202,7 @@ impl Regex {
 
     /// Find an anchored literal pattern (^literal) - just check prefix
     #[inline]
-    fn find_anchored_literal(&self, text: &str, literal: &[u8]) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_anchored_literal(&self, bytes: &[u8], literal: &[u8]) -> Option<Match> {
         if bytes.len() >= literal.len() && bytes.starts_with(literal) {
             Some(Match {
                 start: 0,
@@ -1072,8 +1215,7 @@ impl Regex {
 
     /// Find a pure literal pattern - no engine needed!
     #[inline]
-    fn find_pure_literal(&self, text: &str, finder: &OwnedFinder) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_pure_literal(&self, bytes: &[u8], finder: &OwnedFinder) -> Option<Match> {
         // Use precomputed finder - no per-call preprocessing!
         finder.find(bytes).map(|pos| Match {
             start: pos,
@@ -1083,13 +1225,12 @@ impl Regex {
 
     /// Find using single-byte search (fastest) - used by find()
     #[inline]
-    fn find_with_single_byte(&self, text: &str, byte: u8) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_single_byte(&self, bytes: &[u8], byte: u8) -> Option<Match> {
         let mut start = 0;
 
         while let Some(pos) = memchr(byte, &bytes[start..]) {
             let abs_pos = start + pos;
-            if let Some(m) = self.try_match_at(text, abs_pos) {
+            if let Some(m) = self.try_match_at(bytes, abs_pos) {
                 return Some(m);
             }
             start = abs_pos + 1;
@@ -1102,13 +1243,12 @@ impl Regex {
 
     /// Find using two-byte search - used by find()
     #[inline]
-    fn find_with_two_bytes(&self, text: &str, b1: u8, b2: u8) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_two_bytes(&self, bytes: &[u8], b1: u8, b2: u8) -> Option<Match> {
         let mut start = 0;
 
         while let Some(pos) = memchr2(b1, b2, &bytes[start..]) {
             let abs_pos = start + pos;
-            if let Some(m) = self.try_match_at(text, abs_pos) {
+            if let Some(m) = self.try_match_at(bytes, abs_pos) {
                 return Some(m);
             }
             start = abs_pos + 1;
@@ -1121,13 +1261,12 @@ impl Regex {
 
     /// Find using three-byte search - used by find()
     #[inline]
-    fn find_with_three_bytes(&self, text: &str, b1: u8, b2: u8, b3: u8) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_three_bytes(&self, bytes: &[u8], b1: u8, b2: u8, b3: u8) -> Option<Match> {
         let mut start = 0;
 
         while let Some(pos) = memchr3(b1, b2, b3, &bytes[start..]) {
             let abs_pos = start + pos;
-            if let Some(m) = self.try_match_at(text, abs_pos) {
+            if let Some(m) = self.try_match_at(bytes, abs_pos) {
                 return Some(m);
             }
             start = abs_pos + 1;
@@ -1140,11 +1279,10 @@ impl Regex {
 
     /// Find using literal prefix search (memmem) - used by find()
     #[inline]
-    fn find_with_literal_prefix(&self, text: &str, finder: &OwnedFinder) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_literal_prefix(&self, bytes: &[u8], finder: &OwnedFinder) -> Option<Match> {
         // Use precomputed finder for iteration
         for pos in finder.finder.find_iter(bytes) {
-            if let Some(m) = self.try_match_at(text, pos) {
+            if let Some(m) = self.try_match_at(bytes, pos) {
                 return Some(m);
             }
         }
@@ -1153,13 +1291,12 @@ impl Regex {
 
     /// Find using bitmap-based byte set search (for character classes) - used by find()
     #[inline]
-    fn find_with_bitmap(&self, text: &str, bitmap: &ByteBitmap) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_bitmap(&self, bytes: &[u8], bitmap: &ByteBitmap) -> Option<Match> {
         let mut start = 0;
 
         while let Some(pos) = bitmap.find_in_slice(&bytes[start..]) {
             let abs_pos = start + pos;
-            if let Some(m) = self.try_match_at(text, abs_pos) {
+            if let Some(m) = self.try_match_at(bytes, abs_pos) {
                 return Some(m);
             }
             start = abs_pos + 1;
@@ -1172,14 +1309,13 @@ impl Regex {
 
     /// Find by scanning for digits (0-9) - used by find()
     #[inline]
-    fn find_with_digit_scan(&self, text: &str) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_digit_scan(&self, bytes: &[u8]) -> Option<Match> {
         let mut start = 0;
 
         while start < bytes.len() {
             if let Some(pos) = find_digit(&bytes[start..]) {
                 let abs_pos = start + pos;
-                if let Some(m) = self.try_match_at(text, abs_pos) {
+                if let Some(m) = self.try_match_at(bytes, abs_pos) {
                     return Some(m);
                 }
                 start = abs_pos + 1;
@@ -1192,8 +1328,7 @@ impl Regex {
 
     /// Find by scanning for word characters - used by find()
     #[inline]
-    fn find_with_word_char_scan(&self, text: &str) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_word_char_scan(&self, bytes: &[u8]) -> Option<Match> {
         let unicode_mode = self.flags.contains(Flags::UNICODE);
         let mut start = 0;
 
@@ -1208,7 +1343,7 @@ impl Regex {
             match pos {
                 Some(p) => {
                     let abs_pos = start + p;
-                    if let Some(m) = self.try_match_at(text, abs_pos) {
+                    if let Some(m) = self.try_match_at(bytes, abs_pos) {
                         return Some(m);
                     }
                     start = abs_pos + 1;
@@ -1221,13 +1356,12 @@ impl Regex {
 
     /// Find by scanning for whitespace (branchless using bitmap) - used by find()
     #[inline]
-    fn find_with_whitespace_scan(&self, text: &str) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_whitespace_scan(&self, bytes: &[u8]) -> Option<Match> {
         let mut start = 0;
 
         while let Some(pos) = find_whitespace(&bytes[start..]) {
             let abs_pos = start + pos;
-            if let Some(m) = self.try_match_at(text, abs_pos) {
+            if let Some(m) = self.try_match_at(bytes, abs_pos) {
                 return Some(m);
             }
             start = abs_pos + 1;
@@ -1240,8 +1374,7 @@ impl Regex {
 
     /// Find alternation of pure literals using Aho-Corasick - BLAZING FAST!
     #[inline]
-    fn find_with_alternation_literals(&self, text: &str, literals: &[Vec<u8>], ac: &AhoCorasick) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_alternation_literals(&self, bytes: &[u8], literals: &[Vec<u8>], ac: &AhoCorasick) -> Option<Match> {
         // Aho-Corasick finds the earliest match across all patterns
         ac.find(bytes).map(|mat| {
             Match {
@@ -1253,8 +1386,7 @@ impl Regex {
 
     /// Find pattern with suffix literal by scanning backwards
     #[inline]
-    fn find_with_suffix_literal(&self, text: &str, suffix: &[u8]) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_with_suffix_literal(&self, bytes: &[u8], suffix: &[u8]) -> Option<Match> {
         let finder = memmem::Finder::new(suffix);
 
         // Find all occurrences of the suffix and try to match from before each
@@ -1265,7 +1397,7 @@ impl Regex {
 
             // Try matching at position 0 first if suffix is at start
             if pos == 0 {
-                if let Some(m) = self.try_match_at(text, 0) {
+                if let Some(m) = self.try_match_at(bytes, 0) {
                     return Some(m);
                 }
             } else {
@@ -1274,7 +1406,7 @@ impl Regex {
                 let look_back = pos.min(64);
                 for back in 0..=look_back {
                     let try_pos = pos - back;
-                    if let Some(m) = self.try_match_at(text, try_pos) {
+                    if let Some(m) = self.try_match_at(bytes, try_pos) {
                         // Verify the match actually covers this suffix
                         if m.end >= pos + suffix.len() {
                             return Some(m);
@@ -1290,11 +1422,14 @@ impl Regex {
     /// Uses memchr scanning based on SearchStrategy for fast candidate position finding.
     /// This is the key optimization that makes pure-rust competitive with hybrid.
     pub fn find_at(&self, text: &str, start: usize) -> Option<Match> {
-        let text_bytes = text.as_bytes();
-        let len = text_bytes.len();
+        self.find_at_bytes(text.as_bytes(), start)
+    }
+
+    pub(crate) fn find_at_bytes(&self, text: &[u8], start: usize) -> Option<Match> {
+        let len = text.len();
 
         // For short remaining text, just use linear scan
-        if len.saturating_sub(start) < OPTIMIZATION_THRESHOLD {
+        if len.saturating_sub(start) < self.config.small_input_threshold {
             return self.find_at_linear(text, start);
         }
 
@@ -1303,7 +1438,7 @@ impl Regex {
         // text per call — counterproductive for count-spans with 100K+ calls.
         if !self.use_pike_vm {
             if let Some(ref prog) = self.bit_program {
-                if !prog.has_match(&text_bytes[start..]) {
+                if !prog.has_match(&text[start..]) {
                     return None;
                 }
             }
@@ -1342,7 +1477,7 @@ impl Regex {
 
             SearchStrategy::PureLiteral(finder) => {
                 // Use precomputed finder - no per-call preprocessing overhead!
-                finder.find(&text_bytes[start..])
+                finder.find(&text[start..])
                     .map(|pos| Match { start: start + pos, end: start + pos + finder.len() })
             }
 
@@ -1392,39 +1527,39 @@ impl Regex {
 
             // === PURE FAST PATHS - No interpreter needed! ===
             SearchStrategy::PureDigitPlus => {
-                find_digit_run(text_bytes, start)
+                find_digit_run(text, start)
             }
 
             SearchStrategy::PureLowerPlus => {
-                find_lower_run(text_bytes, start)
+                find_lower_run(text, start)
             }
 
             SearchStrategy::PureUpperPlus => {
-                find_upper_run(text_bytes, start)
+                find_upper_run(text, start)
             }
 
             SearchStrategy::PureAlphaPlus => {
-                find_alpha_run(text_bytes, start)
+                find_alpha_run(text, start)
             }
 
             SearchStrategy::PureAlnumPlus => {
-                find_alnum_run(text_bytes, start)
+                find_alnum_run(text, start)
             }
 
             SearchStrategy::PureWordPlus => {
-                find_word_run(text_bytes, start)
+                find_word_run(text, start)
             }
 
             SearchStrategy::QuotedString(quote) => {
-                find_quoted_string(text_bytes, start, *quote)
+                find_quoted_string(text, start, *quote)
             }
 
             SearchStrategy::PureCapitalWord => {
-                find_capital_word(text_bytes, start)
+                find_capital_word(text, start)
             }
 
             SearchStrategy::PureLowerSuffix(suffix) => {
-                find_lower_suffix(text_bytes, start, suffix)
+                find_lower_suffix(text, start, suffix)
             }
 
             SearchStrategy::None => {
@@ -1448,8 +1583,7 @@ impl Regex {
     /// For Pike VM patterns, uses the cached capture-free path first (fast scan),
     /// then falls back to full capture extraction only when needed.
     #[inline]
-    fn try_match_at(&self, text: &str, pos: usize) -> Option<Match> {
-        let text_bytes = text.as_bytes();
+    fn try_match_at(&self, text: &[u8], pos: usize) -> Option<Match> {
         let bytecode = self.bytecode_slice();
 
         if self.use_pike_vm {
@@ -1461,7 +1595,7 @@ impl Regex {
             return EXEC_SCRATCH.with(|cell| {
                 let mut scratch_opt = cell.borrow_mut();
                 let scratch = scratch_opt.get_or_insert_with(|| self.create_scratch());
-                let vm = pikevm::PikeVm::new(bytecode, text_bytes);
+                let vm = pikevm::PikeVm::new(bytecode, text);
                 match vm.exec_with_scratch(scratch, pos) {
                     pikevm::PikeResult::Match(caps) => {
                         let start = caps.get(0).copied().flatten()?;
@@ -1472,7 +1606,7 @@ impl Regex {
                 }
             });
         } else {
-            let mut ctx = interpreter::ExecContext::new(bytecode, text_bytes);
+            let mut ctx = self.exec_context(bytecode, text, Default::default());
             match ctx.exec(pos) {
                 interpreter::ExecResult::Match => {
                     if let (Some(match_start), Some(match_end)) = (
@@ -1484,15 +1618,18 @@ impl Regex {
                         None
                     }
                 }
-                interpreter::ExecResult::NoMatch => None,
+                interpreter::ExecResult::NoMatch => {
+                    fallible::note_limit(ctx.limit_hit(), pos);
+                    None
+                }
             }
         }
     }
 
     /// Find match using single-byte memchr scanning
     #[inline]
-    fn find_at_single_byte(&self, text: &str, start: usize, byte: u8) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_single_byte(&self, text: &[u8], start: usize, byte: u8) -> Option<Match> {
+        let bytes = &text[start..];
         let mut offset = 0;
 
         while let Some(pos) = memchr(byte, &bytes[offset..]) {
@@ -1507,8 +1644,8 @@ impl Regex {
 
     /// Find match using two-byte memchr scanning
     #[inline]
-    fn find_at_two_bytes(&self, text: &str, start: usize, b1: u8, b2: u8) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_two_bytes(&self, text: &[u8], start: usize, b1: u8, b2: u8) -> Option<Match> {
+        let bytes = &text[start..];
         let mut offset = 0;
 
         while let Some(pos) = memchr2(b1, b2, &bytes[offset..]) {
@@ -1523,8 +1660,8 @@ impl Regex {
 
     /// Find match using three-byte memchr scanning
     #[inline]
-    fn find_at_three_bytes(&self, text: &str, start: usize, b1: u8, b2: u8, b3: u8) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_three_bytes(&self, text: &[u8], start: usize, b1: u8, b2: u8, b3: u8) -> Option<Match> {
+        let bytes = &text[start..];
         let mut offset = 0;
 
         while let Some(pos) = memchr3(b1, b2, b3, &bytes[offset..]) {
@@ -1539,8 +1676,8 @@ impl Regex {
 
     /// Find match using literal prefix memmem scanning
     #[inline]
-    fn find_at_literal_prefix(&self, text: &str, start: usize, finder: &OwnedFinder) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_literal_prefix(&self, text: &[u8], start: usize, finder: &OwnedFinder) -> Option<Match> {
+        let bytes = &text[start..];
         // Use precomputed finder for iteration
         for pos in finder.finder.find_iter(bytes) {
             let abs_pos = start + pos;
@@ -1553,8 +1690,8 @@ impl Regex {
 
     /// Find match using bitmap scanning for character classes
     #[inline]
-    fn find_at_bitmap(&self, text: &str, start: usize, bitmap: &ByteBitmap) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_bitmap(&self, text: &[u8], start: usize, bitmap: &ByteBitmap) -> Option<Match> {
+        let bytes = &text[start..];
         let mut offset = 0;
 
         while let Some(pos) = bitmap.find_in_slice(&bytes[offset..]) {
@@ -1569,8 +1706,8 @@ impl Regex {
 
     /// Find match by scanning for digits
     #[inline]
-    fn find_at_digit(&self, text: &str, start: usize) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_digit(&self, text: &[u8], start: usize) -> Option<Match> {
+        let bytes = &text[start..];
         let mut offset = 0;
 
         while let Some(pos) = find_digit(&bytes[offset..]) {
@@ -1585,8 +1722,8 @@ impl Regex {
 
     /// Find match by scanning for word characters
     #[inline]
-    fn find_at_word_char(&self, text: &str, start: usize) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_word_char(&self, text: &[u8], start: usize) -> Option<Match> {
+        let bytes = &text[start..];
         let unicode_mode = self.flags.contains(Flags::UNICODE);
         let mut offset = 0;
 
@@ -1614,8 +1751,8 @@ impl Regex {
 
     /// Find match by scanning for whitespace
     #[inline]
-    fn find_at_whitespace(&self, text: &str, start: usize) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_whitespace(&self, text: &[u8], start: usize) -> Option<Match> {
+        let bytes = &text[start..];
         let mut offset = 0;
 
         while let Some(pos) = find_whitespace(&bytes[offset..]) {
@@ -1630,8 +1767,8 @@ impl Regex {
 
     /// Find alternation of literals using Aho-Corasick from a starting position
     #[inline]
-    fn find_at_alternation_literals(&self, text: &str, start: usize, literals: &[Vec<u8>], ac: &AhoCorasick) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_alternation_literals(&self, text: &[u8], start: usize, literals: &[Vec<u8>], ac: &AhoCorasick) -> Option<Match> {
+        let bytes = &text[start..];
         ac.find(bytes).map(|mat| {
             Match {
                 start: start + mat.start(),
@@ -1642,15 +1779,14 @@ impl Regex {
 
     /// Find case-insensitive ASCII literal using fast case-folded comparison
     #[inline]
-    fn find_case_insensitive_literal(&self, text: &str, lowercase: &[u8], len: usize) -> Option<Match> {
+    fn find_case_insensitive_literal(&self, text: &[u8], lowercase: &[u8], len: usize) -> Option<Match> {
         self.find_at_case_insensitive_literal(text, 0, lowercase, len)
     }
 
     /// Find case-insensitive ASCII literal from a starting position
     /// Uses optimized byte-by-byte comparison with ASCII case folding
     #[inline]
-    fn find_at_case_insensitive_literal(&self, text: &str, start: usize, lowercase: &[u8], len: usize) -> Option<Match> {
-        let bytes = text.as_bytes();
+    fn find_at_case_insensitive_literal(&self, bytes: &[u8], start: usize, lowercase: &[u8], len: usize) -> Option<Match> {
         if bytes.len() < start + len {
             return None;
         }
@@ -1708,8 +1844,8 @@ impl Regex {
 
     /// Find suffix literal from a starting position
     #[inline]
-    fn find_at_suffix_literal(&self, text: &str, start: usize, suffix: &[u8]) -> Option<Match> {
-        let bytes = &text.as_bytes()[start..];
+    fn find_at_suffix_literal(&self, text: &[u8], start: usize, suffix: &[u8]) -> Option<Match> {
+        let bytes = &text[start..];
         let finder = memmem::Finder::new(suffix);
 
         for pos in finder.find_iter(bytes) {
@@ -1732,14 +1868,13 @@ impl Regex {
     /// Fallback: Linear scan trying every position from start
     #[inline]
     /// Find using selective prefilter to skip to candidate positions.
-    fn find_at_with_selective_prefilter(&self, text: &str, start: usize) -> Option<Match> {
+    fn find_at_with_selective_prefilter(&self, bytes: &[u8], start: usize) -> Option<Match> {
         // Pike VM has its own prefix loop — single call is enough
         if self.use_pike_vm {
-            return self.try_match_at(text, start);
+            return self.try_match_at(bytes, start);
         }
-        let bytes = text.as_bytes();
         let mut search_from = start;
-        while search_from <= text.len() {
+        while search_from <= bytes.len() {
             // Find next literal occurrence
             let remaining = &bytes[search_from..];
             let lit_pos = match &self.selective_prefilter {
@@ -1771,7 +1906,7 @@ impl Regex {
             };
 
             // Try matching at the candidate position
-            if let Some(m) = self.try_match_at(text, try_pos) {
+            if let Some(m) = self.try_match_at(bytes, try_pos) {
                 return Some(m);
             }
 
@@ -1781,7 +1916,7 @@ impl Regex {
         None
     }
 
-    fn find_at_linear(&self, text: &str, start: usize) -> Option<Match> {
+    fn find_at_linear(&self, text: &[u8], start: usize) -> Option<Match> {
         if self.use_pike_vm {
             // Use thread-local Scratch for exec buffer reuse across find_at calls.
             // This is the key optimization: avoids per-call ThreadList allocation.
@@ -1789,7 +1924,6 @@ impl Regex {
             thread_local! {
                 static SCRATCH: RefCell<Option<pikevm::Scratch>> = RefCell::new(None);
             }
-            let text_bytes = text.as_bytes();
             let bytecode = self.bytecode_slice();
 
             return SCRATCH.with(|cell| {
@@ -1798,20 +1932,20 @@ impl Regex {
                 let scratch = scratch_opt.get_or_insert_with(|| self.create_scratch());
 
                 // Check if Wide NFA can handle this text
-                let sample = &text_bytes[..text_bytes.len().min(256)];
+                let sample = &text[..text.len().min(256)];
                 let non_ascii = sample.iter().filter(|&&b| b >= 128).count();
                 let text_is_ascii = non_ascii <= sample.len() / 4;
 
                 if text_is_ascii {
                     if let Some(ref wide_nfa) = self.bit_program {
                         return scratch.find_at(
-                            &pikevm::PikeVm::new(bytecode, text_bytes),
+                            &pikevm::PikeVm::new(bytecode, text),
                             wide_nfa, start,
                         ).map(|(s, e)| Match { start: s, end: e });
                     }
                 }
                 // Fallback: exec with scratch (reuses buffers)
-                let vm = pikevm::PikeVm::new(bytecode, text_bytes);
+                let vm = pikevm::PikeVm::new(bytecode, text);
                 match vm.exec_with_scratch(scratch, start) {
                     pikevm::PikeResult::Match(caps) => {
                         let s = caps.get(0).copied().flatten()?;
@@ -1828,7 +1962,7 @@ impl Regex {
                 return Some(m);
             }
             if pos < text.len() {
-                pos += text[pos..].chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+                pos += utf8_char_len(text, pos);
             } else {
                 break;
             }
@@ -1964,6 +2098,25 @@ impl Regex {
         }
     }
 
+    /// Pike VM scanner over `text`, with the configured DFA cache size.
+    fn pike_scanner<'t>(&self, text: &'t [u8]) -> pikevm::PikeScanner<'t> {
+        // The bytecode outlives any scanner borrowed from this regex
+        let bytecode = unsafe {
+            std::slice::from_raw_parts(self.bytecode, self.bytecode_len())
+        };
+        let mut scanner = pikevm::PikeScanner::new(bytecode, text);
+        scanner.set_dfa_cache_size(self.config.dfa_cache_size);
+        scanner
+    }
+
+    /// Backtracking interpreter context with the configured limits.
+    fn exec_context<'a>(&self, bytecode: &'a [u8], text: &'a [u8], buffers: interpreter::ExecBuffers) -> interpreter::ExecContext<'a> {
+        let mut ctx = interpreter::ExecContext::with_buffers(bytecode, text, buffers);
+        ctx.set_backtrack_limit(self.config.backtrack_limit);
+        ctx.set_stack_limit(self.config.backtrack_stack_limit);
+        ctx
+    }
+
     /// Get the number of capture groups (including group 0). Pure Rust.
     pub fn capture_count(&self) -> usize {
         let header = unsafe {
@@ -1972,6 +2125,17 @@ impl Regex {
         header[2] as usize
     }
 
+    /// Names of all capture groups in group order, including group 0.
+    /// Unnamed groups (and group 0) yield `None`.
+    pub fn capture_names(&self) -> impl Iterator<Item = Option<&str>> + '_ {
+        (0..self.capture_count()).map(|i| self.group_names.get(i).and_then(|n| n.as_deref()))
+    }
+
+    /// Group number of the first capture group called `name`.
+    pub fn capture_index(&self, name: &str) -> Option<usize> {
+        self.group_names.iter().position(|n| n.as_deref() == Some(name))
+    }
+
     /// Get the flags
     pub fn flags(&self) -> Flags {
         self.flags
@@ -2062,7 +2226,9 @@ impl Regex {
             bytecode[4], bytecode[5], bytecode[6], bytecode[7]
         ]) as usize;
         let num_pcs = 8 + body_len + 1;
-        pikevm::Scratch::new(num_pcs, capture_count, register_count, bytecode)
+        let mut scratch = pikevm::Scratch::new(num_pcs, capture_count, register_count, bytecode);
+        scratch.set_dfa_cache_size(self.config.dfa_cache_size);
+        scratch
     }
 
     /// Find the first match starting at or after `start`, using pre-allocated
@@ -2072,33 +2238,31 @@ impl Regex {
         // Skip has_match pre-check: the Wide NFA in find_at_linear_scratch
         // already handles rejection. The pre-check scans the entire remaining
         // text and is counterproductive when matches are dense.
-        self.find_at_linear_scratch(text, start, scratch)
+        self.find_at_linear_scratch(text.as_bytes(), start, scratch)
     }
 
     /// Core scratch-based linear scan for Pike VM.
     /// Two-pass: DFA O(1)/byte finds match_end, bounded exec finds match_start.
     /// All buffers in Scratch — zero allocation per call. DFA cache warms across calls.
-    fn find_at_linear_scratch(&self, text: &str, start: usize, scratch: &mut pikevm::Scratch) -> Option<Match> {
+    fn find_at_linear_scratch(&self, text: &[u8], start: usize, scratch: &mut pikevm::Scratch) -> Option<Match> {
         if self.use_pike_vm {
-            let text_bytes = text.as_bytes();
             // Sample text ONCE at position 0 (not per-call from start).
             // Determines if Wide NFA can handle this text.
-            let sample = &text_bytes[..text_bytes.len().min(256)];
+            let sample = &text[..text.len().min(256)];
             let non_ascii = sample.iter().filter(|&&b| b >= 128).count();
             let text_is_ascii = non_ascii <= sample.len() / 4;
 
             if text_is_ascii {
                 if let Some(ref wide_nfa) = self.bit_program {
                     let bytecode = self.bytecode_slice();
-                    let vm = pikevm::PikeVm::new(bytecode, text_bytes);
+                    let vm = pikevm::PikeVm::new(bytecode, text);
                     return scratch.find_at(&vm, wide_nfa, start)
                         .map(|(s, e)| Match { start: s, end: e });
                 }
             }
             // Fallback: exec with scratch (reuse buffers, no fresh allocation)
             let bytecode = self.bytecode_slice();
-            let text_bytes = text.as_bytes();
-            let vm = pikevm::PikeVm::new(bytecode, text_bytes);
+            let vm = pikevm::PikeVm::new(bytecode, text);
             return match vm.exec_with_scratch(scratch, start) {
                 pikevm::PikeResult::Match(caps) => {
                     let s = caps.get(0).copied().flatten()?;
@@ -2115,7 +2279,7 @@ impl Regex {
                 return Some(m);
             }
             if pos < text.len() {
-                pos += text[pos..].chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+                pos += utf8_char_len(text, pos);
             } else {
                 break;
             }
@@ -2138,16 +2302,20 @@ impl Regex {
     /// This is optimized for counting and uses native Aho-Corasick iteration
     /// for alternation patterns, which is faster than repeated find_at calls.
     pub fn count_matches(&self, text: &str) -> usize {
+        self.count_matches_bytes(text.as_bytes())
+    }
+
+    pub(crate) fn count_matches_bytes(&self, text: &[u8]) -> usize {
         // Decomposed large alternation: use shared AC + small sub-pattern verification
         if !self.sub_patterns.is_empty() && self.sub_ac.is_some() {
             return self.count_matches_decomposed(text);
         }
         match &self.strategy {
             SearchStrategy::AlternationLiterals { ac, .. } => {
-                ac.find_iter(text.as_bytes()).count()
+                ac.find_iter(text).count()
             }
             SearchStrategy::PureLiteral(finder) => {
-                finder.finder.find_iter(text.as_bytes()).count()
+                finder.finder.find_iter(text).count()
             }
             _ => {
                 if self.use_pike_vm {
@@ -2167,23 +2335,22 @@ impl Regex {
                             return self.count_matches_bit_scanner(text, prog);
                         }
                     }
-                    return self.find_iter(text).count();
+                    return self.find_iter_bytes(text).count();
                 }
-                self.find_iter(text).count()
+                self.find_iter_bytes(text).count()
             }
         }
     }
 
     /// Count matches: Wide NFA find_match_end (fast scan) + bounded exec (correct semantics).
-    fn count_matches_bit_scanner(&self, text: &str, prog: &bitvm::BitVmProgram) -> usize {
-        let text_bytes = text.as_bytes();
+    fn count_matches_bit_scanner(&self, text: &[u8], prog: &bitvm::BitVmProgram) -> usize {
         let bytecode = self.bytecode_slice();
         let mut scratch = self.create_scratch();
         let mut count = 0;
         let mut pos = 0;
 
-        while pos <= text_bytes.len() {
-            let match_end = match prog.find_match_end(text_bytes, pos) {
+        while pos <= text.len() {
+            let match_end = match prog.find_match_end(text, pos) {
                 Some(end) => end,
                 None => break,
             };
@@ -2191,10 +2358,10 @@ impl Regex {
             // The Wide NFA tells us WHERE a match ends. The match must START
             // within max_backup bytes before match_end. This avoids scanning
             // millions of bytes when match_end is far from pos.
-            // Use full text prefix (not a slice) so word boundary checks work.
+            // Bound the match ends, not the text, so word boundary checks work.
             let max_backup = 500;
             let exec_start = match_end.saturating_sub(max_backup).max(pos);
-            let bounded_vm = pikevm::PikeVm::new(bytecode, &text_bytes[..match_end]);
+            let bounded_vm = pikevm::PikeVm::new(bytecode, text).match_ends(0..=match_end);
             match bounded_vm.exec_with_scratch(&mut scratch, exec_start) {
                 pikevm::PikeResult::Match(caps) => {
                     count += 1;
@@ -2212,8 +2379,7 @@ impl Regex {
 
     /// Count matches using Pike VM with prefilter acceleration.
     /// Uses memmem/AC to jump to candidate positions, then Pike VM to verify.
-    fn count_matches_pike(&self, text: &str) -> usize {
-        let text_bytes = text.as_bytes();
+    fn count_matches_pike(&self, text: &[u8]) -> usize {
         let bytecode = self.bytecode_slice();
 
         // If we have a memmem or AC prefilter, use prefilter-accelerated counting
@@ -2224,19 +2390,18 @@ impl Regex {
             selective::Prefilter::AhoCorasickInner { .. }
         );
 
-        if has_literal_prefilter && text_bytes.len() > 1024 {
+        if has_literal_prefilter && text.len() > 1024 {
             return self.count_matches_pike_prefiltered(text);
         }
 
         // No useful prefilter — use DFA-cached scanner
-        let mut scanner = pikevm::PikeScanner::new(bytecode, text_bytes);
+        let mut scanner = self.pike_scanner(text);
         scanner.count_all()
     }
 
     /// Count matches by jumping to prefilter candidates and running Pike VM nearby.
     /// Two-stage filtering: AC prefilter → Wide NFA rejection → Pike VM confirm.
-    fn count_matches_pike_prefiltered(&self, text: &str) -> usize {
-        let text_bytes = text.as_bytes();
+    fn count_matches_pike_prefiltered(&self, text: &[u8]) -> usize {
         let bytecode = self.bytecode_slice();
         let mut scratch = self.create_scratch();
         let mut count = 0;
@@ -2254,8 +2419,8 @@ impl Regex {
         // Most patterns are < 200 bytes. Use 300 for safety.
         let forward = 300;
 
-        while search_from < text_bytes.len() {
-            let remaining = &text_bytes[search_from..];
+        while search_from < text.len() {
+            let remaining = &text[search_from..];
 
             // Find next literal candidate
             let lit_pos = match &self.selective_prefilter {
@@ -2283,8 +2448,8 @@ impl Regex {
             // For 96 alternatives, exec adds ~96 initial threads per byte
             // position. Most die immediately (wrong first byte). Average
             // per-byte cost is ~106 ops, so 310-byte window ≈ 33K ops.
-            let window_end = (lit_pos + forward).min(text_bytes.len());
-            let window = &text_bytes[window_start..window_end];
+            let window_end = (lit_pos + forward).min(text.len());
+            let window = &text[window_start..window_end];
             let vm = pikevm::PikeVm::new(bytecode, window);
             match vm.exec_with_scratch(&mut scratch, 0) {
                 pikevm::PikeResult::Match(caps) => {
@@ -2315,6 +2480,10 @@ impl Regex {
     /// assert_eq!(matches.len(), 3);
     /// ```
     pub fn find_iter<'r, 't>(&'r self, text: &'t str) -> MatchIterator<'r, 't> {
+        self.find_iter_bytes(text.as_bytes())
+    }
+
+    pub(crate) fn find_iter_bytes<'r, 't>(&'r self, text: &'t [u8]) -> MatchIterator<'r, 't> {
         // Use specialized iterator for patterns we can handle super fast
         match &self.strategy {
             SearchStrategy::PureLiteral(finder) => {
@@ -2347,10 +2516,7 @@ impl Regex {
             _ => {
                 // Use Pike VM iterator for patterns routed to Pike VM
                 if self.use_pike_vm {
-                    let bytecode = unsafe {
-                        std::slice::from_raw_parts(self.bytecode, self.bytecode_len())
-                    };
-                    let scanner = pikevm::PikeScanner::new(bytecode, text.as_bytes());
+                    let scanner = self.pike_scanner(text);
                     MatchIterator::PikeVm(PikeVmMatches {
                         scanner,
                         regex: self,
@@ -2387,19 +2553,27 @@ impl Regex {
     /// assert_eq!(caps.get_str(2), Some("example"));
     /// assert_eq!(caps.get_str(3), Some("com"));
     /// ```
-    pub fn captures(&self, text: &str) -> Option<Captures> {
+    pub fn captures<'t>(&self, text: &'t str) -> Option<Captures<'t>> {
         self.captures_at(text, 0)
     }
 
     /// Get capture groups from a match starting at the given byte offset.
     /// Get capture groups at a byte offset (pure Rust, with selective prefiltering)
-    pub fn captures_at(&self, text: &str, start: usize) -> Option<Captures> {
-        let text_bytes = text.as_bytes();
+    pub fn captures_at<'t>(&self, text: &'t str, start: usize) -> Option<Captures<'t>> {
+        let groups = self.captures_at_bytes(text.as_bytes(), start)?;
+        Some(self.make_captures(text, groups))
+    }
+
+    /// Capture group spans of the first match at or after `start`.
+    pub(crate) fn captures_at_bytes(&self, text: &[u8], start: usize) -> Option<Vec<Option<(usize, usize)>>> {
         let capture_count = self.capture_count();
+        if start > text.len() {
+            return None;
+        }
 
         // Bit VM fast rejection
         if let Some(ref prog) = self.bit_program {
-            if !prog.has_match(&text_bytes[start..]) {
+            if !prog.has_match(&text[start..]) {
                 return None;
             }
         }
@@ -2417,7 +2591,7 @@ impl Regex {
             return CAP_SCRATCH.with(|cell| {
                 let mut scratch_opt = cell.borrow_mut();
                 let scratch = scratch_opt.get_or_insert_with(|| self.create_scratch());
-                let vm = pikevm::PikeVm::new(bytecode, text_bytes);
+                let vm = pikevm::PikeVm::new(bytecode, text);
                 match vm.exec_with_scratch(scratch, start) {
                 pikevm::PikeResult::Match(caps) => {
                     let mut groups = Vec::with_capacity(capture_count);
@@ -2429,10 +2603,7 @@ impl Regex {
                             _ => groups.push(None),
                         }
                     }
-                    Some(Captures {
-                        text: text.to_string(),
-                        groups,
-                    })
+                    Some(groups)
                 }
                 pikevm::PikeResult::NoMatch => None,
                 }
@@ -2440,12 +2611,12 @@ impl Regex {
         }
 
         // Fallback: backtracking interpreter (for patterns with backreferences)
-        let mut ctx = interpreter::ExecContext::new(bytecode, text_bytes);
+        let mut ctx = self.exec_context(bytecode, text, Default::default());
 
         let mut pos = start;
         while pos <= text.len() {
             // Skip to next candidate position using prefilter
-            pos = self.next_candidate(text_bytes, pos);
+            pos = self.next_candidate(text, pos);
             if pos > text.len() { break; }
 
             match ctx.exec(pos) {
@@ -2459,14 +2630,12 @@ impl Regex {
                             _ => groups.push(None),
                         }
                     }
-                    return Some(Captures {
-                        text: text.to_string(),
-                        groups,
-                    });
+                    return Some(groups);
                 }
                 interpreter::ExecResult::NoMatch => {
+                    fallible::note_limit(ctx.limit_hit(), pos);
                     if pos < text.len() {
-                        pos += text[pos..].chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+                        pos += utf8_char_len(text, pos);
                     } else {
                         break;
                     }
@@ -2477,15 +2646,49 @@ impl Regex {
         None
     }
 
+    /// Wrap capture spans found in `text` into a `Captures`.
+    pub(crate) fn make_captures<'t>(&self, text: &'t str, groups: Vec<Option<(usize, usize)>>) -> Captures<'t> {
+        Captures { text: Cow::Borrowed(text), groups, names: self.group_names.clone(), has_indices: self.flags.has_indices() }
+    }
+
+    /// Iterate over the captures of all non-overlapping matches, like
+    /// JavaScript's `matchAll`.
+    ///
+    /// Empty matches advance by one char (a code point), exactly like
+    /// `find_iter`. Pike VM patterns reuse one scanner for the whole
+    /// iteration instead of a fresh capture search per match.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use quickjs_regex::Regex;
+    ///
+    /// let re = Regex::new(r"(\w)=(\d)").unwrap();
+    /// let pairs: Vec<_> = re.captures_iter("a=1, b=2")
+    ///     .map(|caps| (caps.get_str(1).unwrap().to_string(), caps.get_str(2).unwrap().to_string()))
+    ///     .collect();
+    /// assert_eq!(pairs, [("a".to_string(), "1".to_string()), ("b".to_string(), "2".to_string())]);
+    /// ```
+    pub fn captures_iter<'r, 't>(&'r self, text: &'t str) -> CapturesIterator<'r, 't> {
+        let scanner = self.use_pike_vm.then(|| self.pike_scanner(text.as_bytes()));
+        CapturesIterator {
+            regex: self,
+            text,
+            scanner,
+            pos: 0,
+            last_was_empty: false,
+        }
+    }
+
     /// Alias for captures_at — kept for API compatibility
     #[doc(hidden)]
-    pub fn captures_at_pure_rust(&self, text: &str, start: usize) -> Option<Captures> {
+    pub fn captures_at_pure_rust<'t>(&self, text: &'t str, start: usize) -> Option<Captures<'t>> {
         self.captures_at(text, start)
     }
 
     /// Get captures using pre-allocated scratch. Uses Wide NFA to find match
     /// bounds first, then bounded exec for capture extraction.
-    pub fn captures_at_scratch(&self, text: &str, start: usize, scratch: &mut pikevm::Scratch) -> Option<Captures> {
+    pub fn captures_at_scratch<'t>(&self, text: &'t str, start: usize, scratch: &mut pikevm::Scratch) -> Option<Captures<'t>> {
         let text_bytes = text.as_bytes();
         let capture_count = self.capture_count();
         let bytecode = self.bytecode_slice();
@@ -2494,7 +2697,7 @@ impl Regex {
             // Use Wide NFA to find match_end, then bounded exec for captures
             if let Some(ref wide_nfa) = self.bit_program {
                 let match_end = wide_nfa.find_match_end(text_bytes, start)?;
-                let bounded_vm = pikevm::PikeVm::new(bytecode, &text_bytes[..match_end]);
+                let bounded_vm = pikevm::PikeVm::new(bytecode, text_bytes).match_ends(0..=match_end);
                 return match bounded_vm.exec_with_scratch(scratch, start) {
                     pikevm::PikeResult::Match(caps) => {
                         let mut groups = Vec::with_capacity(capture_count);
@@ -2506,7 +2709,7 @@ impl Regex {
                                 _ => groups.push(None),
                             }
                         }
-                        Some(Captures { text: text.to_string(), groups })
+                        Some(self.make_captures(text, groups))
                     }
                     pikevm::PikeResult::NoMatch => {
                         // Bounded exec disagrees — fall back to full exec
@@ -2522,7 +2725,7 @@ impl Regex {
                                         _ => groups.push(None),
                                     }
                                 }
-                                Some(Captures { text: text.to_string(), groups })
+                                Some(self.make_captures(text, groups))
                             }
                             pikevm::PikeResult::NoMatch => None,
                         }
@@ -2542,7 +2745,7 @@ impl Regex {
                             _ => groups.push(None),
                         }
                     }
-                    Some(Captures { text: text.to_string(), groups })
+                    Some(self.make_captures(text, groups))
                 }
                 pikevm::PikeResult::NoMatch => None,
             };
@@ -2550,6 +2753,70 @@ impl Regex {
         self.captures_at(text, start)
     }
 
+    /// Find a match in UTF-16 text starting at or after code unit `start`.
+    ///
+    /// Offsets of the returned `Match` are UTF-16 code unit indices (JavaScript
+    /// string indices). Lone surrogates are allowed. In unicode mode a `start`
+    /// in the middle of a surrogate pair searches from the start of the pair;
+    /// without it every code unit is a char of its own, as in JavaScript.
+    ///
+    /// The text is transcoded for every call; use `find_in_utf16` to search
+    /// the same text more than once.
+    pub fn find_at_utf16(&self, text: &[u16], start: usize) -> Option<Match> {
+        self.find_in_utf16(&Utf16Haystack::new(text), start)
+    }
+
+    /// Get capture groups from a match in UTF-16 text starting at or after
+    /// code unit `start`. See `find_at_utf16` for offset semantics.
+    pub fn captures_at_utf16(&self, text: &[u16], start: usize) -> Option<Utf16Captures> {
+        self.captures_in_utf16(&Utf16Haystack::new(text), start)
+    }
+
+    /// Like `find_at_utf16`, reusing the transcoding of `haystack` from
+    /// earlier searches.
+    pub fn find_in_utf16(&self, haystack: &Utf16Haystack<'_>, start: usize) -> Option<Match> {
+        if start > haystack.text().len() {
+            return None;
+        }
+        let (re, transcoded) = self.utf16_search(haystack);
+        re.find_at_bytes(&transcoded.bytes, transcoded.byte_offset(start))
+            .map(|m| transcoded.to_units(m))
+    }
+
+    /// Like `captures_at_utf16`, reusing the transcoding of `haystack` from
+    /// earlier searches.
+    pub fn captures_in_utf16(&self, haystack: &Utf16Haystack<'_>, start: usize) -> Option<Utf16Captures> {
+        let text = haystack.text();
+        if start > text.len() {
+            return None;
+        }
+        let (re, transcoded) = self.utf16_search(haystack);
+        let groups = re.captures_at_bytes(&transcoded.bytes, transcoded.byte_offset(start))?;
+        Some(Utf16Captures::new(text, transcoded, groups, self.group_names.clone(), self.flags.has_indices()))
+    }
+
+    /// The regex to run over `haystack` and the haystack transcoded for it.
+    /// Without u, astral chars of the pattern have to match two lone
+    /// surrogates, so such patterns are compiled again for UTF-16.
+    fn utf16_search<'a>(&'a self, haystack: &'a Utf16Haystack<'_>) -> (&'a Regex, &'a utf16::Transcoded) {
+        let unicode = self.flags.is_unicode();
+        let re = match unicode {
+            true => self,
+            false => self
+                .utf16_search
+                .get_or_init(|| {
+                    if !self.pattern.chars().any(|c| c > '\u{FFFF}') || !self.pattern_compiles_to_bytecode() {
+                        return None;
+                    }
+                    let re = Regex::compile(&self.pattern, self.flags, self.config.clone(), true).ok()?;
+                    Some(Box::new(re.into_bytes_safe()))
+                })
+                .as_deref()
+                .unwrap_or(self),
+        };
+        (re, haystack.transcoded(unicode))
+    }
+
     /// Attempt to decompose a large top-level alternation into sub-patterns.
     /// Each branch is compiled as a separate small Regex with its own prefilter.
     /// A shared AC automaton is built from all sub-pattern literals.
@@ -2566,7 +2833,7 @@ impl Regex {
             // Compile each branch as an independent regex.
             // Don't wrap in (?:...) — inline flags like (?i) at the start
             // need to be visible to extract_inline_flags.
-            let sub_re = match Regex::compile_sub_pattern(alt, flags) {
+            let sub_re = match Regex::compile_sub_pattern(alt, flags, self.config.clone()) {
                 Some(r) => r,
                 None => {
                     // If any branch fails to compile, abort decomposition
@@ -2610,20 +2877,14 @@ impl Regex {
             ac_to_sub.push(sub_idxs);
         }
 
-        // Use case-insensitive AC since many sub-patterns use (?i).
-        // Extra candidates for case-sensitive patterns are rejected by Pike VM.
-        let sub_ac = AhoCorasickBuilder::new()
-            .match_kind(MatchKind::LeftmostFirst)
-            .ascii_case_insensitive(true)
-            .build(&all_ac_literals)
-            .ok();
-
+        let sub_ac = sub_pattern_ac(&all_ac_literals);
         if sub_ac.is_none() {
             return;
         }
 
         self.sub_patterns = sub_patterns;
         self.sub_ac = sub_ac;
+        self.sub_ac_literals = all_ac_literals;
         self.ac_to_sub = ac_to_sub;
     }
 
@@ -2665,7 +2926,7 @@ impl Regex {
     }
 
     /// Compile a sub-pattern without triggering further decomposition.
-    fn compile_sub_pattern(pattern: &str, flags: Flags) -> Option<Regex> {
+    fn compile_sub_pattern(pattern: &str, flags: Flags, config: Config) -> Option<Regex> {
         let (processed_pattern, extracted_flags) = extract_inline_flags(pattern);
         let mut final_flags = flags;
         final_flags.insert(extracted_flags.bits());
@@ -2679,28 +2940,7 @@ impl Regex {
         let info = selective::analyze(&ir);
         let sel_prefilter = selective::derive_prefilter(&info);
 
-        let (ac_prefilter, memmem_prefilter) = match &sel_prefilter {
-            selective::Prefilter::AhoCorasickStart(patterns)
-            | selective::Prefilter::AhoCorasickInner { patterns, .. }
-                if patterns.len() >= 2 =>
-            {
-                let ac = AhoCorasickBuilder::new()
-                    .match_kind(MatchKind::LeftmostFirst)
-                    .build(patterns)
-                    .ok();
-                (ac, None)
-            }
-            selective::Prefilter::MemmemStart(needle)
-            | selective::Prefilter::MemmemInner { needle, .. }
-                if needle.len() >= 2 =>
-            {
-                let boxed: Box<[u8]> = needle.clone().into_boxed_slice();
-                let leaked: &'static [u8] = Box::leak(boxed);
-                let finder = memmem::Finder::new(leaked);
-                (None, Some(finder))
-            }
-            _ => (None, None),
-        };
+        let (ac_prefilter, memmem_prefilter) = prefilter_searchers(&sel_prefilter);
 
         let has_lookahead_opcodes = {
             let mut pc = 8;
@@ -2726,7 +2966,7 @@ impl Regex {
             None
         };
 
-        Some(Regex {
+        let mut regex = Regex {
             bit_program,
             bytecode: bytecode_ptr,
             pattern: pattern.to_string(),
@@ -2739,8 +2979,16 @@ impl Regex {
             memmem_prefilter,
             sub_patterns: Vec::new(),
             sub_ac: None,
+            sub_ac_literals: Vec::new(),
             ac_to_sub: Vec::new(),
-        })
+            non_ascii_literals: has_non_ascii_literal(&ast),
+            group_names: collect_group_names(&ast).into(),
+            config,
+            reverse_search: OnceLock::new(),
+            utf16_search: OnceLock::new(),
+        };
+        regex.apply_config();
+        Some(regex)
     }
 
     /// Extract prefilter literals from this regex's selective prefilter.
@@ -2766,9 +3014,8 @@ impl Regex {
     /// Count matches using decomposed sub-patterns.
     /// Shared AC finds literal candidates, then each is verified with
     /// the small sub-pattern Regex instead of the full combined Pike VM.
-    fn count_matches_decomposed(&self, text: &str) -> usize {
+    fn count_matches_decomposed(&self, text: &[u8]) -> usize {
         let ac = self.sub_ac.as_ref().unwrap();
-        let text_bytes = text.as_bytes();
         let mut count = 0;
         let mut pos = 0;
 
@@ -2778,8 +3025,8 @@ impl Regex {
             .collect();
 
         // AC-based counting for sub-patterns with literals
-        while pos < text_bytes.len() {
-            let hit = match ac.find(&text_bytes[pos..]) {
+        while pos < text.len() {
+            let hit = match ac.find(&text[pos..]) {
                 Some(m) => m,
                 None => break,
             };
@@ -2802,7 +3049,7 @@ impl Regex {
                 let forward = 256;
 
                 let try_start = abs_pos.saturating_sub(backup).max(pos);
-                let window_end = (abs_pos + forward).min(text_bytes.len());
+                let window_end = (abs_pos + forward).min(text.len());
 
                 // Include 1 byte before try_start for \b word boundary context.
                 // The Pike VM needs the preceding character to correctly evaluate \b.
@@ -2811,7 +3058,7 @@ impl Regex {
 
                 // Use Pike VM directly with per-sub-pattern scratch
                 let bytecode = sub_re.bytecode_slice();
-                let window_bytes = &text_bytes[ctx_start..window_end];
+                let window_bytes = &text[ctx_start..window_end];
                 let vm = pikevm::PikeVm::new(bytecode, window_bytes);
                 let result = vm.exec_with_scratch(&mut scratches[sub_idx], exec_offset);
                 if let pikevm::PikeResult::Match(caps) = result {
@@ -2833,6 +3080,43 @@ impl Regex {
     }
 }
 
+/// Aho-Corasick or memmem searcher for the literals of a selective prefilter.
+fn prefilter_searchers(prefilter: &selective::Prefilter) -> (Option<AhoCorasick>, Option<memmem::Finder<'static>>) {
+    match prefilter {
+        selective::Prefilter::AhoCorasickStart(patterns)
+        | selective::Prefilter::AhoCorasickInner { patterns, .. }
+            if patterns.len() >= 2 =>
+        {
+            let ac = AhoCorasickBuilder::new()
+                .match_kind(MatchKind::LeftmostFirst)
+                .build(patterns)
+                .ok();
+            (ac, None)
+        }
+        selective::Prefilter::MemmemStart(needle)
+        | selective::Prefilter::MemmemInner { needle, .. }
+            if needle.len() >= 2 =>
+        {
+            let boxed: Box<[u8]> = needle.clone().into_boxed_slice();
+            let leaked: &'static [u8] = Box::leak(boxed);
+            let finder = memmem::Finder::new(leaked);
+            (None, Some(finder))
+        }
+        _ => (None, None),
+    }
+}
+
+/// Case-insensitive AC over the literals of decomposed sub-patterns.
+/// Many sub-patterns use (?i); extra candidates for case-sensitive ones are
+/// rejected by the Pike VM.
+fn sub_pattern_ac(literals: &[Vec<u8>]) -> Option<AhoCorasick> {
+    AhoCorasickBuilder::new()
+        .match_kind(MatchKind::LeftmostFirst)
+        .ascii_case_insensitive(true)
+        .build(literals)
+        .ok()
+}
+
 impl Drop for Regex {
     fn drop(&mut self) {
         if self.owned_bytecode.is_some() {
@@ -2892,17 +3176,32 @@ impl Match {
 /// Captured groups from a regex match.
 ///
 /// Group 0 is always the entire match. Groups 1+ are the explicit
-/// capture groups in the pattern.
+/// capture groups in the pattern. The haystack is borrowed; use
+/// `into_owned` to keep the captures beyond its lifetime.
 #[derive(Debug, Clone)]
-pub struct Captures {
+pub struct Captures<'t> {
     /// The original text
-    text: String,
+    text: Cow<'t, str>,
     /// Pairs of (start, end) byte offsets for each group
     /// None means the group didn't participate in the match
     groups: Vec<Option<(usize, usize)>>,
+    /// Group names by group number, shared with the regex
+    names: Arc<[Option<String>]>,
+    /// Whether the regex has the `d` flag
+    has_indices: bool,
 }
 
-impl Captures {
+impl<'t> Captures<'t> {
+    /// Copy the haystack so the captures no longer borrow it.
+    pub fn into_owned(self) -> Captures<'static> {
+        Captures {
+            text: Cow::Owned(self.text.into_owned()),
+            groups: self.groups,
+            names: self.names,
+            has_indices: self.has_indices,
+        }
+    }
+
     /// Get the number of capture groups (including group 0).
     pub fn len(&self) -> usize {
         self.groups.len()
@@ -2946,6 +3245,55 @@ impl Captures {
     pub fn count_matched(&self) -> usize {
         self.groups.iter().filter(|g| g.is_some()).count()
     }
+
+    /// Byte offset of the match in the input (JavaScript's `offset` argument).
+    pub fn offset(&self) -> usize {
+        self.get(0).map_or(0, |m| m.start)
+    }
+
+    /// The whole input that was searched.
+    pub fn input(&self) -> &str {
+        &self.text
+    }
+
+    /// Get a capture group by name.
+    ///
+    /// If several groups share the name (in different alternatives), this
+    /// is the one that participated in the match.
+    pub fn name(&self, name: &str) -> Option<Match> {
+        self.names.iter().enumerate()
+            .filter(|(_, n)| n.as_deref() == Some(name))
+            .find_map(|(i, _)| self.get(i))
+    }
+
+    /// Get the text of a capture group by name.
+    pub fn name_str(&self, name: &str) -> Option<&str> {
+        self.name(name).map(|m| &self.text[m.start..m.end])
+    }
+
+    /// Iterate over the named capture groups in group order.
+    pub fn iter_named(&self) -> impl Iterator<Item = (&str, Option<Match>)> + '_ {
+        self.names.iter().enumerate().filter_map(|(i, n)| {
+            n.as_deref().map(|name| (name, self.get(i)))
+        })
+    }
+
+    /// Map of named groups to their text, or `None` if the pattern has no
+    /// named groups (JavaScript's `groups` argument).
+    pub fn named_groups(&self) -> Option<HashMap<&str, Option<&str>>> {
+        if !self.names.iter().any(Option::is_some) {
+            return None;
+        }
+        let mut map = HashMap::new();
+        for (name, m) in self.iter_named() {
+            // Duplicate names: keep whichever group participated
+            let entry = map.entry(name).or_insert(None);
+            if let Some(m) = m {
+                *entry = Some(&self.text[m.start..m.end]);
+            }
+        }
+        Some(map)
+    }
 }
 
 /// Match iterator enum - dispatches between literal and general matching
@@ -2966,7 +3314,7 @@ pub enum MatchIterator<'r, 't> {
 pub struct PikeVmMatches<'r, 't> {
     scanner: pikevm::PikeScanner<'t>,
     regex: &'r Regex,
-    text: &'t str,
+    text: &'t [u8],
     pos: usize,
     last_was_empty: bool,
 }
@@ -2980,7 +3328,7 @@ impl<'r, 't> Iterator for PikeVmMatches<'r, 't> {
         let search_start = if self.last_was_empty {
             let mut next = self.pos;
             if next < self.text.len() {
-                next += self.text[next..].chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+                next += utf8_char_len(self.text, next);
             } else {
                 return None;
             }
@@ -3000,6 +3348,52 @@ impl<'r, 't> Iterator for PikeVmMatches<'r, 't> {
     }
 }
 
+/// Iterator over the captures of all non-overlapping matches.
+pub struct CapturesIterator<'r, 't> {
+    regex: &'r Regex,
+    text: &'t str,
+    /// Pike VM scanner reused across matches (None for interpreter patterns)
+    scanner: Option<pikevm::PikeScanner<'t>>,
+    pos: usize,
+    last_was_empty: bool,
+}
+
+impl<'r, 't> Iterator for CapturesIterator<'r, 't> {
+    type Item = Captures<'t>;
+
+    fn next(&mut self) -> Option<Captures<'t>> {
+        let text = self.text.as_bytes();
+        if self.pos > text.len() { return None; }
+
+        let search_start = if self.last_was_empty {
+            if self.pos < text.len() {
+                self.pos + utf8_char_len(text, self.pos)
+            } else {
+                return None;
+            }
+        } else {
+            self.pos
+        };
+
+        let groups = match self.scanner.as_mut() {
+            Some(scanner) => {
+                let caps = scanner.captures_next(search_start)?;
+                (0..self.regex.capture_count())
+                    .map(|i| match (caps.get(i * 2).copied().flatten(), caps.get(i * 2 + 1).copied().flatten()) {
+                        (Some(s), Some(e)) => Some((s, e)),
+                        _ => None,
+                    })
+                    .collect()
+            }
+            None => self.regex.captures_at_bytes(text, search_start)?,
+        };
+        let (start, end) = groups.first().copied().flatten()?;
+        self.last_was_empty = start == end;
+        self.pos = end;
+        Some(self.regex.make_captures(self.text, groups))
+    }
+}
+
 impl<'r, 't> Iterator for MatchIterator<'r, 't> {
     type Item = Match;
 
@@ -3036,8 +3430,7 @@ pub enum LiteralMatches<'r, 't> {
 
 impl<'r, 't> LiteralMatches<'r, 't> {
     /// Create a new literal match iterator
-    fn new(literal: &'r [u8], text: &'t str) -> Self {
-        let bytes = text.as_bytes();
+    fn new(literal: &'r [u8], bytes: &'t [u8]) -> Self {
         if literal.len() == 1 {
             LiteralMatches::Single {
                 bytes,
@@ -3083,7 +3476,7 @@ impl<'r, 't> Iterator for LiteralMatches<'r, 't> {
 pub struct AlternationMatches<'r, 't> {
     ac: &'r AhoCorasick,
     literals: &'r [Vec<u8>],
-    text: &'t str,
+    text: &'t [u8],
     pos: usize,
 }
 
@@ -3095,7 +3488,7 @@ impl<'r, 't> Iterator for AlternationMatches<'r, 't> {
             return None;
         }
 
-        let bytes = &self.text.as_bytes()[self.pos..];
+        let bytes = &self.text[self.pos..];
         if let Some(mat) = self.ac.find(bytes) {
             let start = self.pos + mat.start();
             let end = self.pos + mat.end();
@@ -3110,7 +3503,7 @@ impl<'r, 't> Iterator for AlternationMatches<'r, 't> {
 
 /// Fast iterator for pure digit patterns [0-9]+ - NO INTERPRETER!
 pub struct PureDigitMatches<'t> {
-    text: &'t str,
+    text: &'t [u8],
     pos: usize,
 }
 
@@ -3118,7 +3511,7 @@ impl<'t> Iterator for PureDigitMatches<'t> {
     type Item = Match;
 
     fn next(&mut self) -> Option<Match> {
-        let bytes = self.text.as_bytes();
+        let bytes = self.text;
         let m = find_digit_run(bytes, self.pos)?;
         self.pos = m.end;
         Some(m)
@@ -3127,7 +3520,7 @@ impl<'t> Iterator for PureDigitMatches<'t> {
 
 /// Fast iterator for pure word char patterns \w+ - NO INTERPRETER!
 pub struct PureWordMatches<'t> {
-    text: &'t str,
+    text: &'t [u8],
     pos: usize,
 }
 
@@ -3135,7 +3528,7 @@ impl<'t> Iterator for PureWordMatches<'t> {
     type Item = Match;
 
     fn next(&mut self) -> Option<Match> {
-        let bytes = self.text.as_bytes();
+        let bytes = self.text;
         let m = find_word_run(bytes, self.pos)?;
         self.pos = m.end;
         Some(m)
@@ -3144,7 +3537,7 @@ impl<'t> Iterator for PureWordMatches<'t> {
 
 /// Fast iterator for quoted string patterns "[^"]*" - NO INTERPRETER!
 pub struct QuotedStringMatches<'t> {
-    text: &'t str,
+    text: &'t [u8],
     pos: usize,
     quote: u8,
 }
@@ -3153,7 +3546,7 @@ impl<'t> Iterator for QuotedStringMatches<'t> {
     type Item = Match;
 
     fn next(&mut self) -> Option<Match> {
-        let bytes = self.text.as_bytes();
+        let bytes = self.text;
         let m = find_quoted_string(bytes, self.pos, self.quote)?;
         self.pos = m.end;
         Some(m)
@@ -3162,7 +3555,7 @@ impl<'t> Iterator for QuotedStringMatches<'t> {
 
 /// Fast iterator for capital word patterns [A-Z][a-z]+ - NO INTERPRETER!
 pub struct CapitalWordMatches<'t> {
-    text: &'t str,
+    text: &'t [u8],
     pos: usize,
 }
 
@@ -3170,7 +3563,7 @@ impl<'t> Iterator for CapitalWordMatches<'t> {
     type Item = Match;
 
     fn next(&mut self) -> Option<Match> {
-        let bytes = self.text.as_bytes();
+        let bytes = self.text;
         let m = find_capital_word(bytes, self.pos)?;
         self.pos = m.end;
         Some(m)
@@ -3179,7 +3572,7 @@ impl<'t> Iterator for CapitalWordMatches<'t> {
 
 /// Fast iterator for lowercase+suffix patterns [a-z]+ing - NO INTERPRETER!
 pub struct LowerSuffixMatches<'t> {
-    text: &'t str,
+    text: &'t [u8],
     pos: usize,
     suffix: Vec<u8>,
 }
@@ -3188,7 +3581,7 @@ impl<'t> Iterator for LowerSuffixMatches<'t> {
     type Item = Match;
 
     fn next(&mut self) -> Option<Match> {
-        let bytes = self.text.as_bytes();
+        let bytes = self.text;
         let m = find_lower_suffix(bytes, self.pos, &self.suffix)?;
         self.pos = m.end;
         Some(m)
@@ -3198,7 +3591,7 @@ impl<'t> Iterator for LowerSuffixMatches<'t> {
 /// An iterator over all non-overlapping matches in a string.
 pub struct Matches<'r, 't> {
     regex: &'r Regex,
-    text: &'t str,
+    text: &'t [u8],
     last_end: usize,
     /// Track if last match was empty to avoid infinite loops
     last_was_empty: bool,
@@ -3217,7 +3610,7 @@ impl<'r, 't> Iterator for Matches<'r, 't> {
             let mut next = self.last_end;
             if next < self.text.len() {
                 // Advance by one UTF-8 character
-                next += self.text[next..].chars().next().map(|c| c.len_utf8()).unwrap_or(1);
+                next += utf8_char_len(self.text, next);
             } else {
                 return None;
             }
@@ -3226,7 +3619,7 @@ impl<'r, 't> Iterator for Matches<'r, 't> {
             self.last_end
         };
 
-        match self.regex.find_at(self.text, search_start) {
+        match self.regex.find_at_bytes(self.text, search_start) {
             Some(m) => {
                 self.last_was_empty = m.is_empty();
                 self.last_end = m.end;
@@ -3241,6 +3634,55 @@ impl<'r, 't> Iterator for Matches<'r, 't> {
 // Pattern Analysis - determines optimal search strategy
 // ============================================================================
 
+/// Capture group names indexed by group number, as declared by `(?<name>...)`.
+fn collect_group_names(node: &compiler::parser::Node) -> Vec<Option<String>> {
+    use compiler::parser::Node;
+    fn walk(node: &Node, names: &mut Vec<Option<String>>) {
+        match node {
+            Node::Capture { index, name, sub } => {
+                let index = *index as usize;
+                if names.len() <= index {
+                    names.resize(index + 1, None);
+                }
+                names[index] = name.clone();
+                walk(sub, names);
+            }
+            Node::Lookahead { sub, .. }
+            | Node::Lookbehind { sub, .. }
+            | Node::Group(sub)
+            | Node::Repeat { sub, .. } => walk(sub, names),
+            Node::Concat(nodes) | Node::Alternation(nodes) => {
+                for n in nodes { walk(n, names); }
+            }
+            _ => {}
+        }
+    }
+    let mut names = vec![None];
+    walk(node, &mut names);
+    names
+}
+
+/// Whether the AST contains a non-ASCII literal char or a non-negated class
+/// that includes non-ASCII chars.
+fn has_non_ascii_literal(node: &compiler::parser::Node) -> bool {
+    use compiler::parser::{ClassRange, Node};
+    match node {
+        Node::Literal(c) => !c.is_ascii(),
+        Node::Class { ranges, negated } => !negated && ranges.iter().any(|r| match r {
+            ClassRange::Single(c) => !c.is_ascii(),
+            ClassRange::Range(_, hi) => !hi.is_ascii(),
+            ClassRange::Builtin(_) => false,
+        }),
+        Node::Lookahead { sub, .. }
+        | Node::Lookbehind { sub, .. }
+        | Node::Capture { sub, .. }
+        | Node::Group(sub)
+        | Node::Repeat { sub, .. } => has_non_ascii_literal(sub),
+        Node::Concat(nodes) | Node::Alternation(nodes) => nodes.iter().any(has_non_ascii_literal),
+        _ => false,
+    }
+}
+
 /// Detect patterns that can be handled with pure fast paths (no interpreter!)
 /// These patterns are common and can be matched much faster with specialized code.
 fn detect_pure_pattern(pattern: &str) -> Option<SearchStrategy> {
@@ -5085,4 +5527,72 @@ mod tests {
         }
         assert_eq!(count, 3 + 5 + 5, "grep-captures count should be 13");
     }
+
+    #[test]
+    fn test_captures_iter_matches_find_iter() {
+        let text = "k1=v1; key2=value2; =x; k3=";
+        for pattern in [r"(\w+)=(\w*)", r"(\w*)=(?:(v)|\w)*", r"(a)|(=)", r"(\w)\1*="] {
+            let re = Regex::new(pattern).unwrap();
+            let found: Vec<_> = re.find_iter(text).collect();
+            let caps: Vec<_> = re.captures_iter(text).collect();
+            assert_eq!(caps.iter().map(|c| c.get(0).unwrap()).collect::<Vec<_>>(), found, "{}", pattern);
+            for c in &caps {
+                assert_eq!(c.len(), re.capture_count());
+            }
+        }
+        let re = Regex::new(r"(\w+)=(\w*)").unwrap();
+        let values: Vec<_> = re.captures_iter(text).map(|c| c.get_str(2).unwrap().to_string()).collect();
+        assert_eq!(values, ["v1", "value2", ""]);
+    }
+
+    #[test]
+    fn test_captures_iter_empty_matches() {
+        let re = Regex::new("(x)?").unwrap();
+        let caps: Vec<_> = re.captures_iter("\u{e9}x\u{1F600}").collect();
+        let spans: Vec<_> = caps.iter().map(|c| c.get(0).unwrap()).map(|m| (m.start, m.end)).collect();
+        assert_eq!(spans, [(0, 0), (2, 3), (3, 3), (7, 7)]);
+        assert_eq!(caps.iter().map(|c| c.get_str(1)).collect::<Vec<_>>(), [None, Some("x"), None, None]);
+        let re = Regex::with_flags("", Flags::from_bits(Flags::UNICODE)).unwrap();
+        assert_eq!(re.captures_iter("a\u{1F600}").count(), 3);
+    }
+
+    #[test]
+    fn test_named_capture_groups() {
+        let re = Regex::new(r"(?<year>\d{4})-(\d{2})-(?<day>\d{2})").unwrap();
+        assert_eq!(re.capture_names().collect::<Vec<_>>(), [None, Some("year"), None, Some("day")]);
+        assert_eq!(re.capture_index("day"), Some(3));
+        assert_eq!(re.capture_index("month"), None);
+
+        let caps = re.captures("on 2024-05-17").unwrap();
+        assert_eq!(caps.name("year"), Some(Match { start: 3, end: 7 }));
+        assert_eq!(caps.name_str("day"), Some("17"));
+        assert_eq!(caps.name("month"), None);
+        let named: Vec<_> = caps.iter_named().map(|(n, m)| (n, m.map(|m| m.start))).collect();
+        assert_eq!(named, [("year", Some(3)), ("day", Some(11))]);
+
+        let re = Regex::new(r"(a)(b)").unwrap();
+        assert_eq!(re.capture_names().count(), 3);
+        assert_eq!(re.captures("ab").unwrap().iter_named().count(), 0);
+    }
+
+    #[test]
+    fn test_duplicate_group_names() {
+        let re = Regex::new(r"(?<x>a)|(?<x>b)").unwrap();
+        assert_eq!(re.capture_index("x"), Some(1));
+        assert_eq!(re.captures("b").unwrap().name("x"), Some(Match { start: 0, end: 1 }));
+    }
+
+    #[test]
+    fn test_captures_borrow_and_into_owned() {
+        let re = Regex::new(r"(\w+)@(\w+)").unwrap();
+        let owned = {
+            let text = String::from("mail user@host now");
+            let caps = re.captures(&text).unwrap();
+            let user: &str = caps.get_str(1).unwrap();
+            assert_eq!(user, "user");
+            caps.into_owned()
+        };
+        assert_eq!(owned.get_str(2), Some("host"));
+        assert_eq!(owned.input(), "mail user@host now");
+    }
 }
@@ -0,0 +1,110 @@
+//! Overlapping match iteration.
+//!
+//! Yields, for every position a match can start at, the match `find` would
+//! report there. The next start is found by searching from one char past
+//! the previous start. Pike VM patterns drive one scanner, and its DFA cache
+//! and buffers, across all of those searches; other patterns use `find_at`.
+//! A sticky pattern only tries the position it is given, so there each
+//! position is tried in turn.
+
+use super::{pikevm, utf8_char_len, Match, Regex};
+
+impl Regex {
+    /// Iterate over the matches starting at every position, including ones
+    /// that overlap. Each is the leftmost-first match at its start.
+    pub fn find_overlapping_iter<'r, 't>(&'r self, text: &'t str) -> OverlappingMatches<'r, 't> {
+        self.find_overlapping_iter_bytes(text.as_bytes())
+    }
+
+    pub(crate) fn find_overlapping_iter_bytes<'r, 't>(&'r self, text: &'t [u8]) -> OverlappingMatches<'r, 't> {
+        let sticky = self.flags().is_sticky();
+        let scanner = (self.use_pike_vm && !sticky).then(|| self.pike_scanner(text));
+        OverlappingMatches { regex: self, text, scanner, pos: 0, sticky }
+    }
+}
+
+/// Iterator returned by `Regex::find_overlapping_iter`.
+pub struct OverlappingMatches<'r, 't> {
+    regex: &'r Regex,
+    text: &'t [u8],
+    /// Pike VM scanner reused across starts (None for interpreter and
+    /// sticky patterns)
+    scanner: Option<pikevm::PikeScanner<'t>>,
+    /// First position the next match may start at
+    pos: usize,
+    sticky: bool,
+}
+
+impl<'r, 't> Iterator for OverlappingMatches<'r, 't> {
+    type Item = Match;
+
+    fn next(&mut self) -> Option<Match> {
+        while self.pos <= self.text.len() {
+            let found = match self.scanner.as_mut() {
+                Some(scanner) => scanner.find_next(self.pos).map(|(start, end)| Match { start, end }),
+                None => self.regex.find_at_bytes(self.text, self.pos),
+            };
+            match found {
+                Some(m) => {
+                    self.pos = m.start + utf8_char_len(self.text, m.start);
+                    return Some(m);
+                }
+                None if self.sticky => self.pos += utf8_char_len(self.text, self.pos),
+                None => break,
+            }
+        }
+        self.pos = usize::MAX;
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Flags, Regex, RegexBuilder};
+
+    fn spans(re: &Regex, text: &str) -> Vec<(usize, usize)> {
+        re.find_overlapping_iter(text).map(|m| (m.start, m.end)).collect()
+    }
+
+    #[test]
+    fn test_every_start() {
+        let re = Regex::new("ATA|TAT").unwrap();
+        assert_eq!(spans(&re, "GATATAC"), vec![(1, 4), (2, 5), (3, 6)]);
+        let re = Regex::new(r"\d+").unwrap();
+        assert_eq!(spans(&re, "a123"), vec![(1, 4), (2, 4), (3, 4)]);
+        let re = RegexBuilder::new(r"(\w)\1").pike_vm(false).build().unwrap();
+        assert_eq!(spans(&re, "aaab"), vec![(0, 2), (1, 3)]);
+    }
+
+    #[test]
+    fn test_empty_matches_and_utf8() {
+        let re = Regex::new("x*").unwrap();
+        assert_eq!(spans(&re, "\u{e9}x"), vec![(0, 0), (2, 3), (3, 3)]);
+        let re = Regex::new("").unwrap();
+        assert_eq!(spans(&re, ""), vec![(0, 0)]);
+    }
+
+    #[test]
+    fn test_agrees_with_find_at() {
+        let text = "ab12 aab-\u{e9}x 3a ababab ";
+        for pattern in ["a+b?", r"\d|\w\d", "(ab)+", r"\b", "x*", "[^ ]+ "] {
+            let re = Regex::new(pattern).unwrap();
+            let mut expected = Vec::new();
+            let mut pos = 0;
+            while let Some(m) = re.find_at(text, pos) {
+                expected.push((m.start, m.end));
+                pos = m.start + text[m.start..].chars().next().map_or(1, char::len_utf8);
+                if pos > text.len() {
+                    break;
+                }
+            }
+            assert_eq!(spans(&re, text), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_sticky_tries_each_position() {
+        let re = Regex::with_flags("ab", Flags::parse("y").unwrap()).unwrap();
+        assert_eq!(spans(&re, "xabab"), vec![(1, 3), (3, 5)]);
+    }
+}
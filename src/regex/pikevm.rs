@@ -15,6 +15,10 @@
 //! but execution continues. Only when the matching thread IS the highest
 //! priority, or all higher-priority threads die, does the match finalize.
 
+use std::ops::RangeInclusive;
+
+use super::interrupt::{self, Ticker};
+
 const RE_HEADER_LEN: usize = 8;
 const RE_HEADER_FLAGS: usize = 0;
 const RE_HEADER_CAPTURE_COUNT: usize = 2;
@@ -97,6 +101,14 @@ impl ThreadList {
         }
     }
 
+    /// Whether this list can hold threads for a program with these dimensions.
+    #[inline]
+    fn fits(&self, num_pcs: usize, capture_count: usize, register_count: usize) -> bool {
+        self.seen.len() >= num_pcs
+            && self.capture_stride == capture_count * 2
+            && self.reg_stride == register_count
+    }
+
     /// O(1) clear via generation bump. No memory touched.
     #[inline]
     fn clear(&mut self) {
@@ -174,11 +186,11 @@ enum EpsFrame {
 /// Bytes that always trigger identical NFA transitions share a class.
 /// This reduces the transition table from 256 entries to ~20-50 entries per state,
 /// which means the DFA can cache 5-12× more states before overflow.
-const MAX_DFA_STATES: usize = 65536;
+pub(crate) const MAX_DFA_STATES: usize = 65536;
 
 /// Compute byte equivalence classes from bytecode.
 /// Bytes in the same class have identical behavior for all consuming states.
-/// Returns (class_map, num_classes).
+/// Returns (class_map, num_classes, context_dependent).
 fn compute_byte_classes(bytecode: &[u8]) -> ([u8; 256], usize, bool) {
     use std::collections::BTreeSet;
 
@@ -191,13 +203,15 @@ fn compute_byte_classes(bytecode: &[u8]) -> ([u8; 256], usize, bool) {
     let mut boundaries = BTreeSet::new();
     boundaries.insert(0u16);
     boundaries.insert(128); // ASCII vs non-ASCII boundary
-    let mut has_word_boundary = false;
+    let mut context_dependent = false;
 
     let mut pc = RE_HEADER_LEN;
     while pc < total_pcs && pc < bytecode.len() {
         let opc = bytecode[pc];
-        if opc == op::WORD_BOUNDARY || opc == op::NOT_WORD_BOUNDARY {
-            has_word_boundary = true;
+        // `\b` and multiline `$` look at the next byte, which a cached
+        // transition doesn't know
+        if matches!(opc, op::WORD_BOUNDARY | op::NOT_WORD_BOUNDARY | op::LINE_END_M) {
+            context_dependent = true;
         }
         match opc {
             op::CHAR | op::CHAR_I => {
@@ -220,6 +234,11 @@ fn compute_byte_classes(bytecode: &[u8]) -> ([u8; 256], usize, bool) {
                 boundaries.insert(0x0A); boundaries.insert(0x0B);
                 boundaries.insert(0x0D); boundaries.insert(0x0E);
             }
+            // Multiline `^` looks at the byte just consumed: with `\n` in a
+            // class of its own, the transition on a class decides it
+            op::LINE_START_M => {
+                boundaries.insert(0x0A); boundaries.insert(0x0B);
+            }
             op::SPACE | op::NOT_SPACE => {
                 for &b in &[0x09u16, 0x0A, 0x0B, 0x0C, 0x0D, 0x0E, 0x20, 0x21] {
                     boundaries.insert(b);
@@ -264,7 +283,7 @@ fn compute_byte_classes(bytecode: &[u8]) -> ([u8; 256], usize, bool) {
         }
     }
 
-    (class_map, num_classes as usize, has_word_boundary)
+    (class_map, num_classes as usize, context_dependent)
 }
 
 pub struct LazyDfa {
@@ -285,8 +304,11 @@ pub struct LazyDfa {
     class_map: [u8; 256],
     /// Number of equivalence classes
     num_classes: usize,
-    /// Whether the bytecode has word boundary assertions (position-dependent → no caching)
-    has_word_boundary: bool,
+    /// Whether the bytecode has assertions that depend on the next byte
+    /// (position-dependent → no caching)
+    context_dependent: bool,
+    /// Number of states to cache before falling back to uncached stepping
+    max_states: usize,
 }
 
 impl LazyDfa {
@@ -303,13 +325,14 @@ impl LazyDfa {
             next_id: 0,
             class_map,
             num_classes: 256,
-            has_word_boundary: false,
+            context_dependent: false,
+            max_states: MAX_DFA_STATES,
         }
     }
 
     /// Create a DFA with byte-class equivalence derived from bytecode.
     pub fn with_classes(bytecode: &[u8]) -> Self {
-        let (class_map, num_classes, has_word_boundary) = compute_byte_classes(bytecode);
+        let (class_map, num_classes, context_dependent) = compute_byte_classes(bytecode);
         LazyDfa {
             state_map: std::collections::HashMap::new(),
             transitions: Vec::new(),
@@ -319,17 +342,23 @@ impl LazyDfa {
             next_id: 0,
             class_map,
             num_classes,
-            has_word_boundary,
+            context_dependent,
+            max_states: MAX_DFA_STATES,
         }
     }
 
+    /// Limit the number of cached states.
+    pub fn set_max_states(&mut self, max_states: usize) {
+        self.max_states = max_states;
+    }
+
     /// Get or create a state ID for a given (state set, prev_is_word) pair
     fn get_or_create_state(&mut self, states: &[u32], contains_match: bool, prev_is_word: bool) -> Option<u32> {
         let key = (states.to_vec(), prev_is_word);
         if let Some(&id) = self.state_map.get(&key) {
             return Some(id);
         }
-        if self.next_id as usize >= MAX_DFA_STATES {
+        if self.next_id as usize >= self.max_states {
             return None; // Cache full
         }
         let id = self.next_id;
@@ -380,6 +409,19 @@ pub struct PikeVm<'a> {
     register_count: usize,
     num_pcs: usize,
     unicode_mode: bool,
+    /// Where `exec` starts in the bytecode
+    start_pc: usize,
+    /// Positions a match may end at
+    match_ends: RangeInclusive<usize>,
+}
+
+/// Result of a scan over input that may go on past its end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Scan {
+    Found(usize),
+    None,
+    /// More input is needed to decide
+    Undecided,
 }
 
 pub enum PikeResult {
@@ -405,9 +447,24 @@ impl<'a> PikeVm<'a> {
             register_count,
             num_pcs: RE_HEADER_LEN + bc_len + 1,
             unicode_mode,
+            start_pc: RE_HEADER_LEN,
+            match_ends: 0..=usize::MAX,
         }
     }
 
+    /// Make `exec` try only its start position.
+    pub(crate) fn anchored(mut self) -> Self {
+        self.start_pc = super::anchored::pattern_start(self.bytecode);
+        self
+    }
+
+    /// Only accept matches ending in `ends`. `exec` stops reading the input
+    /// past the last of them, but assertions still see all of it.
+    pub(crate) fn match_ends(mut self, ends: RangeInclusive<usize>) -> Self {
+        self.match_ends = ends;
+        self
+    }
+
     /// Create a persistent scanner with lazy DFA for repeated matching.
     pub fn scanner(&self) -> PikeScanner<'a> {
         PikeScanner::new(self.bytecode, self.input)
@@ -485,8 +542,15 @@ impl<'a> PikeVm<'a> {
                         stack.push((pc + 1, false));
                     }
                 }
-                op::LINE_END | op::LINE_END_M => {
-                    if at == self.input_len || (opcode == op::LINE_END_M && at < self.input_len && self.input[at] == b'\n') {
+                op::LINE_END => {
+                    // Parked like a consuming state, so state sets don't
+                    // depend on where the input ends: consuming anything
+                    // kills the thread, `matches_at_end` resumes it
+                    seen[pc] = true;
+                    states.push(pc as u32);
+                }
+                op::LINE_END_M => {
+                    if at == self.input_len || self.input[at] == b'\n' {
                         stack.push((pc + 1, false));
                     }
                 }
@@ -506,6 +570,26 @@ impl<'a> PikeVm<'a> {
         }
     }
 
+    /// Whether `states`, a closure from `eps_closure_fast`, matches when the
+    /// input ends here: resumes the threads parked at `$`, which holds at
+    /// the end. `buf`, `seen` and `stack` are scratch space.
+    fn matches_at_end(&self, states: &[u32], buf: &mut Vec<u32>, seen: &mut [bool], stack: &mut Vec<(usize, bool)>) -> bool {
+        buf.clear();
+        seen.fill(false);
+        buf.extend_from_slice(states);
+        let mut i = 0;
+        while i < buf.len() {
+            let pc = buf[i] as usize;
+            match self.bytecode[pc] {
+                op::MATCH => return true,
+                op::LINE_END => self.eps_closure_fast(buf, seen, stack, pc + 1, self.input_len),
+                _ => {}
+            }
+            i += 1;
+        }
+        false
+    }
+
     /// Full execution with captures and greedy/lazy semantics.
     pub fn exec(&self, start_pos: usize) -> PikeResult {
         let mut curr = ThreadList::new(self.num_pcs, self.capture_count, self.register_count);
@@ -513,7 +597,76 @@ impl<'a> PikeVm<'a> {
         let mut eps_stack: Vec<EpsFrame> = Vec::with_capacity(64);
         let mut tmp_caps = vec![None; self.capture_count * 2];
         let mut tmp_regs = vec![0usize; self.register_count];
-        self.exec_inner(&mut curr, &mut next, &mut eps_stack, &mut tmp_caps, &mut tmp_regs, start_pos)
+        let mut caps = Vec::new();
+        if self.exec_inner(&mut curr, &mut next, &mut eps_stack, &mut tmp_caps, &mut tmp_regs, start_pos, &mut caps) {
+            PikeResult::Match(caps)
+        } else {
+            PikeResult::NoMatch
+        }
+    }
+
+    /// First position, from `start_pos` on, that a match ends at or before
+    /// and that no match spans: none starts before it and ends after it.
+    /// With `truncated` the input may go on past its end, so the answer is
+    /// undecided if it still depends on what follows.
+    pub(crate) fn unspanned_point(&self, start_pos: usize, truncated: bool) -> Scan {
+        let (mut curr, mut next, mut eps_stack, mut tmp_caps, mut tmp_regs) = self.scan_buffers();
+        self.epsilon_closure(&mut curr, &mut eps_stack, &mut tmp_caps, &mut tmp_regs, self.start_pc, start_pos);
+        let mut ticker = Ticker::new();
+
+        let mut point: Option<usize> = None;
+        let mut at = start_pos;
+        loop {
+            if !(truncated && at == self.input_len) {
+                for &(pc, slot_idx) in &curr.threads {
+                    if self.bytecode.get(pc as usize) != Some(&op::MATCH) {
+                        continue;
+                    }
+                    // Threads keep the earliest start at their pc, which
+                    // spans anything a later start would
+                    let start = curr.get_caps(slot_idx)[0];
+                    if point.is_none_or(|point| start.is_some_and(|start| start < point)) {
+                        point = Some(at);
+                    }
+                }
+            }
+            if let Some(point) = point {
+                // Threads started from here on can't span the point
+                let spanning = curr.threads.iter().any(|&(pc, slot_idx)| {
+                    self.bytecode.get(pc as usize) != Some(&op::MATCH)
+                        && curr.get_caps(slot_idx)[0].is_some_and(|start| start < point)
+                });
+                if !spanning {
+                    return Scan::Found(point);
+                }
+            }
+            if at >= self.input_len || curr.threads.is_empty() {
+                break;
+            }
+            if ticker.tick() {
+                return Scan::None;
+            }
+            let char_len = self.advance(&mut curr, &mut next, &mut eps_stack, &mut tmp_caps, &mut tmp_regs, at);
+            if char_len == 0 {
+                break;
+            }
+            at += char_len;
+        }
+        match point {
+            _ if truncated && !curr.threads.is_empty() => Scan::Undecided,
+            Some(point) => Scan::Found(point),
+            None => Scan::None,
+        }
+    }
+
+    fn scan_buffers(&self) -> (ThreadList, ThreadList, Vec<EpsFrame>, Vec<Option<usize>>, Vec<usize>) {
+        (
+            ThreadList::new(self.num_pcs, self.capture_count, self.register_count),
+            ThreadList::new(self.num_pcs, self.capture_count, self.register_count),
+            Vec::with_capacity(64),
+            vec![None; self.capture_count * 2],
+            vec![0; self.register_count],
+        )
     }
 
     /// Full execution reusing shared Scratch (avoids per-call allocation).
@@ -525,6 +678,16 @@ impl<'a> PikeVm<'a> {
         )
     }
 
+    /// Like `exec_with_scratch`, but writes the capture slots of a match into
+    /// `caps` instead of allocating them. Returns whether there was a match.
+    pub fn exec_with_scratch_into(&self, scratch: &mut Scratch, start_pos: usize, caps: &mut Vec<Option<usize>>) -> bool {
+        self.exec_reuse_into(
+            &mut scratch.curr, &mut scratch.next,
+            &mut scratch.eps_stack, &mut scratch.tmp_caps, &mut scratch.tmp_regs,
+            start_pos, caps,
+        )
+    }
+
     /// Full execution reusing pre-allocated buffers (avoids per-call allocation).
     fn exec_reuse(
         &self,
@@ -535,13 +698,33 @@ impl<'a> PikeVm<'a> {
         tmp_regs: &mut Vec<usize>,
         start_pos: usize,
     ) -> PikeResult {
-        // Ensure buffers are sized correctly for this VM
-        if curr.seen.len() < self.num_pcs {
+        let mut caps = Vec::new();
+        if self.exec_reuse_into(curr, next, eps_stack, tmp_caps, tmp_regs, start_pos, &mut caps) {
+            PikeResult::Match(caps)
+        } else {
+            PikeResult::NoMatch
+        }
+    }
+
+    /// `exec_reuse` writing the match's capture slots into `caps`.
+    fn exec_reuse_into(
+        &self,
+        curr: &mut ThreadList,
+        next: &mut ThreadList,
+        eps_stack: &mut Vec<EpsFrame>,
+        tmp_caps: &mut Vec<Option<usize>>,
+        tmp_regs: &mut Vec<usize>,
+        start_pos: usize,
+        caps: &mut Vec<Option<usize>>,
+    ) -> bool {
+        // Ensure buffers are sized correctly for this VM (a thread-local
+        // scratch may have been built for a different regex)
+        if !curr.fits(self.num_pcs, self.capture_count, self.register_count) {
             *curr = ThreadList::new(self.num_pcs, self.capture_count, self.register_count);
         } else {
             curr.clear();
         }
-        if next.seen.len() < self.num_pcs {
+        if !next.fits(self.num_pcs, self.capture_count, self.register_count) {
             *next = ThreadList::new(self.num_pcs, self.capture_count, self.register_count);
         } else {
             next.clear();
@@ -550,10 +733,11 @@ impl<'a> PikeVm<'a> {
         for v in tmp_caps.iter_mut() { *v = None; }
         tmp_regs.resize(self.register_count, 0);
         for v in tmp_regs.iter_mut() { *v = 0; }
-        self.exec_inner(curr, next, eps_stack, tmp_caps, tmp_regs, start_pos)
+        self.exec_inner(curr, next, eps_stack, tmp_caps, tmp_regs, start_pos, caps)
     }
 
-    /// Core exec implementation with external buffers.
+    /// Core exec implementation with external buffers. The capture slots of
+    /// the match are written to `out`; returns whether there was a match.
     fn exec_inner(
         &self,
         curr: &mut ThreadList,
@@ -562,133 +746,122 @@ impl<'a> PikeVm<'a> {
         tmp_caps: &mut [Option<usize>],
         tmp_regs: &mut [usize],
         start_pos: usize,
-    ) -> PikeResult {
-        let mut candidate: Option<Vec<Option<usize>>> = None;
+        out: &mut Vec<Option<usize>>,
+    ) -> bool {
+        // `out` holds the candidate (leftmost match so far) once this is set
+        let mut has_candidate = false;
 
         // Initialize: epsilon closure from bytecode start
-        self.epsilon_closure(curr, eps_stack, tmp_caps, tmp_regs, RE_HEADER_LEN, start_pos);
+        self.epsilon_closure(curr, eps_stack, tmp_caps, tmp_regs, self.start_pc, start_pos);
 
+        let mut ticker = Ticker::new();
         let mut at = start_pos;
         loop {
-            // === MATCH CHECK WITH SHADOWING ===
-            // Scan threads in priority order (index 0 = highest priority).
-            // If the highest-priority thread that reached MATCH exists, check
-            // if ANY higher-priority non-MATCH thread is still alive.
-            let mut first_match_idx: Option<usize> = None;
-            for (i, &(pc, _slot_idx)) in curr.threads.iter().enumerate() {
-                if (pc as usize) < self.bytecode.len() && self.bytecode[pc as usize] == op::MATCH {
-                    first_match_idx = Some(i);
-                    break; // First MATCH in priority order
-                }
+            if self.settle(curr, out, &mut has_candidate) {
+                return true;
             }
 
-            if let Some(match_idx) = first_match_idx {
-                let (_, slot_idx) = curr.threads[match_idx];
-                let caps = curr.get_caps(slot_idx).to_vec();
-
-                #[cfg(test)]
-                eprintln!("[pike] at={} match_idx={} caps={:?} candidate={:?} threads={}",
-                    at, match_idx, &caps[..2.min(caps.len())], candidate.as_ref().map(|c| &c[..2.min(c.len())]), curr.threads.len());
-
-                if match_idx == 0 {
-                    if let Some(prev) = candidate {
-                        if prev.get(0) < caps.get(0) {
-                            return PikeResult::Match(prev);
-                        }
-                    }
-                    return PikeResult::Match(caps);
-                }
-
-                match &candidate {
-                    None => { candidate = Some(caps); }
-                    Some(prev) => {
-                        if caps.get(0) == prev.get(0) {
-                            // Same start position: update with latest (longest) match
-                            candidate = Some(caps);
-                        }
-                    }
-                }
-            } else if let Some(ref cand) = candidate {
-                // MATCH disappeared. Check if any alive thread could still
-                // produce a match from the same or earlier start position.
-                // If not: the candidate is the leftmost match — return it.
-                if let Some(&Some(cand_start)) = cand.get(0) {
-                    let mut has_earlier_thread = false;
-                    for &(_, slot_idx) in &curr.threads {
-                        let thread_caps = curr.get_caps(slot_idx);
-                        if let Some(&Some(s)) = thread_caps.get(0) {
-                            if s <= cand_start {
-                                has_earlier_thread = true;
-                                break;
-                            }
-                        }
-                    }
-                    if !has_earlier_thread {
-                        return PikeResult::Match(candidate.unwrap());
-                    }
-                }
+            if at >= self.input_len.min(*self.match_ends.end()) {
+                break;
+            }
+            if ticker.tick() {
+                return false;
             }
 
-            if at >= self.input_len {
+            let char_len = self.advance(curr, next, eps_stack, tmp_caps, tmp_regs, at);
+            if char_len == 0 { break; }
+            at += char_len;
+
+            if curr.threads.is_empty() {
                 break;
             }
+        }
 
-            // === ADVANCE: consume input[at] ===
-            let (c, char_len) = self.next_char(at);
-            if char_len == 0 { break; }
+        self.settle_final(curr, out, has_candidate)
+    }
 
-            for i in 0..curr.threads.len() {
-                let (pc, slot_idx) = curr.threads[i];
-                let pc_usize = pc as usize;
-                if pc_usize >= self.bytecode.len() { continue; }
-
-                let opcode = self.bytecode[pc_usize];
-                if let Some((next_pc, advance)) = self.try_consume(pc_usize, opcode, at, c) {
-                    let actual_advance = if advance > char_len { advance } else { char_len };
-                    let cs = curr.capture_stride;
-                    let src = slot_idx as usize * cs;
-                    for j in 0..cs.min(tmp_caps.len()) {
-                        tmp_caps[j] = curr.slots[src + j];
-                    }
-                    let rs = curr.reg_stride;
-                    if rs > 0 {
-                        let src_r = slot_idx as usize * rs;
-                        for j in 0..rs.min(tmp_regs.len()) {
-                            tmp_regs[j] = curr.regs[src_r + j];
-                        }
-                    }
+    /// Leftmost-first bookkeeping for the threads at one position: updates
+    /// the candidate in `out` and returns true once it is the final match.
+    #[inline]
+    fn settle(&self, curr: &mut ThreadList, out: &mut Vec<Option<usize>>, has_candidate: &mut bool) -> bool {
+        // Threads are in priority order: a thread at MATCH beats every
+        // thread after it, including later starts, and loses to every
+        // thread before it
+        let first_match = curr.threads.iter()
+            .position(|&(pc, _)| self.bytecode.get(pc as usize) == Some(&op::MATCH));
+        if let Some(i) = first_match {
+            let (_, slot_idx) = curr.threads[i];
+            out.clear();
+            out.extend_from_slice(curr.get_caps(slot_idx));
+            *has_candidate = true;
+            curr.threads.truncate(i);
+        }
+        *has_candidate && curr.threads.is_empty()
+    }
 
-                    self.epsilon_closure(next, eps_stack, tmp_caps, tmp_regs, next_pc, at + actual_advance);
+    /// Consume the char at `at` with every thread in `curr`, leaving the
+    /// surviving threads in `curr`. Returns the char's length (0 at the end).
+    #[inline]
+    fn advance(
+        &self,
+        curr: &mut ThreadList,
+        next: &mut ThreadList,
+        eps_stack: &mut Vec<EpsFrame>,
+        tmp_caps: &mut [Option<usize>],
+        tmp_regs: &mut [usize],
+        at: usize,
+    ) -> usize {
+        // === ADVANCE: consume input[at] ===
+        let (c, char_len) = self.next_char(at);
+        if char_len == 0 { return 0; }
+
+        for i in 0..curr.threads.len() {
+            let (pc, slot_idx) = curr.threads[i];
+            let pc_usize = pc as usize;
+            if pc_usize >= self.bytecode.len() { continue; }
+
+            let opcode = self.bytecode[pc_usize];
+            if let Some((next_pc, advance)) = self.try_consume(pc_usize, opcode, at, c, char_len) {
+                let actual_advance = if advance > char_len { advance } else { char_len };
+                let cs = curr.capture_stride;
+                let src = slot_idx as usize * cs;
+                for j in 0..cs.min(tmp_caps.len()) {
+                    tmp_caps[j] = curr.slots[src + j];
+                }
+                let rs = curr.reg_stride;
+                if rs > 0 {
+                    let src_r = slot_idx as usize * rs;
+                    for j in 0..rs.min(tmp_regs.len()) {
+                        tmp_regs[j] = curr.regs[src_r + j];
+                    }
                 }
-            }
-
-            std::mem::swap(curr, next);
-            next.clear();
-            at += char_len;
 
-            if curr.threads.is_empty() {
-                break;
+                self.epsilon_closure(next, eps_stack, tmp_caps, tmp_regs, next_pc, at + actual_advance);
             }
         }
 
+        std::mem::swap(curr, next);
+        next.clear();
+        char_len
+    }
+
+    /// Match decision at the end of the input, after `settle` found none.
+    fn settle_final(&self, curr: &ThreadList, out: &mut Vec<Option<usize>>, has_candidate: bool) -> bool {
         // Final match check: prefer candidate (leftmost) over any final-position match
         for &(pc, slot_idx) in &curr.threads {
             if (pc as usize) < self.bytecode.len() && self.bytecode[pc as usize] == op::MATCH {
-                let caps = curr.get_caps(slot_idx).to_vec();
+                let caps = curr.get_caps(slot_idx);
                 // Check candidate first — leftmost wins
-                if let Some(ref prev) = candidate {
-                    if prev.get(0) <= caps.get(0) {
-                        return PikeResult::Match(candidate.unwrap());
-                    }
+                if has_candidate && out.get(0) <= caps.get(0) {
+                    return true;
                 }
-                return PikeResult::Match(caps);
+                out.clear();
+                out.extend_from_slice(caps);
+                return true;
             }
         }
 
-        match candidate {
-            Some(caps) => PikeResult::Match(caps),
-            None => PikeResult::NoMatch,
-        }
+        has_candidate
     }
 
     /// Epsilon closure: follow all non-consuming transitions, maintaining priority order.
@@ -836,6 +1009,9 @@ impl<'a> PikeVm<'a> {
                             }
                         }
 
+                        // Matches ending outside the accepted range are dropped
+                        op::MATCH if !self.match_ends.contains(&at) => {}
+
                         // Terminal (consuming) state: add to thread list with current captures
                         _ => {
                             threads.add(pc as u32, caps, regs);
@@ -854,8 +1030,7 @@ impl<'a> PikeVm<'a> {
         }
     }
 
-    fn try_consume(&self, pc: usize, opcode: u8, at: usize, c: u32) -> Option<(usize, usize)> {
-        let char_len = if c < 0x80 { 1 } else { char::from_u32(c).map(|ch| ch.len_utf8()).unwrap_or(1) };
+    fn try_consume(&self, pc: usize, opcode: u8, at: usize, c: u32, char_len: usize) -> Option<(usize, usize)> {
 
         match opcode {
             op::CHAR | op::CHAR_I => {
@@ -974,19 +1149,8 @@ impl<'a> PikeVm<'a> {
         if pos >= self.input_len { return (0, 0); }
         let b = self.input[pos];
         if b < 0x80 { return (b as u32, 1); }
-        // Decode UTF-8 without validating the entire remaining text (was O(N²))
-        let len = match b {
-            0xC0..=0xDF => 2,
-            0xE0..=0xEF => 3,
-            0xF0..=0xF7 => 4,
-            _ => return (b as u32, 1), // invalid UTF-8 lead byte
-        };
-        if pos + len > self.input_len { return (b as u32, 1); }
-        // Decode only the needed bytes
-        std::str::from_utf8(&self.input[pos..pos + len]).ok()
-            .and_then(|s| s.chars().next())
-            .map(|ch| (ch as u32, ch.len_utf8()))
-            .unwrap_or((b as u32, 1))
+        // Decode only the bytes of this char (validating the whole text was O(N²))
+        super::util::decode_utf8_at(self.input, pos, self.unicode_mode)
     }
 
     #[inline] fn read_u16(&self, pc: usize) -> u16 { u16::from_le_bytes([self.bytecode[pc], self.bytecode[pc+1]]) }
@@ -1097,13 +1261,20 @@ impl Scratch {
         }
     }
 
+    /// Limit the number of states the scratch's DFA caches.
+    pub(crate) fn set_dfa_cache_size(&mut self, max_states: usize) {
+        self.dfa.get_mut().set_max_states(max_states);
+    }
+
     /// Two-pass find: Wide NFA for fast match_end + bounded exec for correct semantics.
     /// Pass 1: Wide NFA scans at O(states/64)/byte — finds where a match ends.
     /// Pass 2: Bounded exec on input[..match_end] — correct greedy/lazy/assertion semantics.
     pub fn find_at(&mut self, vm: &PikeVm, wide_nfa: &super::bitvm::BitVmProgram, start_pos: usize) -> Option<(usize, usize)> {
         let match_end = wide_nfa.find_match_end_reuse(vm.input, start_pos, &mut self.wide_curr, &mut self.wide_next)?;
 
-        let bounded_vm = PikeVm::new(vm.bytecode, &vm.input[..match_end]);
+        // Bound the ends rather than the input, so assertions at the bound
+        // still see what follows
+        let bounded_vm = PikeVm::new(vm.bytecode, vm.input).match_ends(0..=match_end);
         match bounded_vm.exec_reuse(
             &mut self.curr, &mut self.next,
             &mut self.eps_stack, &mut self.tmp_caps, &mut self.tmp_regs,
@@ -1131,6 +1302,25 @@ impl Scratch {
             }
         }
     }
+
+    /// End of the earliest match at or after `start_pos`: the first position
+    /// where any match ends. One DFA pass, with no bounded exec after it.
+    /// The Wide NFA only rules matches out, and only on ASCII input, where
+    /// its bytes are the chars the pattern sees.
+    pub fn shortest_match_end(&mut self, vm: &PikeVm, wide_nfa: Option<&super::bitvm::BitVmProgram>, start_pos: usize) -> Option<usize> {
+        if let Some(wide_nfa) = wide_nfa {
+            if vm.input[start_pos..].is_ascii() {
+                wide_nfa.earliest_match_end_reuse(vm.input, start_pos, &mut self.wide_curr, &mut self.wide_next)?;
+            }
+        }
+        let mut dfa = self.dfa.borrow_mut();
+        PikeScanner::find_match_cached_inner(
+            vm, &mut dfa,
+            &mut self.dfa_curr_states, &mut self.dfa_next_states,
+            &mut self.dfa_seen, &mut self.dfa_eps_stack,
+            start_pos, true,
+        )
+    }
 }
 
 /// DFA cache storage: either owned by the scanner or borrowed from a Regex.
@@ -1139,6 +1329,92 @@ enum DfaStorage<'a> {
     Borrowed(&'a RefCell<LazyDfa>),
 }
 
+/// Pike VM threads kept between calls, for input that arrives in pieces.
+/// Each call may pass a VM over a different buffer, as long as positions
+/// keep their meaning (see `rebase`).
+pub(crate) struct Threads {
+    curr: ThreadList,
+    next: ThreadList,
+    eps_stack: Vec<EpsFrame>,
+    tmp_caps: Vec<Option<usize>>,
+    tmp_regs: Vec<usize>,
+    /// Capture slots of the leftmost match found so far
+    candidate: Vec<Option<usize>>,
+    has_candidate: bool,
+}
+
+impl Threads {
+    pub(crate) fn new(vm: &PikeVm) -> Self {
+        Threads {
+            curr: ThreadList::new(vm.num_pcs, vm.capture_count, vm.register_count),
+            next: ThreadList::new(vm.num_pcs, vm.capture_count, vm.register_count),
+            eps_stack: Vec::with_capacity(64),
+            tmp_caps: vec![None; vm.capture_count * 2],
+            tmp_regs: vec![0; vm.register_count],
+            candidate: Vec::new(),
+            has_candidate: false,
+        }
+    }
+
+    /// Drop all threads and start a search at `at`.
+    pub(crate) fn start(&mut self, vm: &PikeVm, at: usize) {
+        self.curr.clear();
+        self.next.clear();
+        self.tmp_caps.fill(None);
+        self.tmp_regs.fill(0);
+        self.candidate.clear();
+        self.has_candidate = false;
+        vm.epsilon_closure(&mut self.curr, &mut self.eps_stack, &mut self.tmp_caps, &mut self.tmp_regs, RE_HEADER_LEN, at);
+    }
+
+    /// Update the candidate; true once it is the final match.
+    pub(crate) fn settle(&mut self, vm: &PikeVm) -> bool {
+        vm.settle(&mut self.curr, &mut self.candidate, &mut self.has_candidate)
+    }
+
+    /// Consume the char at `at`. Returns its length.
+    pub(crate) fn advance(&mut self, vm: &PikeVm, at: usize) -> usize {
+        vm.advance(&mut self.curr, &mut self.next, &mut self.eps_stack, &mut self.tmp_caps, &mut self.tmp_regs, at)
+    }
+
+    /// Decide at the end of the input; true if there is a match.
+    pub(crate) fn settle_final(&mut self, vm: &PikeVm) -> bool {
+        vm.settle_final(&self.curr, &mut self.candidate, self.has_candidate)
+    }
+
+    /// Whether no thread is left.
+    pub(crate) fn is_dead(&self) -> bool {
+        self.curr.threads.is_empty()
+    }
+
+    /// Capture slots of the candidate, once `settle` or `settle_final`
+    /// returned true.
+    pub(crate) fn candidate(&self) -> &[Option<usize>] {
+        &self.candidate
+    }
+
+    /// Earliest input position a live thread or the candidate refers to.
+    pub(crate) fn earliest_position(&self) -> Option<usize> {
+        let live = self.curr.threads.iter().flat_map(|&(_, slot_idx)| self.curr.get_caps(slot_idx));
+        live.chain(&self.candidate).filter_map(|&pos| pos).min()
+    }
+
+    /// Shift every position down by `by`, after the first `by` bytes of
+    /// the input were dropped. No position may be below `by`.
+    pub(crate) fn rebase(&mut self, by: usize) {
+        let cs = self.curr.capture_stride;
+        for &(_, slot_idx) in &self.curr.threads {
+            let base = slot_idx as usize * cs;
+            for pos in self.curr.slots[base..base + cs].iter_mut().flatten() {
+                *pos -= by;
+            }
+        }
+        for pos in self.candidate.iter_mut().flatten() {
+            *pos -= by;
+        }
+    }
+}
+
 /// Persistent scanner with warm DFA cache for repeated matching.
 /// Holds all state needed to scan efficiently across multiple find_next calls.
 /// Reuses exec buffers (ThreadLists) across calls to avoid per-call allocation.
@@ -1179,6 +1455,14 @@ impl<'a> PikeScanner<'a> {
         }
     }
 
+    /// Limit the number of states the scanner's DFA caches.
+    pub fn set_dfa_cache_size(&mut self, max_states: usize) {
+        match &mut self.dfa {
+            DfaStorage::Owned(dfa) => dfa.set_max_states(max_states),
+            DfaStorage::Borrowed(cell) => cell.borrow_mut().set_max_states(max_states),
+        }
+    }
+
     /// Create a scanner that borrows a shared DFA cache from a Regex.
     /// The DFA state map persists across calls, giving O(1) per byte on warm cache.
     pub fn with_cache(bytecode: &'a [u8], input: &'a [u8], cache: &'a RefCell<LazyDfa>) -> Self {
@@ -1209,21 +1493,26 @@ impl<'a> PikeScanner<'a> {
     ///   2. Bounded exec on input[..match_end] → finds match_start
     ///      Only processes ~match_length bytes, not the entire remaining text.
     pub fn find_next(&mut self, start_pos: usize) -> Option<(usize, usize)> {
+        let caps = self.captures_next(start_pos)?;
+        let s = caps.get(0).copied().flatten()?;
+        let e = caps.get(1).copied().flatten()?;
+        Some((s, e))
+    }
+
+    /// Like `find_next`, but returns all capture slots of the match
+    /// (start/end pairs, `None` for groups that did not participate).
+    pub fn captures_next(&mut self, start_pos: usize) -> Option<Vec<Option<usize>>> {
         // Pass 1: DFA scan for match_end (fast)
         let match_end = self.find_match_cached(start_pos)?;
 
-        // Pass 2: bounded exec on input[..match_end].
-        let bounded_vm = PikeVm::new(self.vm.bytecode, &self.vm.input[..match_end]);
+        // Pass 2: exec bounded to match ends up to match_end.
+        let bounded_vm = PikeVm::new(self.vm.bytecode, self.vm.input).match_ends(0..=match_end);
         match bounded_vm.exec_reuse(
             &mut self.exec_curr, &mut self.exec_next,
             &mut self.exec_eps_stack, &mut self.exec_tmp_caps, &mut self.exec_tmp_regs,
             start_pos,
         ) {
-            PikeResult::Match(caps) => {
-                let s = caps.get(0).copied().flatten()?;
-                let e = caps.get(1).copied().flatten()?;
-                Some((s, e))
-            }
+            PikeResult::Match(caps) => Some(caps),
             PikeResult::NoMatch => {
                 // DFA said match exists but bounded exec disagrees.
                 // Fall back to full exec (handles edge cases with assertions).
@@ -1232,11 +1521,7 @@ impl<'a> PikeScanner<'a> {
                     &mut self.exec_eps_stack, &mut self.exec_tmp_caps, &mut self.exec_tmp_regs,
                     start_pos,
                 ) {
-                    PikeResult::Match(caps) => {
-                        let s = caps.get(0).copied().flatten()?;
-                        let e = caps.get(1).copied().flatten()?;
-                        Some((s, e))
-                    }
+                    PikeResult::Match(caps) => Some(caps),
                     PikeResult::NoMatch => None,
                 }
             }
@@ -1250,11 +1535,11 @@ impl<'a> PikeScanner<'a> {
     /// For non-ASCII-heavy text or Unicode mode, uses exec_reuse directly
     /// (the DFA's per-non-ASCII-byte overhead makes it slower than raw exec).
     pub fn count_all(&mut self) -> usize {
-        // Skip DFA for word boundary patterns (can't cache due to 1-byte lookahead)
+        // Skip DFA for patterns whose transitions can't be cached (`\b`, multiline `$`)
         // and for non-ASCII Unicode text (DFA has per-byte overhead).
         let has_wb = match &self.dfa {
-            DfaStorage::Owned(dfa) => dfa.has_word_boundary,
-            DfaStorage::Borrowed(cell) => cell.borrow().has_word_boundary,
+            DfaStorage::Owned(dfa) => dfa.context_dependent,
+            DfaStorage::Borrowed(cell) => cell.borrow().context_dependent,
         };
         let sample = &self.vm.input[..self.vm.input_len.min(256)];
         let non_ascii = sample.iter().filter(|&&b| b >= 128).count();
@@ -1306,7 +1591,7 @@ impl<'a> PikeScanner<'a> {
                     &self.vm, dfa,
                     &mut self.curr_states, &mut self.next_states,
                     &mut self.seen, &mut self.eps_stack,
-                    start_pos,
+                    start_pos, false,
                 )
             }
             DfaStorage::Borrowed(cell) => {
@@ -1315,13 +1600,15 @@ impl<'a> PikeScanner<'a> {
                     &self.vm, &mut dfa,
                     &mut self.curr_states, &mut self.next_states,
                     &mut self.seen, &mut self.eps_stack,
-                    start_pos,
+                    start_pos, false,
                 )
             }
         }
     }
 
     /// Core DFA-cached scan logic, operating on a mutable DFA reference.
+    /// With `earliest` the scan stops at the first position where any match
+    /// ends, rather than at the end of the leftmost-first one.
     pub(crate) fn find_match_cached_inner(
         vm: &PikeVm<'a>,
         dfa: &mut LazyDfa,
@@ -1330,6 +1617,7 @@ impl<'a> PikeScanner<'a> {
         seen: &mut Vec<bool>,
         eps_stack: &mut Vec<(usize, bool)>,
         start_pos: usize,
+        earliest: bool,
     ) -> Option<usize> {
         curr_states.clear();
         seen.fill(false);
@@ -1353,6 +1641,9 @@ impl<'a> PikeScanner<'a> {
         let mut prev_is_word = init_prev_word;
         let mut current_dfa_state = match dfa.get_or_create_state(curr_states, init_has_match, prev_is_word) {
             Some(id) => id,
+            None if earliest => {
+                return Self::earliest_match_end_uncached(vm, curr_states, next_states, seen, eps_stack, start_pos);
+            }
             None => {
                 // DFA cache full — fall back to uncached Pike VM
                 return Self::find_match_uncached_vm(vm, start_pos);
@@ -1362,14 +1653,23 @@ impl<'a> PikeScanner<'a> {
         let mut at = start_pos;
         let mut best_end: Option<usize> = None;
         let initial_dfa_state = current_dfa_state;
+        let mut ticker = Ticker::new();
+        if interrupt::fired() {
+            return None;
+        }
 
         #[cfg(debug_assertions)]
         eprintln!("[DFA] start: pos={} initial_states={} dfa_states={} num_classes={}", start_pos, curr_states.len(), dfa.next_id, dfa.num_classes);
 
         loop {
-            // Check for match
-            if dfa.state_has_match(current_dfa_state) {
+            // Check for match, resuming threads parked at `$` at the end
+            let at_end_match = at == vm.input_len
+                && vm.matches_at_end(dfa.get_state_set(current_dfa_state), next_states, seen, eps_stack);
+            if dfa.state_has_match(current_dfa_state) || at_end_match {
                 best_end = Some(at);
+                if earliest {
+                    return best_end;
+                }
                 // Check if MATCH is highest priority (first in state set)
                 let states = dfa.get_state_set(current_dfa_state);
                 if let Some(&first_pc) = states.first() {
@@ -1379,18 +1679,20 @@ impl<'a> PikeScanner<'a> {
                         return best_end; // Highest priority match → immediate win
                     }
                 }
-            } else if best_end.is_some() {
-                // MATCH was present but is now gone — the match has ended.
+            } else if best_end.is_some() && dfa.get_state_set(current_dfa_state).is_empty() {
+                // Every thread that outranked the match has died, so the
+                // match found last is the leftmost-first one.
                 #[cfg(debug_assertions)]
                 eprintln!("[DFA] match ended at={} best_end={:?} state={}", at, best_end, current_dfa_state);
-                // Match ended and we've returned to the initial prefix-loop state.
-                // The match is complete — return it. This prevents the DFA from
-                // extending through the prefix loop to merge separate matches.
                 return best_end;
             }
 
             if at >= vm.input_len { break; }
 
+            if ticker.tick() {
+                return None;
+            }
+
             let b = vm.input[at];
             if b >= 128 {
                 // Non-ASCII: multi-byte UTF-8 char. Can't use byte-class DFA cache
@@ -1408,7 +1710,9 @@ impl<'a> PikeScanner<'a> {
                     let pc_usize = pc as usize;
                     if pc_usize >= vm.bytecode.len() { continue; }
                     let opcode = vm.bytecode[pc_usize];
-                    if let Some((next_pc, _)) = vm.try_consume(pc_usize, opcode, at, c) {
+                    // Threads below a match never win over it
+                    if opcode == op::MATCH { break; }
+                    if let Some((next_pc, _)) = vm.try_consume(pc_usize, opcode, at, c, char_len) {
                         vm.eps_closure_fast(
                             next_states, seen, eps_stack,
                             next_pc, at + char_len,
@@ -1431,6 +1735,9 @@ impl<'a> PikeScanner<'a> {
                     Some(next_id) => {
                         current_dfa_state = next_id;
                     }
+                    None if earliest => {
+                        return Self::earliest_match_end_uncached(vm, next_states, curr_states, seen, eps_stack, at + char_len);
+                    }
                     None => {
                         return best_end.or_else(|| Self::find_match_uncached_vm(vm, at));
                     }
@@ -1468,7 +1775,9 @@ impl<'a> PikeScanner<'a> {
                 let pc_usize = pc as usize;
                 if pc_usize >= vm.bytecode.len() { continue; }
                 let opcode = vm.bytecode[pc_usize];
-                if let Some((next_pc, _)) = vm.try_consume(pc_usize, opcode, at, c) {
+                // Threads below a match never win over it
+                if opcode == op::MATCH { break; }
+                if let Some((next_pc, _)) = vm.try_consume(pc_usize, opcode, at, c, char_len) {
                     vm.eps_closure_fast(
                         next_states, seen, eps_stack,
                         next_pc, at + char_len,
@@ -1484,9 +1793,12 @@ impl<'a> PikeScanner<'a> {
 
             match dfa.get_or_create_state(next_states, next_has_match, prev_is_word) {
                 Some(next_id) => {
-                    dfa.store(current_dfa_state, b, next_id, dfa.has_word_boundary);
+                    dfa.store(current_dfa_state, b, next_id, dfa.context_dependent);
                     current_dfa_state = next_id;
                 }
+                None if earliest => {
+                    return Self::earliest_match_end_uncached(vm, next_states, curr_states, seen, eps_stack, at + char_len);
+                }
                 None => {
                     // DFA cache full
                     return best_end.or_else(|| Self::find_match_uncached_vm(vm, at));
@@ -1504,6 +1816,42 @@ impl<'a> PikeScanner<'a> {
         best_end
     }
 
+    /// Earliest match end by stepping the NFA state sets directly, for when
+    /// the DFA cache is full. `states` holds the threads at `at`.
+    fn earliest_match_end_uncached(
+        vm: &PikeVm<'a>,
+        states: &mut Vec<u32>,
+        next_states: &mut Vec<u32>,
+        seen: &mut [bool],
+        eps_stack: &mut Vec<(usize, bool)>,
+        mut at: usize,
+    ) -> Option<usize> {
+        let mut ticker = Ticker::new();
+        loop {
+            let at_end_match = at == vm.input_len && vm.matches_at_end(states, next_states, seen, eps_stack);
+            if at_end_match || states.iter().any(|&pc| vm.bytecode.get(pc as usize) == Some(&op::MATCH)) {
+                return Some(at);
+            }
+            if at >= vm.input_len || states.is_empty() || ticker.tick() {
+                return None;
+            }
+            let (c, char_len) = vm.next_char(at);
+            if char_len == 0 {
+                return None;
+            }
+            next_states.clear();
+            seen.fill(false);
+            for &pc in states.iter() {
+                let pc = pc as usize;
+                if let Some((next_pc, _)) = vm.bytecode.get(pc).and_then(|&opcode| vm.try_consume(pc, opcode, at, c, char_len)) {
+                    vm.eps_closure_fast(next_states, seen, eps_stack, next_pc, at + char_len);
+                }
+            }
+            std::mem::swap(states, next_states);
+            at += char_len;
+        }
+    }
+
     /// Fallback uncached scan (for non-ASCII or DFA overflow)
     fn find_match_uncached_vm(vm: &PikeVm<'a>, start_pos: usize) -> Option<usize> {
         // This is a static method (no &self) — can't reuse PikeScanner buffers.
@@ -1536,22 +1884,13 @@ impl<'a> PikeScanner<'a> {
     // Unicode mode: find the start of the UTF-8 char containing this byte.
     // pos might be at a continuation byte (0x80..0xBF) — scan back for lead byte.
     let mut start = pos;
-    while start > 0 && (input[start] & 0xC0) == 0x80 {
+    while start > 0 && pos - start < 3 && (input[start] & 0xC0) == 0x80 {
         start -= 1;
-        if pos - start >= 4 { return false; }
-    }
-    let lead = input[start];
-    let len = match lead {
-        0xC0..=0xDF => 2,
-        0xE0..=0xEF => 3,
-        0xF0..=0xF7 => 4,
-        _ => return false,
-    };
-    if start + len > input.len() { return false; }
-    std::str::from_utf8(&input[start..start + len]).ok()
-        .and_then(|s| s.chars().next())
-        .map(|c| c.is_alphanumeric() || c == '_')
-        .unwrap_or(false)
+    }
+    let (c, len) = super::util::decode_utf8_at(input, start, true);
+    // A byte not covered by the char at `start` is invalid UTF-8 on its own
+    let c = if start + len > pos { c } else { 0xFFFD };
+    char::from_u32(c).map(|c| c.is_alphanumeric() || c == '_').unwrap_or(false)
 }
 
 /// Detect if a Range16 bytecode is the \w character class pattern
@@ -1608,6 +1947,14 @@ mod tests {
     #[test] fn test_find() { assert_eq!(pike_find("world", "hello world"), Some((6, 11))); }
     #[test] fn test_bounded() { assert!(pike_match("[A-Za-z]{8,13}", "abcdefghij")); }
 
+    #[test]
+    fn test_match_beats_lower_priority_threads() {
+        // The first alternative matches, so the longer match through the
+        // second one loses even though it ends later
+        assert_eq!(pike_find("(?:a|ab)c?", "abc"), Some((0, 1)));
+        assert_eq!(pike_find("(aa|aabaac|ba|b|c)*", "aabaac"), Some((0, 4)));
+    }
+
     #[test]
     fn test_greedy_star() {
         // Greedy: a* on "aaa" → matches all 3
@@ -1646,6 +1993,61 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_dfa_cache_with_line_anchors() {
+        let texts = ["end x end", "ending\nend", "send", "end\nx", "caf\u{e9} end\nend"];
+        let cases: [(&str, [&[(usize, usize)]; 5]); 3] = [
+            ("end$", [&[(6, 9)], &[(7, 10)], &[(1, 4)], &[], &[(10, 13)]]),
+            (r"\w+$", [&[(6, 9)], &[(7, 10)], &[(0, 4)], &[(4, 5)], &[(10, 13)]]),
+            (r"(?m)^e\w", [&[(0, 2)], &[(0, 2), (7, 9)], &[], &[(0, 2)], &[(10, 12)]]),
+        ];
+        for (pattern, expected) in cases {
+            let re = crate::regex::Regex::new(pattern).unwrap();
+            for (text, expected) in texts.into_iter().zip(expected) {
+                let found: Vec<_> = re.find_iter(text).map(|m| (m.start, m.end)).collect();
+                assert_eq!(found, expected, "{} {:?}", pattern, text);
+            }
+        }
+    }
+
+    #[test]
+    fn test_dfa_line_anchors_on_multi_line_input() {
+        // The state after the first line used to be reused at the end of
+        // the second, so none of these found anything past the first line
+        let text = "the dog keeps barking\nstill running";
+        let cases: [(&str, &[(usize, usize)]); 4] = [
+            (r"\w+$", &[(28, 35)]),
+            (r"(?m)[a-z]+ing$", &[(14, 21), (28, 35)]),
+            (r"(?:dog|fox|still) [a-z ]+$", &[(22, 35)]),
+            (r"(?m)^\w+", &[(0, 3), (22, 27)]),
+        ];
+        for (pattern, expected) in cases {
+            let re = crate::regex::Regex::new(pattern).unwrap();
+            let found: Vec<_> = re.find_iter(text).map(|m| (m.start, m.end)).collect();
+            assert_eq!(found, expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_dfa_scan_runs_past_first_match_end() {
+        // Higher priority threads can outlive a match the DFA already saw
+        for (pattern, text, expected) in [
+            ("(ab)+", "x ababab ab", &[(2, 8), (9, 11)][..]),
+            ("a|ab", "abab", &[(0, 1), (2, 3)]),
+            ("(?:ab|a)c?", "abcac", &[(0, 3), (3, 5)]),
+        ] {
+            let bc = compiler::compile_regex(pattern, Flags::empty()).unwrap();
+            let mut scanner = PikeScanner::new(&bc, text.as_bytes());
+            let mut found = Vec::new();
+            let mut pos = 0;
+            while let Some((s, e)) = scanner.find_next(pos) {
+                found.push((s, e));
+                pos = e;
+            }
+            assert_eq!(found, expected, "{}", pattern);
+        }
+    }
+
     #[test]
     fn test_no_catastrophic_backtracking() {
         // Exponential in backtracker, linear in Pike VM
@@ -0,0 +1,283 @@
+//! Push-based matching for input that arrives in pieces.
+//!
+//! A `PushMatcher` keeps Pike VM threads between `feed` calls. Each call
+//! runs them over the new bytes only and reports matches as soon as no
+//! further input can change them. Only the bytes from the start of the
+//! leftmost match still in progress (plus one char of context) are kept.
+//!
+//! A thread only steps past a char once the next char is complete, since
+//! `$` and `\b` after it look at that char.
+
+use std::collections::VecDeque;
+
+use super::{pikevm, utf8_char_len, Engine, Error, Regex, Result, StreamMatch};
+
+/// Bytes kept before the search position for `\b` and multiline `^`.
+const CONTEXT: usize = 4;
+
+/// What a `PushMatcher` learned from the input so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PushEvent {
+    /// A match that no further input can change
+    Confirmed(StreamMatch),
+    /// No further match is possible; the input from `offset` on is ignored
+    Rejected { offset: u64 },
+}
+
+/// Matcher fed input piece by piece, created by `bytes::Regex::push_matcher`.
+///
+/// ```
+/// use quickjs_regex::bytes::Regex;
+/// use quickjs_regex::{PushEvent, StreamMatch};
+///
+/// let re = Regex::new(r"\d+;").unwrap();
+/// let mut matcher = re.push_matcher().unwrap();
+/// assert_eq!(matcher.feed(b"len=12").count(), 0);
+/// let events: Vec<_> = matcher.feed(b"3; rest").collect();
+/// assert_eq!(events, vec![PushEvent::Confirmed(StreamMatch { start: 4, end: 8 })]);
+/// let events: Vec<_> = matcher.finish().collect();
+/// assert_eq!(events, vec![PushEvent::Rejected { offset: 13 }]);
+/// ```
+pub struct PushMatcher<'r> {
+    regex: &'r Regex,
+    threads: pikevm::Threads,
+    /// Input fed so far, from stream offset `base` on
+    window: Vec<u8>,
+    base: u64,
+    /// Window position the threads are at
+    at: usize,
+    /// Whether the threads run a search (else one starts at `at`)
+    started: bool,
+    last_was_empty: bool,
+    done: bool,
+    events: VecDeque<PushEvent>,
+}
+
+impl<'r> PushMatcher<'r> {
+    pub(crate) fn new(regex: &'r Regex) -> Result<Self> {
+        if !regex.supports_engine(Engine::PikeVm) {
+            return Err(Error::UnsupportedEngine(Engine::PikeVm));
+        }
+        let threads = pikevm::Threads::new(&pikevm::PikeVm::new(regex.bytecode_slice(), &[]));
+        Ok(PushMatcher {
+            regex,
+            threads,
+            window: Vec::new(),
+            base: 0,
+            at: 0,
+            started: false,
+            last_was_empty: false,
+            done: false,
+            events: VecDeque::new(),
+        })
+    }
+
+    /// Feed the next piece of input and return what it decided.
+    pub fn feed(&mut self, data: &[u8]) -> impl Iterator<Item = PushEvent> + '_ {
+        if !self.done {
+            self.window.extend_from_slice(data);
+            self.run(false);
+            self.trim();
+        }
+        self.events.drain(..)
+    }
+
+    /// Signal the end of the input and return the remaining events. The
+    /// last one is always `Rejected`, at the end of the input.
+    pub fn finish(&mut self) -> impl Iterator<Item = PushEvent> + '_ {
+        if !self.done {
+            self.run(true);
+        }
+        self.events.drain(..)
+    }
+
+    /// Whether a `Rejected` event was returned; further input is ignored.
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+
+    /// Number of input bytes held for matches still in progress.
+    pub fn buffered_len(&self) -> usize {
+        self.window.len()
+    }
+
+    /// Step the threads as far as the input allows.
+    fn run(&mut self, finishing: bool) {
+        // The VM borrows the window while events are recorded
+        let window = std::mem::take(&mut self.window);
+        self.run_on(&window, finishing);
+        self.window = window;
+        if self.done {
+            self.window = Vec::new();
+        }
+    }
+
+    fn run_on(&mut self, window: &[u8], finishing: bool) {
+        let vm = pikevm::PikeVm::new(self.regex.bytecode_slice(), window);
+        // The bytes decide `pos` (and the char there is whole)
+        let known = |pos: usize| finishing || has_char(window, pos);
+        loop {
+            if !self.started {
+                let mut start = self.at;
+                if self.last_was_empty {
+                    if start >= window.len() && finishing {
+                        return self.reject();
+                    }
+                    if !known(start) {
+                        return;
+                    }
+                    start += utf8_char_len(window, start);
+                }
+                if !known(start) {
+                    return;
+                }
+                self.threads.start(&vm, start);
+                self.at = start;
+                self.started = true;
+                self.last_was_empty = false;
+            }
+
+            if self.threads.settle(&vm) {
+                self.confirm();
+                continue;
+            }
+            if self.threads.is_dead() || (finishing && self.at >= window.len()) {
+                if self.threads.settle_final(&vm) {
+                    self.confirm();
+                    continue;
+                }
+                return self.reject();
+            }
+            if !known(self.at) || !known(self.at + sequence_len(window[self.at])) {
+                return;
+            }
+            self.at += self.threads.advance(&vm, self.at);
+        }
+    }
+
+    /// Report the candidate and start the next search at its end.
+    fn confirm(&mut self) {
+        let (start, end) = match self.threads.candidate() {
+            [Some(start), Some(end), ..] => (*start, *end),
+            _ => unreachable!("a match always sets group 0"),
+        };
+        self.events.push_back(PushEvent::Confirmed(StreamMatch {
+            start: self.base + start as u64,
+            end: self.base + end as u64,
+        }));
+        self.at = end;
+        self.last_was_empty = start == end;
+        self.started = false;
+    }
+
+    fn reject(&mut self) {
+        self.events.push_back(PushEvent::Rejected { offset: self.base + self.at as u64 });
+        self.done = true;
+    }
+
+    /// Drop input no thread refers to any more.
+    fn trim(&mut self) {
+        if self.done {
+            return;
+        }
+        let mut needed = self.at;
+        if self.started {
+            needed = needed.min(self.threads.earliest_position().unwrap_or(needed));
+        }
+        let keep_from = needed.saturating_sub(CONTEXT);
+        if keep_from == 0 {
+            return;
+        }
+        self.window.drain(..keep_from);
+        self.base += keep_from as u64;
+        self.at -= keep_from;
+        if self.started {
+            self.threads.rebase(keep_from);
+        }
+    }
+}
+
+/// Whether `window` holds all of the char at `pos`.
+fn has_char(window: &[u8], pos: usize) -> bool {
+    pos < window.len() && pos + sequence_len(window[pos]) <= window.len()
+}
+
+/// Length of the UTF-8 sequence `lead` starts (1 for any other byte).
+fn sequence_len(lead: u8) -> usize {
+    match lead {
+        0xC0..=0xDF => 2,
+        0xE0..=0xEF => 3,
+        0xF0..=0xF7 => 4,
+        _ => 1,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::bytes::Regex;
+    use crate::regex::{Flags, PushEvent, StreamMatch};
+
+    fn feed_in_pieces(re: &Regex, hay: &[u8], piece: usize) -> Vec<PushEvent> {
+        let mut matcher = re.push_matcher().unwrap();
+        let mut events = Vec::new();
+        for chunk in hay.chunks(piece) {
+            events.extend(matcher.feed(chunk));
+        }
+        events.extend(matcher.finish());
+        events
+    }
+
+    #[test]
+    fn test_pieces_match_whole_input() {
+        let hay = "line 12 foobar word caf\u{e9} \u{1F600}x end\nfoo 7 wordy word\nend".as_bytes();
+        let patterns = [r"\d+", "foo|foobar", r"\bword\b", "x*", "^line", "(?:^|\n)foo", r"\S+\s", "(a|b)+c|.", "end$"];
+        for pattern in patterns {
+            let re = Regex::new(pattern).unwrap();
+            let mut expected: Vec<_> = re.find_iter(hay)
+                .map(|m| PushEvent::Confirmed(StreamMatch { start: m.start as u64, end: m.end as u64 }))
+                .collect();
+            expected.push(PushEvent::Rejected { offset: hay.len() as u64 });
+            for piece in [1, 2, 5, 64] {
+                assert_eq!(feed_in_pieces(&re, hay, piece), expected, "{} piece={}", pattern, piece);
+            }
+        }
+    }
+
+    #[test]
+    fn test_confirms_before_finish() {
+        let re = Regex::with_flags(r"GET (\S+) HTTP/1\.[01]\r\n", Flags::parse("y").unwrap()).unwrap();
+        let mut matcher = re.push_matcher().unwrap();
+        for &b in b"GET /index HTTP/1.1\r".iter() {
+            assert_eq!(matcher.feed(&[b]).count(), 0);
+        }
+        let events: Vec<_> = matcher.feed(b"\nGET").collect();
+        assert_eq!(events, vec![PushEvent::Confirmed(StreamMatch { start: 0, end: 21 })]);
+        // A sticky pattern is rejected once the input can't match, which
+        // takes one char past the mismatch
+        assert_eq!(matcher.feed(b"X").count(), 0);
+        let events: Vec<_> = matcher.feed(b" ").collect();
+        assert_eq!(events, vec![PushEvent::Rejected { offset: 25 }]);
+        assert!(matcher.is_done());
+        assert_eq!(matcher.feed(b"GET / HTTP/1.0\r\n").count(), 0);
+    }
+
+    #[test]
+    fn test_keeps_only_unfinished_input() {
+        let re = Regex::new(r"<\d+>").unwrap();
+        let mut matcher = re.push_matcher().unwrap();
+        let mut confirmed = 0;
+        for _ in 0..1000 {
+            confirmed += matcher.feed(b"some text <42> more ").count();
+            assert!(matcher.buffered_len() < 32);
+        }
+        assert_eq!(confirmed, 1000);
+        assert_eq!(matcher.feed(b"<1234").count(), 0);
+        assert!(matcher.buffered_len() >= 5);
+    }
+
+    #[test]
+    fn test_unsupported_pattern() {
+        let re = Regex::new(r"(a)\1").unwrap();
+        assert!(re.push_matcher().is_err());
+    }
+}
@@ -0,0 +1,315 @@
+//! `String.prototype.replace`-style substitution.
+//!
+//! Replacement strings use the ECMAScript GetSubstitution syntax:
+//!
+//! | Template   | Inserts                                         |
+//! |------------|-------------------------------------------------|
+//! | `$$`       | a literal `$`                                   |
+//! | `$&`       | the matched text                                |
+//! | `` $` ``   | the text before the match                       |
+//! | `$'`       | the text after the match                        |
+//! | `$n`/`$nn` | capture group `n` (1-99), empty if unmatched    |
+//! | `$<name>`  | named group `name`, empty if unmatched          |
+//!
+//! Anything else, including `$0` and references to groups the pattern does
+//! not have, is copied through literally.
+//!
+//! A closure taking `(&Captures, &mut String)` can be used instead of a
+//! template, like a JavaScript replacer function. The `Captures` carries
+//! everything the JS callback receives: the match, each group, the offset,
+//! the whole input and the named groups.
+
+use std::borrow::Cow;
+
+use super::{Captures, Regex};
+
+/// Produces the replacement text for each match.
+///
+/// Implemented for substitution templates (`&str`, `String`) and for
+/// closures `FnMut(&Captures, &mut String)`.
+pub trait Replacer {
+    /// Append the replacement for one match to `dst`.
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String);
+
+    /// Return the replacement if it is the same for every match, so capture
+    /// extraction can be skipped.
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        None
+    }
+}
+
+impl Replacer for &str {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        caps.expand(self, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        (!self.contains('$')).then_some(Cow::Borrowed(*self))
+    }
+}
+
+impl Replacer for &String {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        self.as_str().replace_append(caps, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        (!self.contains('$')).then_some(Cow::Borrowed(self.as_str()))
+    }
+}
+
+impl Replacer for String {
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        self.as_str().replace_append(caps, dst);
+    }
+
+    fn no_expansion(&mut self) -> Option<Cow<'_, str>> {
+        (!self.contains('$')).then_some(Cow::Borrowed(self.as_str()))
+    }
+}
+
+impl<F> Replacer for F
+where
+    F: FnMut(&Captures<'_>, &mut String),
+{
+    fn replace_append(&mut self, caps: &Captures<'_>, dst: &mut String) {
+        self(caps, dst);
+    }
+}
+
+impl Regex {
+    /// Replace matches, like JavaScript's `String.prototype.replace`: every
+    /// match if the `g` flag is set, otherwise only the first.
+    pub fn replace<R: Replacer>(&self, text: &str, replacement: R) -> String {
+        let limit = if self.flags().is_global() { 0 } else { 1 };
+        self.replace_n(text, limit, replacement)
+    }
+
+    /// Replace every non-overlapping match, regardless of the `g` flag.
+    pub fn replace_all<R: Replacer>(&self, text: &str, replacement: R) -> String {
+        self.replace_n(text, 0, replacement)
+    }
+
+    /// Replace at most `limit` matches, left to right. A limit of 0 replaces all.
+    pub fn replace_n<R: Replacer>(&self, text: &str, limit: usize, mut replacement: R) -> String {
+        let mut out = String::with_capacity(text.len());
+        let mut last = 0;
+
+        if let Some(fixed) = replacement.no_expansion() {
+            for (n, m) in self.find_iter(text).enumerate() {
+                if limit != 0 && n >= limit {
+                    break;
+                }
+                out.push_str(&text[last..m.start]);
+                out.push_str(&fixed);
+                last = m.end;
+            }
+            out.push_str(&text[last..]);
+            return out;
+        }
+
+        // Each match is found once, together with its groups
+        for (n, caps) in self.captures_iter(text).enumerate() {
+            if limit != 0 && n >= limit {
+                break;
+            }
+            let m = caps.get(0).expect("group 0 of a match is set");
+            out.push_str(&text[last..m.start]);
+            replacement.replace_append(&caps, &mut out);
+            last = m.end;
+        }
+
+        out.push_str(&text[last..]);
+        out
+    }
+}
+
+impl Captures<'_> {
+    /// Append the expansion of a substitution template for this match
+    /// (ECMAScript GetSubstitution).
+    pub fn expand(&self, template: &str, dst: &mut String) {
+        let text = &*self.text;
+        let (start, end) = self.groups.first().copied().flatten().unwrap_or((0, 0));
+        let group_count = self.groups.len().saturating_sub(1);
+        let group = |i: usize| self.get_str(i).unwrap_or("");
+        let has_names = self.names.iter().any(Option::is_some);
+
+        let bytes = template.as_bytes();
+        let mut literal_start = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            if bytes[i] != b'$' || i + 1 >= bytes.len() {
+                i += 1;
+                continue;
+            }
+            let (insert, consumed) = match bytes[i + 1] {
+                b'$' => ("$", 2),
+                b'&' => (&text[start..end], 2),
+                b'`' => (&text[..start], 2),
+                b'\'' => (&text[end..], 2),
+                b'0'..=b'9' => {
+                    let one = (bytes[i + 1] - b'0') as usize;
+                    let two = bytes.get(i + 2)
+                        .filter(|b| b.is_ascii_digit())
+                        .map(|b| one * 10 + (b - b'0') as usize);
+                    // Prefer the two-digit reference when it names an existing group
+                    match two {
+                        Some(nn) if (1..=group_count).contains(&nn) => (group(nn), 3),
+                        _ if (1..=group_count).contains(&one) => (group(one), 2),
+                        _ => {
+                            i += 2;
+                            continue;
+                        }
+                    }
+                }
+                b'<' if has_names => {
+                    match template[i + 2..].find('>') {
+                        Some(len) => {
+                            let name = &template[i + 2..i + 2 + len];
+                            (self.name_str(name).unwrap_or(""), len + 3)
+                        }
+                        None => {
+                            i += 2;
+                            continue;
+                        }
+                    }
+                }
+                _ => {
+                    i += 1;
+                    continue;
+                }
+            };
+            dst.push_str(&template[literal_start..i]);
+            dst.push_str(insert);
+            i += consumed;
+            literal_start = i;
+        }
+        dst.push_str(&template[literal_start..]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Captures, Flags, Regex};
+
+    fn global(pattern: &str) -> Regex {
+        Regex::with_flags(pattern, Flags::from_bits(Flags::GLOBAL)).unwrap()
+    }
+
+    #[test]
+    fn test_replace_honors_global_flag() {
+        let re = Regex::new("o").unwrap();
+        assert_eq!(re.replace("foo boo", "0"), "f0o boo");
+        assert_eq!(re.replace_all("foo boo", "0"), "f00 b00");
+        assert_eq!(global("o").replace("foo boo", "0"), "f00 b00");
+        assert_eq!(re.replace_n("foo boo", 3, "0"), "f00 b0o");
+        assert_eq!(re.replace("xyz", "0"), "xyz");
+    }
+
+    #[test]
+    fn test_special_patterns() {
+        let re = Regex::new("b+").unwrap();
+        assert_eq!(re.replace("abbc", "[$&]"), "a[bb]c");
+        assert_eq!(re.replace("abbc", "[$`]"), "a[a]c");
+        assert_eq!(re.replace("abbc", "[$']"), "a[c]c");
+        assert_eq!(re.replace("abbc", "$$"), "a$c");
+        assert_eq!(re.replace("abbc", "$"), "a$c");
+        assert_eq!(re.replace("abbc", "$x$"), "a$x$c");
+    }
+
+    #[test]
+    fn test_numbered_groups() {
+        let re = Regex::new(r"(\w+) (\w+)").unwrap();
+        assert_eq!(re.replace("hello world", "$2 $1"), "world hello");
+        // $0 is not a group reference
+        assert_eq!(re.replace("hello world", "$0"), "$0");
+        // $3 does not exist and is kept literally
+        assert_eq!(re.replace("hello world", "$3"), "$3");
+        // $10 with two groups is $1 followed by "0"
+        assert_eq!(re.replace("hello world", "$10"), "hello0");
+        assert_eq!(re.replace("hello world", "$01"), "hello");
+        assert_eq!(re.replace("hello world", "$00"), "$00");
+    }
+
+    #[test]
+    fn test_two_digit_groups() {
+        let pattern = "(a)".repeat(11);
+        let re = Regex::new(&pattern).unwrap();
+        let text = "a".repeat(11);
+        assert_eq!(re.replace(&text, "$11"), "a");
+        assert_eq!(re.replace(&text, "$12"), "a2");
+        assert_eq!(re.replace(&text, "$011"), "a1");
+    }
+
+    #[test]
+    fn test_unmatched_group_is_empty() {
+        let re = Regex::new("(a)|(b)").unwrap();
+        assert_eq!(re.replace("b", "[$1|$2]"), "[|b]");
+    }
+
+    #[test]
+    fn test_named_groups() {
+        let re = Regex::new(r"(?<year>\d{4})-(?<month>\d{2})").unwrap();
+        assert_eq!(re.replace("2024-05", "$<month>/$<year>"), "05/2024");
+        assert_eq!(re.replace("2024-05", "[$<day>]"), "[]");
+        assert_eq!(re.replace("2024-05", "$<month"), "$<month");
+        // Without named groups `$<` is literal
+        let re = Regex::new(r"(\d+)").unwrap();
+        assert_eq!(re.replace("42", "$<x>"), "$<x>");
+    }
+
+    #[test]
+    fn test_empty_matches() {
+        let re = global("");
+        assert_eq!(re.replace("abc", "-"), "-a-b-c-");
+        assert_eq!(global("x*").replace("\u{e9}x\u{e9}", "-"), "-\u{e9}--\u{e9}-");
+        assert_eq!(global("x*").replace("\u{e9}x\u{e9}", "[$&]"), "[]\u{e9}[x][]\u{e9}[]");
+    }
+
+    #[test]
+    fn test_closure_receives_js_arguments() {
+        let re = global(r"(?<n>\d)(x)?");
+        let mut seen = Vec::new();
+        let out = re.replace("a1b2x", |caps: &Captures, dst: &mut String| {
+            seen.push((caps.offset(), caps.get_str(2).is_some(), caps.input().len()));
+            let groups = caps.named_groups().unwrap();
+            dst.push('<');
+            dst.push_str(groups["n"].unwrap());
+            dst.push('>');
+        });
+        assert_eq!(out, "a<1>b<2>");
+        assert_eq!(seen, vec![(1, false, 5), (3, true, 5)]);
+    }
+
+    #[test]
+    fn test_closure_called_once_per_match() {
+        // Backreferences run on the interpreter, the rest on the Pike VM
+        for (pattern, text, expected) in [
+            (r"(\w)\1", "aabxcc", vec![(0, "a"), (4, "c")]),
+            (r"(\d+)-(\d+)", "1-2 33-44", vec![(0, "2"), (4, "44")]),
+        ] {
+            let mut calls = Vec::new();
+            global(pattern).replace(text, |caps: &Captures, _: &mut String| {
+                let last = caps.get_str(caps.len() - 1).unwrap().to_string();
+                calls.push((caps.offset(), last));
+            });
+            let expected: Vec<_> = expected.into_iter().map(|(at, s)| (at, s.to_string())).collect();
+            assert_eq!(calls, expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_closure_with_decomposed_alternation() {
+        let words = ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot",
+                     "golf", "hotel", "india", "juliet", "kilo", "lima"];
+        let pattern = words.iter().map(|w| format!("({w})")).collect::<Vec<_>>().join("|");
+        let re = Regex::new(&pattern).unwrap();
+        assert!(re.decomposition_coverage().is_some());
+        let out = re.replace_all("echo, kilo and lima", |caps: &Captures, dst: &mut String| {
+            let group = (1..caps.len()).find(|&i| caps.get(i).is_some()).unwrap();
+            dst.push_str(&group.to_string());
+        });
+        assert_eq!(out, "5, 11 and 12");
+        assert_eq!(re.replace_all("golf", "[$7]"), "[golf]");
+    }
+}
@@ -0,0 +1,196 @@
+//! Finding the last match by scanning from the end of the input.
+//!
+//! `rfind` reports the match `find_iter` would report last. On first use the
+//! pattern is compiled a second time with its concatenations reversed and
+//! `^`/`$` swapped. That program runs on the end of the input, reversed char
+//! by char into a buffer that grows towards the start of the input until
+//! the scan no longer depends on what lies before it. So the work is about
+//! the distance from the last match to the end of the input.
+//!
+//! - The reversed scan finds the last point that no match spans and that
+//!   some match starts at or after. `find_iter` resumed there reports the
+//!   same matches it would from the start, so a forward pass from the point
+//!   ends with the last match.
+//! - When every match ends with a literal, `memmem::FinderRev` finds the
+//!   last one and the reversed scan starts there.
+//!
+//! Patterns with backreferences or lookaround can't be reversed. For them
+//! the forward pass starts at the beginning of the input.
+
+use memchr::memmem;
+
+use super::{compiler, extract_inline_flags, pikevm, Match, Matches, PikeVmMatches, Regex};
+
+/// Bytes of the input reversed for the first scan.
+const FIRST_WINDOW: usize = 4096;
+
+/// The reversed program of a pattern, with a finder for the literal its
+/// matches end with.
+pub(crate) struct ReverseSearch {
+    bytecode: Vec<u8>,
+    suffix: Option<memmem::FinderRev<'static>>,
+}
+
+impl Regex {
+    /// Find the last match, the one `find_iter` reports last.
+    ///
+    /// ```
+    /// use quickjs_regex::Regex;
+    ///
+    /// let re = Regex::new(r"\d+:\d+").unwrap();
+    /// let m = re.rfind("12:30 login, 23:45 logout").unwrap();
+    /// assert_eq!((m.start, m.end), (13, 18));
+    /// ```
+    pub fn rfind(&self, text: &str) -> Option<Match> {
+        self.rfind_at(text, text.len())
+    }
+
+    /// Like `rfind`, but only looks at `text[..end]`, as if the text ended
+    /// there. None if `end` is not a char boundary of `text`.
+    pub fn rfind_at(&self, text: &str, end: usize) -> Option<Match> {
+        let hay = text.get(..end)?;
+        let from = match self.reverse_search() {
+            Some(reverse) => reverse.last_unspanned(hay)?,
+            None => 0,
+        };
+        let text = hay.as_bytes();
+        if self.use_pike_vm {
+            let scanner = self.pike_scanner(text);
+            PikeVmMatches { scanner, regex: self, text, pos: from, last_was_empty: false }.last()
+        } else {
+            Matches { regex: self, text, last_end: from, last_was_empty: false }.last()
+        }
+    }
+
+    fn reverse_search(&self) -> Option<&ReverseSearch> {
+        self.reverse_search.get_or_init(|| {
+            if !self.pattern_compiles_to_bytecode() {
+                return None;
+            }
+            let (pattern, inline_flags) = extract_inline_flags(&self.pattern);
+            let mut flags = self.flags;
+            flags.insert(inline_flags.bits());
+            let reversed = compiler::compile_reversed(&pattern, flags).ok()??;
+            let suffix = (self.config.prefilters && !reversed.suffix.is_empty())
+                .then(|| memmem::FinderRev::new(reversed.suffix.as_bytes()).into_owned());
+            Some(ReverseSearch { bytecode: reversed.bytecode, suffix })
+        }).as_ref()
+    }
+}
+
+impl ReverseSearch {
+    /// The last point of `hay` that no match spans and that a match starts
+    /// at or after. None if nothing matches.
+    fn last_unspanned(&self, hay: &str) -> Option<usize> {
+        // No match ends after the last suffix
+        let at = match &self.suffix {
+            Some(suffix) => hay.len() - suffix.rfind(hay.as_bytes())? - suffix.needle().len(),
+            None => 0,
+        };
+        let mut reversed = Reversed { hay, from: hay.len(), bytes: Vec::new() };
+        reversed.grow(at + FIRST_WINDOW);
+        loop {
+            let vm = pikevm::PikeVm::new(&self.bytecode, &reversed.bytes);
+            match vm.unspanned_point(at, reversed.from > 0) {
+                pikevm::Scan::Found(point) => return Some(hay.len() - point),
+                pikevm::Scan::None => return None,
+                pikevm::Scan::Undecided => reversed.grow(2 * reversed.bytes.len()),
+            }
+        }
+    }
+}
+
+/// The end of a haystack, reversed char by char. Growing it appends the
+/// chars before that end, so positions in it stay put.
+struct Reversed<'h> {
+    hay: &'h str,
+    /// Start of the reversed part of `hay`
+    from: usize,
+    bytes: Vec<u8>,
+}
+
+impl Reversed<'_> {
+    /// Cover at least the last `len` bytes of the haystack.
+    fn grow(&mut self, len: usize) {
+        let mut from = self.hay.len().saturating_sub(len);
+        while !self.hay.is_char_boundary(from) {
+            from -= 1;
+        }
+        for c in self.hay[from..self.from].chars().rev() {
+            self.bytes.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes());
+        }
+        self.from = from;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Match, Regex, RegexBuilder};
+
+    #[test]
+    fn test_last_match() {
+        let re = Regex::new(r"\d+").unwrap();
+        assert_eq!(re.rfind("a1 22 333x"), Some(Match { start: 6, end: 9 }));
+        assert_eq!(re.rfind_at("a1 22 333x", 8), Some(Match { start: 6, end: 8 }));
+        assert_eq!(re.rfind_at("a1 22 333x", 6), Some(Match { start: 3, end: 5 }));
+        assert_eq!(re.rfind("none"), None);
+        assert_eq!(re.rfind_at("a1", 3), None);
+    }
+
+    #[test]
+    fn test_last_of_find_iter() {
+        let text = "aaa abcd a-aa_ab aaaa";
+        for pattern in ["a+?", "ab|abcd", "aa", "a*", r"\w+?\b", "(?:a|ab)(?:cd)?", r"a\B"] {
+            let re = Regex::new(pattern).unwrap();
+            for end in 0..=text.len() {
+                assert_eq!(re.rfind_at(text, end), re.find_iter(&text[..end]).last(), "{} {}", pattern, end);
+            }
+        }
+        assert_eq!(Regex::new("a+?").unwrap().rfind("aaa"), Some(Match { start: 2, end: 3 }));
+        assert_eq!(Regex::new("ab|abcd").unwrap().rfind("abcd"), Some(Match { start: 0, end: 2 }));
+        assert_eq!(Regex::new("aa").unwrap().rfind("aaa"), Some(Match { start: 0, end: 2 }));
+    }
+
+    #[test]
+    fn test_assertions_see_the_whole_text() {
+        let re = Regex::new(r"\bfoo\b").unwrap();
+        assert_eq!(re.rfind("foo foobar foo_ foo."), Some(Match { start: 16, end: 19 }));
+        let re = Regex::new("^a|b$").unwrap();
+        assert_eq!(re.rfind("ab\nab"), Some(Match { start: 4, end: 5 }));
+        assert_eq!(re.rfind_at("ab\nab", 4), Some(Match { start: 0, end: 1 }));
+        let re = Regex::new("(?m)^\\w").unwrap();
+        assert_eq!(re.rfind("ab\ncd"), Some(Match { start: 3, end: 4 }));
+    }
+
+    #[test]
+    fn test_long_input_and_utf8() {
+        let mut text = "caf\u{e9} 12 ".repeat(3000);
+        text.push_str("\u{1F600}\u{e9}\u{e9}!");
+        let re = Regex::new("\u{e9}+").unwrap();
+        assert_eq!(re.rfind(&text).map(|m| m.len()), Some(4));
+        let re = Regex::new(r"\d+ caf").unwrap();
+        assert_eq!(re.rfind(&text).map(|m| m.as_str(&text)), Some("12 caf"));
+        // The only match is far from the end
+        let re = Regex::new(r"^caf\S").unwrap();
+        assert_eq!(re.rfind(&text), Some(Match { start: 0, end: 5 }));
+    }
+
+    #[test]
+    fn test_backtracking_fallback() {
+        // a0 a1 _ a3 b4 _ a6 b7 a8 b9 _ a11 a12 - a14 a15
+        let text = "aa ab abab aa-aa";
+        let cases = [(r"(a)\1", (14, 16)), (r"a(?=b)", (8, 9)), (r"(?<=a)b", (9, 10)), (r"(\w)(\w)\2?a", (6, 9))];
+        for (pattern, (start, end)) in cases {
+            let re = Regex::new(pattern).unwrap();
+            assert_eq!(re.rfind(text), Some(Match { start, end }), "{}", pattern);
+        }
+        // Forcing the fallback with a lookahead gives the same matches
+        for pattern in [r"\w+", "a|ab|b", "ab*a?", r"b\b"] {
+            let reversible = Regex::new(pattern).unwrap();
+            let fallback = RegexBuilder::new(&format!(r"(?=[\s\S])(?:{})", pattern)).build().unwrap();
+            for end in 0..=text.len() {
+                assert_eq!(reversible.rfind_at(text, end), fallback.rfind_at(text, end), "{} {}", pattern, end);
+            }
+        }
+    }
+}
@@ -278,7 +278,7 @@ pub fn analyze(node: &RegexS) -> StaticInfo {
         // Possible literals union all branches.
         RegexS::Alt(alts) => {
             let mut info = StaticInfo::empty();
-            let mut all_start_bytes: Vec<u8> = vec![];
+            let mut all_start_bytes: Option<Vec<u8>> = Some(vec![]);
             let mut min_len = usize::MAX;
             let mut max_len: Option<usize> = Some(0);
             let mut any_can_match_empty = false;
@@ -291,11 +291,10 @@ pub fn analyze(node: &RegexS) -> StaticInfo {
                 info.possible_literals.extend(alt_info.possible_literals);
 
                 // Start bytes = union of all branch start bytes
-                if let Some(bytes) = alt_info.start_bytes {
-                    all_start_bytes.extend(bytes);
-                } else {
+                match (&mut all_start_bytes, alt_info.start_bytes) {
+                    (Some(all), Some(bytes)) => all.extend(bytes),
                     // One branch has unknown start → can't constrain
-                    all_start_bytes.clear();
+                    _ => all_start_bytes = None,
                 }
 
                 min_len = min_len.min(alt_info.min_length);
@@ -318,13 +317,11 @@ pub fn analyze(node: &RegexS) -> StaticInfo {
             // (for alternation, typically nothing is universally required)
             info.required_literals.clear();
 
-            info.start_bytes = if all_start_bytes.is_empty() {
-                None
-            } else {
-                all_start_bytes.sort();
-                all_start_bytes.dedup();
-                Some(all_start_bytes)
-            };
+            info.start_bytes = all_start_bytes.filter(|bytes| !bytes.is_empty()).map(|mut bytes| {
+                bytes.sort();
+                bytes.dedup();
+                bytes
+            });
 
             info.min_length = if min_len == usize::MAX { 0 } else { min_len };
             info.max_length = max_len;
@@ -661,6 +658,14 @@ mod tests {
         assert!(!matches!(pf, Prefilter::None), "should have a prefilter, got {:?}", pf);
     }
 
+    #[test]
+    fn test_alternation_start_bytes_need_every_branch() {
+        for pattern in ["$|^bab", "(?:b){0,2}?|a", ".|(?:ab|[ab])a", "b|x*|c"] {
+            assert_eq!(analyze_pattern(pattern).start_bytes, None, "{}", pattern);
+        }
+        assert_eq!(analyze_pattern("b|a|ab").start_bytes, Some(vec![b'a', b'b']));
+    }
+
     #[test]
     fn test_prefilter_anchored() {
         let info = analyze_pattern("^test");
@@ -0,0 +1,640 @@
+//! Saving compiled regexes and loading them back.
+//!
+//! `to_bytes` writes what compiling a pattern computes: the bytecode, the
+//! search strategy, the selective prefilter with its literals, and for a
+//! decomposed alternation the sub-patterns and the literal table that routes
+//! candidates to them. `from_bytes` rebuilds what can't be stored: the
+//! Aho-Corasick automatons and memmem finders from the saved literals, and
+//! the bit VM program from the bytecode.
+//!
+//! All integers are little-endian, lengths are `u32` and sizes `u64`. The
+//! data starts with a magic number and the format version, and data of
+//! another version is rejected rather than misread. The bytecode goes
+//! through `verify_bytecode` before any engine can run it, and the flags,
+//! group names and engine choice saved with it have to agree with it.
+//!
+//! The search strategy and prefilter skip positions the engines never see,
+//! so a wrong one loses matches. They are checked against the ones the
+//! pattern gives, which takes compiling and analyzing it again; that costs
+//! little next to the rest of loading. If the pattern doesn't compile
+//! to the saved bytecode, the bytecode is what runs: the strategy and
+//! prefilter are dropped, and programs built from the pattern, like the
+//! reversed one for `rfind`, are not used.
+
+use std::sync::OnceLock;
+
+use aho_corasick::{AhoCorasickBuilder, MatchKind};
+
+use super::builder::Config;
+use super::{
+    analyze_pattern, bitvm, compiler, extract_inline_flags, prefilter_searchers, selective, sub_pattern_ac,
+    verify_bytecode, ByteBitmap, Engine, Error, Flags, OwnedFinder, Regex, Result, SearchStrategy,
+};
+
+const MAGIC: &[u8; 4] = b"QJRX";
+/// Bumped whenever the layout of the data changes.
+const FORMAT_VERSION: u16 = 1;
+
+impl Regex {
+    /// Save the compiled regex, for loading with `from_bytes`.
+    ///
+    /// ```
+    /// use quickjs_regex::Regex;
+    ///
+    /// let saved = Regex::new(r"(\w+)@(\w+)\.com").unwrap().to_bytes();
+    /// let re = Regex::from_bytes(&saved).unwrap();
+    /// let caps = re.captures("mail bob@example.com").unwrap();
+    /// assert_eq!(caps.get_str(2), Some("example"));
+    /// ```
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut w = Writer(Vec::new());
+        w.0.extend_from_slice(MAGIC);
+        w.u16(FORMAT_VERSION);
+        self.write(&mut w);
+        w.0
+    }
+
+    /// Load a regex saved by `to_bytes`. Fails with `Error::Deserialize` on
+    /// data of another format version, truncated data, unknown tags or a
+    /// search strategy that isn't the pattern's, and with
+    /// `Error::InvalidBytecode` on bytecode that fails verification.
+    pub fn from_bytes(data: &[u8]) -> Result<Regex> {
+        let mut r = Reader { data, pos: 0 };
+        if r.take(MAGIC.len())? != MAGIC {
+            return Err(invalid("not a serialized regex"));
+        }
+        let version = r.u16()?;
+        if version != FORMAT_VERSION {
+            return Err(Error::Deserialize(format!("format version {} (expected {})", version, FORMAT_VERSION)));
+        }
+        let regex = Regex::read(&mut r)?;
+        if r.pos != data.len() {
+            return Err(invalid("trailing data"));
+        }
+        Ok(regex)
+    }
+
+    fn write(&self, w: &mut Writer) {
+        w.str(&self.pattern);
+        w.u16(self.flags.bits());
+        write_config(w, &self.config);
+        w.bytes(self.bytecode_slice());
+        w.bool(self.use_pike_vm);
+        w.bool(self.bit_program.is_some());
+        write_strategy(w, &self.strategy);
+        write_prefilter(w, &self.selective_prefilter);
+        w.bool(self.non_ascii_literals);
+        w.len(self.group_names.len());
+        for name in self.group_names.iter() {
+            w.bool(name.is_some());
+            if let Some(name) = name {
+                w.str(name);
+            }
+        }
+        w.len(self.sub_patterns.len());
+        for sub in &self.sub_patterns {
+            sub.write(w);
+        }
+        w.byte_strings(&self.sub_ac_literals);
+        w.len(self.ac_to_sub.len());
+        for subs in &self.ac_to_sub {
+            w.len(subs.len());
+            for &sub in subs {
+                w.size(sub);
+            }
+        }
+    }
+
+    fn read(r: &mut Reader) -> Result<Regex> {
+        let pattern = r.str()?;
+        let flags = Flags::from_bits(r.u16()?);
+        let config = read_config(r)?;
+        let mut bytecode = r.bytes()?.to_vec();
+        verify_bytecode(&bytecode).map_err(Error::InvalidBytecode)?;
+        if u16::from_le_bytes([bytecode[0], bytecode[1]]) != flags.bits() {
+            return Err(invalid("flags differ from the bytecode's"));
+        }
+        let use_pike_vm = r.bool()?;
+        let bit_program = if r.bool()? { bitvm::BitVmProgram::compile(&bytecode) } else { None };
+        let saved = r.pos;
+        let strategy = read_strategy(r, flags)?;
+        let selective_prefilter = read_prefilter(r)?;
+        let (strategy, selective_prefilter) = if compiles_to(&pattern, flags, &bytecode) {
+            // Searching with them skips positions, so they must be what
+            // the pattern gives, which can't be wrong for its bytecode
+            let mut expected = Writer(Vec::new());
+            let (expected_strategy, expected_prefilter) = pattern_searches(&pattern, flags, &config);
+            write_strategy(&mut expected, &expected_strategy);
+            write_prefilter(&mut expected, &expected_prefilter);
+            if r.data[saved..r.pos] != expected.0 {
+                return Err(invalid("search strategy or prefilter differs from the pattern's"));
+            }
+            (strategy, selective_prefilter)
+        } else {
+            // They describe the pattern, not the bytecode that runs
+            (SearchStrategy::None, selective::Prefilter::None)
+        };
+        let (ac_prefilter, memmem_prefilter) = prefilter_searchers(&selective_prefilter);
+        let non_ascii_literals = r.bool()?;
+        let group_names = (0..r.len()?)
+            .map(|_| if r.bool()? { r.str().map(Some) } else { Ok(None) })
+            .collect::<Result<Vec<_>>>()?;
+        if group_names.len() != usize::from(bytecode[2]) {
+            return Err(invalid("group names differ from the bytecode's groups"));
+        }
+        let sub_patterns = (0..r.len()?).map(|_| Regex::read(r)).collect::<Result<Vec<_>>>()?;
+        let sub_ac_literals = r.byte_strings()?;
+        let ac_to_sub = (0..r.len()?)
+            .map(|_| (0..r.len()?).map(|_| r.size()).collect::<Result<Vec<_>>>())
+            .collect::<Result<Vec<_>>>()?;
+        if ac_to_sub.iter().flatten().any(|&sub| sub >= sub_patterns.len()) {
+            return Err(invalid("literal table names a missing sub-pattern"));
+        }
+        let sub_ac = if sub_patterns.is_empty() {
+            None
+        } else {
+            Some(sub_pattern_ac(&sub_ac_literals).ok_or_else(|| invalid("sub-pattern literals"))?)
+        };
+
+        let regex = Regex {
+            bit_program,
+            bytecode: bytecode.as_mut_ptr(),
+            pattern,
+            flags,
+            strategy,
+            owned_bytecode: Some(bytecode),
+            selective_prefilter,
+            use_pike_vm,
+            ac_prefilter,
+            memmem_prefilter,
+            sub_patterns,
+            sub_ac,
+            sub_ac_literals,
+            ac_to_sub,
+            non_ascii_literals,
+            group_names: group_names.into(),
+            config,
+            reverse_search: OnceLock::new(),
+            utf16_search: OnceLock::new(),
+        };
+        if regex.use_pike_vm && !regex.supports_engine(Engine::PikeVm) {
+            return Err(invalid("Pike VM chosen for bytecode it can't run"));
+        }
+        Ok(regex)
+    }
+
+    /// Whether the pattern compiles to this regex's bytecode. Programs
+    /// built from the pattern later, like the reversed one, are only used
+    /// when it does: data loaded with `from_bytes` may pair a pattern with
+    /// bytecode it doesn't compile to.
+    pub(crate) fn pattern_compiles_to_bytecode(&self) -> bool {
+        compiles_to(&self.pattern, self.flags, self.bytecode_slice())
+    }
+}
+
+fn compiles_to(pattern: &str, flags: Flags, bytecode: &[u8]) -> bool {
+    let (pattern, _) = extract_inline_flags(pattern);
+    compiler::compile_regex(&pattern, flags).is_ok_and(|compiled| compiled == bytecode)
+}
+
+/// The search strategy and prefilter compiling `pattern` gives.
+fn pattern_searches(pattern: &str, flags: Flags, config: &Config) -> (SearchStrategy, selective::Prefilter) {
+    let (pattern, _) = extract_inline_flags(pattern);
+    match compiler::parser::parse(&pattern, flags) {
+        Ok(ast) if config.prefilters => (
+            analyze_pattern(&pattern, flags),
+            selective::derive_prefilter(&selective::analyze(&selective::from_ast(&ast))),
+        ),
+        _ => (SearchStrategy::None, selective::Prefilter::None),
+    }
+}
+
+fn invalid(what: &str) -> Error {
+    Error::Deserialize(what.to_string())
+}
+
+fn write_config(w: &mut Writer, config: &Config) {
+    w.size(config.backtrack_limit);
+    w.size(config.backtrack_stack_limit);
+    w.size(config.dfa_cache_size);
+    w.bool(config.pike_vm);
+    w.bool(config.bit_vm);
+    w.bool(config.decompose_alternations);
+    w.bool(config.prefilters);
+    w.size(config.small_input_threshold);
+}
+
+fn read_config(r: &mut Reader) -> Result<Config> {
+    Ok(Config {
+        backtrack_limit: r.limit()?,
+        backtrack_stack_limit: r.limit()?,
+        dfa_cache_size: r.limit()?,
+        pike_vm: r.bool()?,
+        bit_vm: r.bool()?,
+        decompose_alternations: r.bool()?,
+        prefilters: r.bool()?,
+        small_input_threshold: r.limit()?,
+    })
+}
+
+fn write_strategy(w: &mut Writer, strategy: &SearchStrategy) {
+    match strategy {
+        SearchStrategy::Anchored => w.u8(0),
+        SearchStrategy::AnchoredLiteral(lit) => {
+            w.u8(1);
+            w.bytes(lit);
+        }
+        SearchStrategy::PureLiteral(finder) => {
+            w.u8(2);
+            w.bytes(finder.needle());
+        }
+        SearchStrategy::SingleByte(b) => {
+            w.u8(3);
+            w.u8(*b);
+        }
+        SearchStrategy::TwoBytes(b1, b2) => {
+            w.u8(4);
+            w.u8(*b1);
+            w.u8(*b2);
+        }
+        SearchStrategy::ThreeBytes(b1, b2, b3) => {
+            w.u8(5);
+            w.u8(*b1);
+            w.u8(*b2);
+            w.u8(*b3);
+        }
+        SearchStrategy::LiteralPrefix(finder) => {
+            w.u8(6);
+            w.bytes(finder.needle());
+        }
+        SearchStrategy::AlternationLiterals { literals, .. } => {
+            w.u8(7);
+            w.byte_strings(literals);
+        }
+        SearchStrategy::CaseInsensitiveLiteral { lowercase, len } => {
+            w.u8(8);
+            w.bytes(lowercase);
+            w.size(*len);
+        }
+        SearchStrategy::SuffixLiteral(lit) => {
+            w.u8(9);
+            w.bytes(lit);
+        }
+        SearchStrategy::Bitmap(bitmap) => {
+            w.u8(10);
+            for word in bitmap.bits {
+                w.0.extend_from_slice(&word.to_le_bytes());
+            }
+        }
+        SearchStrategy::Digit => w.u8(11),
+        SearchStrategy::WordChar => w.u8(12),
+        SearchStrategy::Whitespace => w.u8(13),
+        SearchStrategy::PureDigitPlus => w.u8(14),
+        SearchStrategy::PureLowerPlus => w.u8(15),
+        SearchStrategy::PureUpperPlus => w.u8(16),
+        SearchStrategy::PureAlphaPlus => w.u8(17),
+        SearchStrategy::PureAlnumPlus => w.u8(18),
+        SearchStrategy::PureWordPlus => w.u8(19),
+        SearchStrategy::QuotedString(quote) => {
+            w.u8(20);
+            w.u8(*quote);
+        }
+        SearchStrategy::PureCapitalWord => w.u8(21),
+        SearchStrategy::PureLowerSuffix(suffix) => {
+            w.u8(22);
+            w.bytes(suffix);
+        }
+        SearchStrategy::None => w.u8(23),
+    }
+}
+
+/// `flags` are the regex's: alternation literals are matched ignoring
+/// ASCII case under `i`.
+fn read_strategy(r: &mut Reader, flags: Flags) -> Result<SearchStrategy> {
+    Ok(match r.u8()? {
+        0 => SearchStrategy::Anchored,
+        1 => SearchStrategy::AnchoredLiteral(r.bytes()?.to_vec()),
+        2 => SearchStrategy::PureLiteral(OwnedFinder::new(r.bytes()?.to_vec())),
+        3 => SearchStrategy::SingleByte(r.u8()?),
+        4 => SearchStrategy::TwoBytes(r.u8()?, r.u8()?),
+        5 => SearchStrategy::ThreeBytes(r.u8()?, r.u8()?, r.u8()?),
+        6 => SearchStrategy::LiteralPrefix(OwnedFinder::new(r.bytes()?.to_vec())),
+        7 => {
+            let literals = r.byte_strings()?;
+            let ac = AhoCorasickBuilder::new()
+                .match_kind(MatchKind::LeftmostFirst)
+                .ascii_case_insensitive(flags.is_ignore_case())
+                .build(&literals)
+                .map_err(|_| invalid("alternation literals"))?;
+            SearchStrategy::AlternationLiterals { literals, ac }
+        }
+        8 => SearchStrategy::CaseInsensitiveLiteral { lowercase: r.bytes()?.to_vec(), len: r.size()? },
+        9 => SearchStrategy::SuffixLiteral(r.bytes()?.to_vec()),
+        10 => {
+            let mut bits = [0; 4];
+            for word in &mut bits {
+                *word = r.u64()?;
+            }
+            SearchStrategy::Bitmap(ByteBitmap { bits })
+        }
+        11 => SearchStrategy::Digit,
+        12 => SearchStrategy::WordChar,
+        13 => SearchStrategy::Whitespace,
+        14 => SearchStrategy::PureDigitPlus,
+        15 => SearchStrategy::PureLowerPlus,
+        16 => SearchStrategy::PureUpperPlus,
+        17 => SearchStrategy::PureAlphaPlus,
+        18 => SearchStrategy::PureAlnumPlus,
+        19 => SearchStrategy::PureWordPlus,
+        20 => SearchStrategy::QuotedString(r.u8()?),
+        21 => SearchStrategy::PureCapitalWord,
+        22 => SearchStrategy::PureLowerSuffix(r.bytes()?.to_vec()),
+        23 => SearchStrategy::None,
+        tag => return Err(Error::Deserialize(format!("unknown search strategy {}", tag))),
+    })
+}
+
+fn write_prefilter(w: &mut Writer, prefilter: &selective::Prefilter) {
+    use selective::Prefilter;
+    match prefilter {
+        Prefilter::None => w.u8(0),
+        Prefilter::AnchoredStart => w.u8(1),
+        Prefilter::SingleByte(b) => {
+            w.u8(2);
+            w.u8(*b);
+        }
+        Prefilter::ByteSet(bytes) => {
+            w.u8(3);
+            w.bytes(bytes);
+        }
+        Prefilter::MemmemStart(needle) => {
+            w.u8(4);
+            w.bytes(needle);
+        }
+        Prefilter::MemmemInner { needle, min_prefix } => {
+            w.u8(5);
+            w.bytes(needle);
+            w.size(*min_prefix);
+        }
+        Prefilter::AhoCorasickStart(patterns) => {
+            w.u8(6);
+            w.byte_strings(patterns);
+        }
+        Prefilter::AhoCorasickInner { patterns, min_prefix } => {
+            w.u8(7);
+            w.byte_strings(patterns);
+            w.size(*min_prefix);
+        }
+    }
+}
+
+fn read_prefilter(r: &mut Reader) -> Result<selective::Prefilter> {
+    use selective::Prefilter;
+    Ok(match r.u8()? {
+        0 => Prefilter::None,
+        1 => Prefilter::AnchoredStart,
+        2 => Prefilter::SingleByte(r.u8()?),
+        3 => Prefilter::ByteSet(r.bytes()?.to_vec()),
+        4 => Prefilter::MemmemStart(r.bytes()?.to_vec()),
+        5 => Prefilter::MemmemInner { needle: r.bytes()?.to_vec(), min_prefix: r.size()? },
+        6 => Prefilter::AhoCorasickStart(r.byte_strings()?),
+        7 => Prefilter::AhoCorasickInner { patterns: r.byte_strings()?, min_prefix: r.size()? },
+        tag => return Err(Error::Deserialize(format!("unknown prefilter {}", tag))),
+    })
+}
+
+struct Writer(Vec<u8>);
+
+impl Writer {
+    fn u8(&mut self, v: u8) {
+        self.0.push(v);
+    }
+
+    fn bool(&mut self, v: bool) {
+        self.u8(v as u8);
+    }
+
+    fn u16(&mut self, v: u16) {
+        self.0.extend_from_slice(&v.to_le_bytes());
+    }
+
+    fn len(&mut self, len: usize) {
+        let len = u32::try_from(len).expect("length does not fit the format");
+        self.0.extend_from_slice(&len.to_le_bytes());
+    }
+
+    fn size(&mut self, v: usize) {
+        self.0.extend_from_slice(&(v as u64).to_le_bytes());
+    }
+
+    fn bytes(&mut self, bytes: &[u8]) {
+        self.len(bytes.len());
+        self.0.extend_from_slice(bytes);
+    }
+
+    fn str(&mut self, s: &str) {
+        self.bytes(s.as_bytes());
+    }
+
+    fn byte_strings(&mut self, strings: &[Vec<u8>]) {
+        self.len(strings.len());
+        for s in strings {
+            self.bytes(s);
+        }
+    }
+}
+
+struct Reader<'a> {
+    data: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8]> {
+        let bytes = self.data.get(self.pos..).and_then(|rest| rest.get(..n)).ok_or_else(|| invalid("truncated data"))?;
+        self.pos += n;
+        Ok(bytes)
+    }
+
+    fn u8(&mut self) -> Result<u8> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn bool(&mut self) -> Result<bool> {
+        match self.u8()? {
+            0 => Ok(false),
+            1 => Ok(true),
+            _ => Err(invalid("bad boolean")),
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16> {
+        let b = self.take(2)?;
+        Ok(u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u64(&mut self) -> Result<u64> {
+        let mut word = [0; 8];
+        word.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(word))
+    }
+
+    fn len(&mut self) -> Result<usize> {
+        let b = self.take(4)?;
+        Ok(u32::from_le_bytes([b[0], b[1], b[2], b[3]]) as usize)
+    }
+
+    fn size(&mut self) -> Result<usize> {
+        usize::try_from(self.u64()?).map_err(|_| invalid("size out of range"))
+    }
+
+    /// A size that stands for a limit: saturates where `usize` is narrower.
+    fn limit(&mut self) -> Result<usize> {
+        Ok(usize::try_from(self.u64()?).unwrap_or(usize::MAX))
+    }
+
+    fn bytes(&mut self) -> Result<&'a [u8]> {
+        let len = self.len()?;
+        self.take(len)
+    }
+
+    fn str(&mut self) -> Result<String> {
+        let bytes = self.bytes()?;
+        String::from_utf8(bytes.to_vec()).map_err(|_| invalid("string is not UTF-8"))
+    }
+
+    fn byte_strings(&mut self) -> Result<Vec<Vec<u8>>> {
+        (0..self.len()?).map(|_| self.bytes().map(<[u8]>::to_vec)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::selective::Prefilter;
+    use crate::regex::{BytecodeError, Error, Flags, Match, OwnedFinder, Regex, RegexBuilder, SearchStrategy};
+
+    /// Patterns covering the search strategies, prefilters and engines.
+    const PATTERNS: &[&str] = &[
+        "hello", "^abc", "foo|bar|baz", r"\d+", r"[a-z]+ing", r"(?i)select", r"\w+@\w+\.com",
+        r#""[^"]*""#, "[A-Z][a-z]+", r"(\w)\1", r"a(?=b)", r"(?<year>\d{4})-(?<month>\d\d)", "x*", "[aeiou]{2}",
+    ];
+
+    #[test]
+    fn test_round_trip_matches_like_the_original() {
+        let text = "Hello hello, SELECT something from foo@bar.com; \"quoted\" 2024-06 running aa ee Zebra abba x";
+        for &pattern in PATTERNS {
+            let re = Regex::new(pattern).unwrap();
+            let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+            assert_eq!(loaded.pattern(), pattern);
+            assert_eq!(loaded.flags(), re.flags());
+            assert_eq!(format!("{:?}", loaded.strategy), format!("{:?}", re.strategy), "{}", pattern);
+            assert_eq!(loaded.debug_bytecode(), re.debug_bytecode());
+            assert_eq!(loaded.find_iter(text).collect::<Vec<_>>(), re.find_iter(text).collect::<Vec<_>>(), "{}", pattern);
+            let caps = |re: &Regex| re.captures(text).map(|c| c.iter().collect::<Vec<_>>());
+            assert_eq!(caps(&loaded), caps(&re), "{}", pattern);
+            assert_eq!(loaded.to_bytes(), re.to_bytes());
+        }
+        let re = Regex::new(r"(?<year>\d{4})-(?<month>\d\d)").unwrap();
+        let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+        assert_eq!(loaded.captures(text).unwrap().name("month").map(|m| m.start), Some(62));
+    }
+
+    #[test]
+    fn test_decomposed_alternation_and_config() {
+        let words = ["alpha", "bravo", "charlie", "delta", "echo", "foxtrot", "golf", "hotel", "india", "juliet", "kilo", "lima"];
+        let pattern = words.iter().map(|w| format!(r"{}\d+", w)).collect::<Vec<_>>().join("|");
+        let re = Regex::with_flags(&pattern, Flags::parse("i").unwrap()).unwrap();
+        assert!(!re.sub_patterns.is_empty());
+        let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+        assert_eq!(loaded.sub_patterns.len(), re.sub_patterns.len());
+        let text = "x KILO42 golf7 echo india9";
+        assert_eq!(loaded.find_iter(text).collect::<Vec<_>>(), re.find_iter(text).collect::<Vec<_>>());
+
+        let re = RegexBuilder::new("(a+)+b").backtrack_limit(1000).pike_vm(false).build().unwrap();
+        let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+        assert!(!loaded.use_pike_vm);
+        assert!(loaded.try_find(&"a".repeat(30)).is_err());
+    }
+
+    #[test]
+    fn test_rejects_bad_data() {
+        let saved = Regex::new(r"\w+").unwrap().to_bytes();
+        let is_deserialize_error = |data: &[u8]| matches!(Regex::from_bytes(data), Err(Error::Deserialize(_)));
+        assert!(is_deserialize_error(b"nope"));
+        assert!(is_deserialize_error(&saved[..saved.len() - 1]));
+        let mut trailing = saved.clone();
+        trailing.push(0);
+        assert!(is_deserialize_error(&trailing));
+        let mut other_version = saved.clone();
+        other_version[4] = 99;
+        assert!(matches!(Regex::from_bytes(&other_version), Err(Error::Deserialize(msg)) if msg.contains("version 99")));
+    }
+
+    #[test]
+    fn test_rejects_data_that_disagrees_with_the_bytecode() {
+        let is_deserialize_error = |re: Regex| matches!(Regex::from_bytes(&re.to_bytes()), Err(Error::Deserialize(_)));
+        let mut re = Regex::new("a").unwrap();
+        re.flags = Flags::parse("i").unwrap();
+        assert!(is_deserialize_error(re));
+        let mut re = Regex::new("(a)b").unwrap();
+        re.group_names = vec![None].into();
+        assert!(is_deserialize_error(re));
+        let mut re = Regex::new(r"(a)\1").unwrap();
+        re.use_pike_vm = true;
+        assert!(is_deserialize_error(re));
+    }
+
+    #[test]
+    fn test_rejects_a_strategy_the_pattern_does_not_give() {
+        let is_deserialize_error = |re: Regex| matches!(Regex::from_bytes(&re.to_bytes()), Err(Error::Deserialize(_)));
+        let mut re = Regex::new("(?i)select").unwrap();
+        re.strategy = SearchStrategy::CaseInsensitiveLiteral { lowercase: Vec::new(), len: 6 };
+        assert!(is_deserialize_error(re));
+        let mut re = Regex::new("hello").unwrap();
+        re.strategy = SearchStrategy::PureLiteral(OwnedFinder::new(b"world".to_vec()));
+        assert!(is_deserialize_error(re));
+        let mut re = Regex::new(r"\w+foo").unwrap();
+        re.selective_prefilter = Prefilter::MemmemInner { needle: b"bar".to_vec(), min_prefix: 9 };
+        assert!(is_deserialize_error(re));
+
+        // With bytecode the pattern doesn't compile to, the pattern's
+        // strategy goes unused
+        let mut re = Regex::new("aaa").unwrap();
+        re.pattern = "bbb".to_string();
+        re.strategy = SearchStrategy::PureLiteral(OwnedFinder::new(b"bbb".to_vec()));
+        let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+        assert_eq!(loaded.find("bbb aaa"), Some(Match { start: 4, end: 7 }));
+    }
+
+    #[test]
+    fn test_bytecode_wins_over_a_different_pattern() {
+        let mut re = Regex::new("aaa").unwrap();
+        re.pattern = "bbb".to_string();
+        let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+        let text = "aaa bbb";
+        assert_eq!(loaded.rfind(text), loaded.find_iter(text).last());
+        assert_eq!(loaded.rfind(text), Some(Match { start: 0, end: 3 }));
+
+        let mut re = Regex::new("a\u{1F600}").unwrap();
+        re.pattern = "b\u{1F600}".to_string();
+        let loaded = Regex::from_bytes(&re.to_bytes()).unwrap();
+        let text: Vec<u16> = "b\u{1F600} a\u{1F600}".encode_utf16().collect();
+        // Not compiled again from the pattern for code units: the saved
+        // astral char never meets the lone surrogates it is split into
+        assert_eq!(loaded.find_at_utf16(&text, 0), None);
+    }
+
+    #[test]
+    fn test_rejects_corrupt_bytecode() {
+        let re = Regex::new(r"(a)\1").unwrap();
+        let mut saved = re.to_bytes();
+        let bytecode = re.bytecode_slice();
+        let at = saved.windows(bytecode.len()).position(|w| w == bytecode).unwrap();
+        // Leave only group 0: saving group 1 is the first bad instruction
+        saved[at + 2] = 1;
+        assert_eq!(
+            Regex::from_bytes(&saved).unwrap_err(),
+            Error::InvalidBytecode(BytecodeError::CaptureIndex { pc: 21, index: 1 })
+        );
+    }
+}
@@ -0,0 +1,358 @@
+//! Matching many independent patterns against one haystack.
+//!
+//! A `RegexSet` compiles each pattern on its own, with its own flags, and
+//! joins their literals into one shared Aho-Corasick automaton, the same way
+//! large top-level alternations are decomposed. One pass of the automaton
+//! over the haystack tells which patterns can match at all; each candidate
+//! pattern is then searched on its own, from the start of the haystack, to
+//! confirm it and find its leftmost match. Only the literal pass is shared:
+//! a haystack holding literals of many patterns costs a full search per
+//! pattern. A literal can sit anywhere in a match, so its position does not
+//! say where the match starts, and the search can't begin at the hit.
+//!
+//! Unlike a prefilter, which only has to find good starting points, the
+//! literals here decide whether a pattern is searched at all, so each
+//! pattern contributes a set of literals of which every match must contain
+//! one. Patterns without such a set are always searched.
+
+use std::collections::HashMap;
+
+use aho_corasick::{AhoCorasick, AhoCorasickBuilder, MatchKind};
+
+use super::compiler::parser::{self, Node};
+use super::{extract_inline_flags, Flags, Match, Regex, Result};
+
+/// A set of patterns, filtered by one literal scan of the haystack.
+#[derive(Debug)]
+pub struct RegexSet {
+    /// The compiled patterns, in the order given
+    regexes: Vec<Regex>,
+    /// Shared automaton over every pattern's literals
+    ac: Option<AhoCorasick>,
+    /// Maps AC pattern index → indices of the patterns requiring that literal
+    ac_to_pattern: Vec<Vec<usize>>,
+    /// Patterns without literals, which every search has to run
+    unfiltered: Vec<usize>,
+}
+
+/// Which patterns of a `RegexSet` matched, and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SetMatches {
+    /// Leftmost match of each pattern, None if it did not match
+    spans: Vec<Option<Match>>,
+}
+
+impl RegexSet {
+    /// Compile a set of patterns, all without flags.
+    pub fn new<I, S>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        Self::with_flags(patterns.into_iter().map(|p| (p, Flags::empty())))
+    }
+
+    /// Compile a set of `(pattern, flags)` pairs.
+    pub fn with_flags<I, S>(patterns: I) -> Result<Self>
+    where
+        I: IntoIterator<Item = (S, Flags)>,
+        S: AsRef<str>,
+    {
+        let regexes = patterns.into_iter()
+            .map(|(pattern, flags)| Regex::with_flags(pattern.as_ref(), flags))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut literal_to_patterns: HashMap<Vec<u8>, Vec<usize>> = HashMap::new();
+        let mut unfiltered = Vec::new();
+        for (i, regex) in regexes.iter().enumerate() {
+            match literal_cover(regex) {
+                Some(literals) => {
+                    for lit in literals {
+                        let key = lit.to_ascii_lowercase().into_bytes();
+                        let entry = literal_to_patterns.entry(key).or_default();
+                        if !entry.contains(&i) {
+                            entry.push(i);
+                        }
+                    }
+                }
+                None => unfiltered.push(i),
+            }
+        }
+
+        let mut literals = literal_to_patterns.keys().cloned().collect::<Vec<_>>();
+        literals.sort();
+        let ac_to_pattern = literals.iter()
+            .map(|lit| literal_to_patterns[lit].clone())
+            .collect::<Vec<_>>();
+
+        // Standard match kind so overlapping hits report every literal.
+        // Case-insensitive: extra candidates are rejected by the search.
+        let ac = if literals.is_empty() {
+            None
+        } else {
+            AhoCorasickBuilder::new()
+                .match_kind(MatchKind::Standard)
+                .ascii_case_insensitive(true)
+                .build(&literals)
+                .ok()
+        };
+        if ac.is_none() {
+            // Without an automaton every pattern has to be searched
+            unfiltered = (0..regexes.len()).collect();
+        }
+
+        Ok(RegexSet { regexes, ac, ac_to_pattern, unfiltered })
+    }
+
+    /// Number of patterns in the set.
+    pub fn len(&self) -> usize {
+        self.regexes.len()
+    }
+
+    /// Check if the set has no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.regexes.is_empty()
+    }
+
+    /// The source patterns, in the order given.
+    pub fn patterns(&self) -> impl Iterator<Item = &str> + '_ {
+        self.regexes.iter().map(Regex::pattern)
+    }
+
+    /// The compiled regex for pattern `i`.
+    pub fn regex(&self, i: usize) -> Option<&Regex> {
+        self.regexes.get(i)
+    }
+
+    /// Check if any pattern matches `text`. Stops at the first pattern
+    /// that does.
+    pub fn is_match(&self, text: &str) -> bool {
+        self.unfiltered.iter().any(|&i| self.regexes[i].is_match(text))
+            || self.scan(text, |i| self.regexes[i].is_match(text))
+    }
+
+    /// Find which patterns match `text`, with the leftmost match of each.
+    /// Every pattern without literals and every pattern whose literal shows
+    /// up is searched with `Regex::find`.
+    pub fn matches(&self, text: &str) -> SetMatches {
+        let mut spans = vec![None; self.regexes.len()];
+        for &i in &self.unfiltered {
+            spans[i] = self.regexes[i].find(text);
+        }
+        self.scan(text, |i| {
+            spans[i] = self.regexes[i].find(text);
+            false
+        });
+        SetMatches { spans }
+    }
+
+    /// Run the literal scan, calling `verify` once for each filtered pattern
+    /// whose literal shows up. Stops early when `verify` returns true.
+    fn scan(&self, text: &str, mut verify: impl FnMut(usize) -> bool) -> bool {
+        let Some(ac) = &self.ac else { return false };
+        let mut decided = vec![false; self.regexes.len()];
+        let mut remaining = self.regexes.len() - self.unfiltered.len();
+        for hit in ac.find_overlapping_iter(text.as_bytes()) {
+            for &i in &self.ac_to_pattern[hit.pattern().as_usize()] {
+                if decided[i] {
+                    continue;
+                }
+                decided[i] = true;
+                remaining -= 1;
+                if verify(i) {
+                    return true;
+                }
+            }
+            if remaining == 0 {
+                break;
+            }
+        }
+        false
+    }
+}
+
+impl SetMatches {
+    /// Check if any pattern matched.
+    pub fn matched_any(&self) -> bool {
+        self.spans.iter().any(Option::is_some)
+    }
+
+    /// Check if pattern `i` matched.
+    pub fn matched(&self, i: usize) -> bool {
+        self.get(i).is_some()
+    }
+
+    /// Leftmost match of pattern `i`, if it matched.
+    pub fn get(&self, i: usize) -> Option<Match> {
+        self.spans.get(i).copied().flatten()
+    }
+
+    /// Number of patterns in the set that was searched.
+    pub fn len(&self) -> usize {
+        self.spans.len()
+    }
+
+    /// Check if the searched set had no patterns.
+    pub fn is_empty(&self) -> bool {
+        self.spans.is_empty()
+    }
+
+    /// Indices of the patterns that matched, in ascending order.
+    pub fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.spans.iter().enumerate().filter(|(_, m)| m.is_some()).map(|(i, _)| i)
+    }
+}
+
+/// Most literals a pattern may contribute before it is left unfiltered.
+const MAX_COVER: usize = 64;
+
+/// Literals of which every match of `regex` contains at least one, or None
+/// if no such set is known.
+fn literal_cover(regex: &Regex) -> Option<Vec<String>> {
+    let (pattern, _) = extract_inline_flags(regex.pattern());
+    let flags = regex.flags();
+    let ast = parser::parse(&pattern, flags).ok()?;
+    cover(&ast, flags)
+}
+
+fn cover(node: &Node, flags: Flags) -> Option<Vec<String>> {
+    match node {
+        Node::Literal(c) => literal_run(&[*c], flags),
+        Node::Capture { sub, .. } | Node::Group(sub) => cover(sub, flags),
+        Node::Repeat { sub, min, .. } if *min > 0 => cover(sub, flags),
+        Node::Alternation(alts) => {
+            let mut all = Vec::new();
+            for alt in alts {
+                all.extend(cover(alt, flags)?);
+                if all.len() > MAX_COVER {
+                    return None;
+                }
+            }
+            all.sort();
+            all.dedup();
+            Some(all)
+        }
+        Node::Concat(nodes) => {
+            // Any one part's cover works for the whole sequence; runs of
+            // adjacent literals are joined into longer strings first
+            let mut best: Option<Vec<String>> = None;
+            let mut run = Vec::new();
+            for n in nodes.iter().map(Some).chain([None]) {
+                if let Some(Node::Literal(c)) = n {
+                    run.push(*c);
+                    continue;
+                }
+                let candidates = [literal_run(&run, flags), n.and_then(|n| cover(n, flags))];
+                run.clear();
+                for candidate in candidates.into_iter().flatten() {
+                    if best.as_ref().map_or(true, |b| score(&candidate) > score(b)) {
+                        best = Some(candidate);
+                    }
+                }
+            }
+            best
+        }
+        _ => None,
+    }
+}
+
+/// A run of literal chars as a one-literal cover. With the `i` flag only
+/// chars whose case variants are all ASCII are usable, since the shared
+/// automaton only folds ASCII case.
+fn literal_run(chars: &[char], flags: Flags) -> Option<Vec<String>> {
+    if chars.is_empty() {
+        return None;
+    }
+    if flags.is_ignore_case() {
+        // In Unicode mode 'k' and 's' also match KELVIN SIGN and LONG S
+        let unicode = flags.is_unicode();
+        let foldable = |c: &char| c.is_ascii() && !(unicode && matches!(c, 'k' | 'K' | 's' | 'S'));
+        if !chars.iter().all(foldable) {
+            return None;
+        }
+    }
+    Some(vec![chars.iter().collect()])
+}
+
+/// Prefer covers whose shortest literal is longest, then smaller covers.
+fn score(cover: &[String]) -> (usize, std::cmp::Reverse<usize>) {
+    let shortest = cover.iter().map(String::len).min().unwrap_or(0);
+    (shortest, std::cmp::Reverse(cover.len()))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Flags, Match, RegexSet};
+
+    #[test]
+    fn test_matches_reports_each_pattern() {
+        let set = RegexSet::new([r"foo\d+", "bar", r"\bbaz", "^x", "qux|quux"]).unwrap();
+        assert_eq!(set.len(), 5);
+        let text = "a bar, foo12 and bar";
+        let matches = set.matches(text);
+        assert_eq!(matches.iter().collect::<Vec<_>>(), [0, 1]);
+        assert_eq!(matches.get(0), Some(Match { start: 7, end: 12 }));
+        assert_eq!(matches.get(1), Some(Match { start: 2, end: 5 }));
+        assert!(!matches.matched(2) && !matches.matched(4));
+        assert!(set.is_match(text));
+        assert!(set.is_match("xyz"));
+        assert!(!set.is_match("nothing here"));
+        assert!(!set.matches("nothing here").matched_any());
+    }
+
+    #[test]
+    fn test_per_pattern_flags() {
+        let set = RegexSet::with_flags([
+            ("hello", Flags::empty()),
+            ("hello", Flags::from_bits(Flags::IGNORE_CASE)),
+            ("^world", Flags::from_bits(Flags::MULTILINE)),
+        ]).unwrap();
+        let matches = set.matches("HELLO\nworld");
+        assert_eq!(matches.iter().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(matches.get(2), Some(Match { start: 6, end: 11 }));
+    }
+
+    #[test]
+    fn test_agrees_with_individual_regexes() {
+        let patterns = ["alpha", r"be+ta", "(?i)GAMMA", r"del\w+a", "eps(?=ilon)",
+                        r"(\d)\1", r"z{2,}", "omega|psi"];
+        let set = RegexSet::new(patterns).unwrap();
+        assert!(set.ac.is_some() && set.unfiltered.len() < patterns.len());
+        for text in ["alpha beta", "BEETA gamma", "delta epsilon 11", "zz psi", "abc", ""] {
+            let matches = set.matches(text);
+            for (i, pattern) in set.patterns().enumerate() {
+                assert_eq!(matches.get(i), set.regex(i).unwrap().find(text), "{} on {:?}", pattern, text);
+            }
+            assert_eq!(set.is_match(text), matches.matched_any());
+        }
+    }
+
+    #[test]
+    fn test_literal_covers() {
+        let set = RegexSet::new([r"(?:foo|ba+r)\d", r"x(?!abc)y", r"(ab)*\d", "a|[bc]"]).unwrap();
+        assert_eq!(set.ac_to_pattern.iter().flatten().filter(|&&i| i == 0).count(), 2);
+        assert_eq!(set.unfiltered, [2, 3]);
+        // Literals inside a negative lookahead are not required
+        assert!(set.matches("xy").matched(1));
+    }
+
+    #[test]
+    fn test_case_folding_beyond_ascii() {
+        let set = RegexSet::with_flags([
+            ("kelvin", Flags::parse("iu").unwrap()),
+            ("Kelvin", Flags::parse("i").unwrap()),
+        ]).unwrap();
+        let matches = set.matches("\u{212A}ELVIN");
+        assert_eq!(matches.iter().collect::<Vec<_>>(), [0]);
+        assert!(set.matches("kELVIN").matched(1));
+    }
+
+    #[test]
+    fn test_empty_set_and_invalid_pattern() {
+        let set = RegexSet::new(Vec::<&str>::new()).unwrap();
+        assert!(set.is_empty());
+        assert!(!set.is_match("abc"));
+        assert!(set.matches("abc").is_empty());
+        assert!(RegexSet::new(["ok", "("]).is_err());
+    }
+}
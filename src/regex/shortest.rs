@@ -0,0 +1,133 @@
+//! Earliest match ends.
+//!
+//! `shortest_match` reports where a match first becomes certain: the
+//! smallest end of any match, whatever it starts at. It does not go on to
+//! settle leftmost-first priority, so Pike VM patterns need one lazy DFA
+//! pass, after the bit VM has had the chance to rule the input out.
+//! Patterns with backreferences or lookaround run on the backtracker, which
+//! binary searches for the earliest end with searches that only accept
+//! matches ending up to a bound.
+
+use super::{pikevm, Anchored, Regex};
+
+impl Regex {
+    /// End of the earliest match: the first position at which some match
+    /// ends. Cheaper than `find` when only whether and where a match
+    /// becomes certain matters.
+    ///
+    /// ```
+    /// use quickjs_regex::Regex;
+    ///
+    /// let re = Regex::new(r"abcd|c").unwrap();
+    /// assert_eq!(re.shortest_match("abcd"), Some(3));
+    /// assert_eq!(re.find("abcd").map(|m| m.end), Some(4));
+    /// ```
+    pub fn shortest_match(&self, text: &str) -> Option<usize> {
+        self.shortest_match_at(text, 0)
+    }
+
+    /// Like `shortest_match`, for matches starting at or after `start`.
+    pub fn shortest_match_at(&self, text: &str, start: usize) -> Option<usize> {
+        self.shortest_match_at_bytes(text.as_bytes(), start, None)
+    }
+
+    /// Like `shortest_match_at`, reusing scratch space (and its warm DFA
+    /// cache) from `create_scratch` on this regex.
+    pub fn shortest_match_at_scratch(&self, text: &str, start: usize, scratch: &mut pikevm::Scratch) -> Option<usize> {
+        self.shortest_match_at_bytes(text.as_bytes(), start, Some(scratch))
+    }
+
+    pub(crate) fn shortest_match_at_bytes(&self, text: &[u8], start: usize, scratch: Option<&mut pikevm::Scratch>) -> Option<usize> {
+        if start > text.len() {
+            return None;
+        }
+        if !self.use_pike_vm {
+            return self.shortest_match_backtracking(text, start);
+        }
+        let vm = pikevm::PikeVm::new(self.bytecode_slice(), text);
+        let wide_nfa = self.bit_program.as_ref();
+        match scratch {
+            Some(scratch) => scratch.shortest_match_end(&vm, wide_nfa, start),
+            None => self.create_scratch().shortest_match_end(&vm, wide_nfa, start),
+        }
+    }
+
+    /// `shortest_match_at` on the backtracker: binary search between `start`
+    /// and the end of the leftmost-first match.
+    fn shortest_match_backtracking(&self, text: &[u8], start: usize) -> Option<usize> {
+        // A match ends at `hi`, none ends before `lo`
+        let mut hi = self.find_at_bytes(text, start)?.end;
+        let mut lo = start;
+        while lo < hi {
+            let mid = lo + (hi - lo) / 2;
+            match self.span_slots(text, start..mid, Anchored::No) {
+                Some(slots) => hi = slots[1]?,
+                None => lo = mid + 1,
+            }
+        }
+        Some(hi)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Regex, RegexBuilder};
+
+    #[test]
+    fn test_earliest_end() {
+        let cases = [
+            (r"\d+|a\w+", "xabc 123", Some(3)),
+            ("a+", "baaa", Some(2)),
+            ("abcd|c", "abcd", Some(3)),
+            ("x*", "abc", Some(0)),
+            ("\u{e9}+", "caf\u{e9}\u{e9}", Some(5)),
+            ("z", "abc", None),
+        ];
+        for (pattern, text, expected) in cases {
+            let re = Regex::new(pattern).unwrap();
+            assert_eq!(re.shortest_match(text), expected, "{}", pattern);
+            // Lookaround that always holds sends the pattern to the backtracker
+            let re = Regex::new(&format!(r"(?:(?=[\s\S])|$)(?:{})", pattern)).unwrap();
+            assert_eq!(re.shortest_match(text), expected, "{} (backtracking)", pattern);
+        }
+        let re = Regex::new(r"\d+").unwrap();
+        assert_eq!(re.shortest_match_at("12 34", 1), Some(2));
+        assert_eq!(re.shortest_match_at("12 34", 2), Some(4));
+        assert_eq!(re.shortest_match_at("12 34", 6), None);
+    }
+
+    #[test]
+    fn test_assertions() {
+        let cases = [
+            (r"\bfoo\b", "foobar foo", Some(10)),
+            ("o$", "foo\nfoo", Some(7)),
+            ("(?m)o$", "fo\nfoo", Some(2)),
+            ("(?m)^f", "xf\nfo", Some(4)),
+            (r"(a)\1|b", "xaab", Some(3)),
+            ("x(?=y)|z", "xz xy", Some(2)),
+        ];
+        for (pattern, text, expected) in cases {
+            let re = Regex::new(pattern).unwrap();
+            assert_eq!(re.shortest_match(text), expected, "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_scratch_reuse_and_small_dfa_cache() {
+        let texts = ["end x", "x end", "no", "ending\nend", "send"];
+        let cases = [
+            (r"\bend$", [None, Some(5), None, Some(10), None]),
+            ("end$", [None, Some(5), None, Some(10), Some(4)]),
+            (r"(?m)^e\w+", [Some(2), None, None, Some(2), None]),
+        ];
+        for (pattern, expected) in cases {
+            let re = Regex::new(pattern).unwrap();
+            let tiny = RegexBuilder::new(pattern).dfa_cache_size(1).build().unwrap();
+            let mut scratch = re.create_scratch();
+            for (text, expected) in texts.into_iter().zip(expected) {
+                assert_eq!(re.shortest_match_at_scratch(text, 0, &mut scratch), expected, "{} {:?}", pattern, text);
+                assert_eq!(tiny.shortest_match(text), expected, "{} {:?}", pattern, text);
+            }
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! `String.prototype.split` with a regex separator.
+//!
+//! Follows `RegExp.prototype[Symbol.split]`: the separator is tried as a
+//! sticky match at each position, capture groups are spliced into the
+//! result (`None` for groups that did not participate), an empty match
+//! where the previous piece ended never splits, and no match is attempted
+//! at the very end of the input.
+
+use super::{utf8_char_len, Regex};
+
+impl Regex {
+    /// Split `text` around matches of the pattern, like JavaScript's
+    /// `text.split(regex)`. Captured groups are included between the pieces.
+    pub fn split<'t>(&self, text: &'t str) -> Vec<Option<&'t str>> {
+        self.splitn(text, usize::MAX)
+    }
+
+    /// Like `split`, but returns at most `limit` elements (pieces and groups
+    /// together), like JavaScript's `text.split(regex, limit)`.
+    pub fn splitn<'t>(&self, text: &'t str, limit: usize) -> Vec<Option<&'t str>> {
+        let mut out = Vec::new();
+        if limit == 0 {
+            return out;
+        }
+        let bytes = text.as_bytes();
+        if bytes.is_empty() {
+            if self.find_at_bytes(bytes, 0).is_none() {
+                out.push(Some(text));
+            }
+            return out;
+        }
+
+        let has_groups = self.capture_count() > 1;
+        let sticky = self.flags().is_sticky();
+        // p: end of the last separator, q: where the next match is tried
+        let mut p = 0;
+        let mut q = 0;
+        while q < bytes.len() {
+            // The leftmost match at or after q is the first position the
+            // spec's sticky loop would succeed at
+            let groups = if has_groups {
+                self.captures_at_bytes(bytes, q)
+            } else {
+                self.find_at_bytes(bytes, q).map(|m| vec![Some((m.start, m.end))])
+            };
+            let (start, end) = match groups.as_ref().and_then(|g| g[0]) {
+                Some((start, _)) if start >= bytes.len() => break,
+                Some(span) => span,
+                // A sticky regex only tries q itself; keep stepping
+                None if sticky => {
+                    q += utf8_char_len(bytes, q);
+                    continue;
+                }
+                None => break,
+            };
+            if end == p {
+                q = start + utf8_char_len(bytes, start);
+                continue;
+            }
+
+            out.push(Some(&text[p..start]));
+            if out.len() == limit {
+                return out;
+            }
+            p = end;
+            for group in groups.iter().flatten().skip(1) {
+                out.push(group.map(|(s, e)| &text[s..e]));
+                if out.len() == limit {
+                    return out;
+                }
+            }
+            q = p;
+        }
+
+        out.push(Some(&text[p..]));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{Flags, Regex};
+
+    fn split<'t>(pattern: &str, text: &'t str) -> Vec<Option<&'t str>> {
+        Regex::new(pattern).unwrap().split(text)
+    }
+
+    #[test]
+    fn test_basic_split() {
+        assert_eq!(split(r",\s*", "a, b,c"), vec![Some("a"), Some("b"), Some("c")]);
+        assert_eq!(split(",", ",a,"), vec![Some(""), Some("a"), Some("")]);
+        assert_eq!(split("x", "abc"), vec![Some("abc")]);
+    }
+
+    #[test]
+    fn test_empty_matches() {
+        assert_eq!(split("", "abc"), vec![Some("a"), Some("b"), Some("c")]);
+        // No split at the start or at the end of the input
+        assert_eq!(split("x*", "axxb"), vec![Some("a"), Some("b")]);
+        assert_eq!(split(r"\b", "ab cd"), vec![Some("ab"), Some(" "), Some("cd")]);
+        assert_eq!(split("", "\u{e9}\u{1F600}"), vec![Some("\u{e9}"), Some("\u{1F600}")]);
+    }
+
+    #[test]
+    fn test_empty_input() {
+        assert_eq!(split("", ""), Vec::<Option<&str>>::new());
+        assert_eq!(split("a*", ""), Vec::<Option<&str>>::new());
+        assert_eq!(split("a", ""), vec![Some("")]);
+    }
+
+    #[test]
+    fn test_captures_are_spliced_in() {
+        assert_eq!(split("(-)|(\\+)", "1-2+3"), vec![
+            Some("1"), Some("-"), None, Some("2"), None, Some("+"), Some("3"),
+        ]);
+    }
+
+    #[test]
+    fn test_limit() {
+        let re = Regex::new("(,)").unwrap();
+        assert_eq!(re.splitn("a,b,c", 0), Vec::<Option<&str>>::new());
+        assert_eq!(re.splitn("a,b,c", 1), vec![Some("a")]);
+        assert_eq!(re.splitn("a,b,c", 2), vec![Some("a"), Some(",")]);
+        assert_eq!(re.splitn("a,b,c", 4), vec![Some("a"), Some(","), Some("b"), Some(",")]);
+    }
+
+    #[test]
+    fn test_sticky_behaves_like_non_sticky() {
+        let re = Regex::with_flags(",", Flags::from_bits(Flags::STICKY)).unwrap();
+        assert_eq!(re.split("a,b,c"), vec![Some("a"), Some("b"), Some("c")]);
+        let re = Regex::with_flags("", Flags::from_bits(Flags::STICKY)).unwrap();
+        assert_eq!(re.split("ab"), vec![Some("a"), Some("b")]);
+    }
+
+    #[test]
+    fn test_lookbehind_sees_previous_piece() {
+        assert_eq!(split("(?<=a)", "aab"), vec![Some("a"), Some("a"), Some("b")]);
+    }
+}
@@ -0,0 +1,317 @@
+//! Searching an `io::Read` source chunk by chunk.
+//!
+//! The searcher keeps a window of the stream in memory. It searches the
+//! window and reports a match only once the bytes read so far decide it,
+//! i.e. when at least `max_match_len` bytes (plus one char of lookahead for
+//! `$` and `\b`) follow its start. Positions that can no longer start a
+//! match are dropped, keeping `max_match_len` bytes before the search
+//! position as context for `\b` and lookbehind, and the next chunk is
+//! appended.
+//!
+//! Patterns the Pike VM runs are searched with one `PikeScanner` per window
+//! sharing a single lazy DFA cache, so the DFA stays warm across chunks.
+
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::io::{self, Read};
+
+use super::{pikevm, utf8_char_len, Regex};
+
+/// Bytes of lookahead past a match for `$` and `\b` (one UTF-8 char).
+const SLACK: usize = 4;
+
+/// A match in a stream, with absolute byte offsets from the stream start.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamMatch {
+    /// Start byte offset (inclusive)
+    pub start: u64,
+    /// End byte offset (exclusive)
+    pub end: u64,
+}
+
+impl StreamMatch {
+    /// Get the length of the match in bytes
+    pub fn len(&self) -> u64 {
+        self.end - self.start
+    }
+
+    /// Check if the match is empty
+    pub fn is_empty(&self) -> bool {
+        self.start == self.end
+    }
+}
+
+/// Iterator over the non-overlapping matches in a stream, created by
+/// `bytes::Regex::stream`.
+///
+/// Matches (and lookarounds) are assumed to span at most `max_match_len`
+/// bytes. A longer match may be reported cut short, or missed.
+///
+/// ```
+/// use quickjs_regex::bytes::Regex;
+///
+/// let re = Regex::new(r"\d+").unwrap();
+/// let input: &[u8] = b"a1 b22 c333";
+/// let mut searcher = re.stream(input).chunk_size(4);
+/// let first = searcher.next().unwrap().unwrap();
+/// assert_eq!((first.start, first.end), (1, 2));
+/// assert_eq!(searcher.matched_bytes(), b"1");
+/// assert_eq!(searcher.map(|m| m.unwrap().start).collect::<Vec<_>>(), vec![4, 8]);
+/// ```
+pub struct StreamSearcher<'r, R> {
+    regex: &'r Regex,
+    reader: R,
+    /// The undecided rest of the stream read so far, with context before it
+    buf: Vec<u8>,
+    /// Stream offset of `buf[0]`
+    offset: u64,
+    /// Where the next search starts in `buf`
+    pos: usize,
+    last_was_empty: bool,
+    /// Decided matches not yet returned
+    queue: VecDeque<StreamMatch>,
+    /// Span in `buf` of the match last returned
+    last: (usize, usize),
+    eof: bool,
+    failed: bool,
+    /// Read error held back until the bytes read before it are searched
+    error: Option<io::Error>,
+    chunk_size: usize,
+    max_match_len: usize,
+    /// DFA cache shared by the scanners of all windows
+    dfa: Option<RefCell<pikevm::LazyDfa>>,
+}
+
+impl<'r, R: Read> StreamSearcher<'r, R> {
+    pub(crate) fn new(regex: &'r Regex, reader: R) -> Self {
+        let dfa = regex.use_pike_vm.then(|| {
+            let mut dfa = pikevm::LazyDfa::with_classes(regex.bytecode_slice());
+            dfa.set_max_states(regex.config.dfa_cache_size);
+            RefCell::new(dfa)
+        });
+        StreamSearcher {
+            regex,
+            reader,
+            buf: Vec::new(),
+            offset: 0,
+            pos: 0,
+            last_was_empty: false,
+            queue: VecDeque::new(),
+            last: (0, 0),
+            eof: false,
+            failed: false,
+            error: None,
+            chunk_size: 64 * 1024,
+            max_match_len: 64 * 1024,
+            dfa,
+        }
+    }
+
+    /// Bytes read from the source at a time (default: 64 KiB).
+    pub fn chunk_size(mut self, bytes: usize) -> Self {
+        self.chunk_size = bytes.max(1);
+        self
+    }
+
+    /// Longest match (including lookarounds) the searcher must find intact
+    /// (default: 64 KiB). Memory use is about twice this plus the chunk size.
+    pub fn max_match_len(mut self, bytes: usize) -> Self {
+        self.max_match_len = bytes;
+        self
+    }
+
+    /// Bytes of the match last returned by `next`.
+    pub fn matched_bytes(&self) -> &[u8] {
+        &self.buf[self.last.0..self.last.1]
+    }
+
+    /// Give back the source.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// First buffer position that can start an undecided match.
+    fn limit(&self) -> usize {
+        if self.eof {
+            usize::MAX
+        } else {
+            self.buf.len().saturating_sub(self.max_match_len + SLACK)
+        }
+    }
+
+    /// Search the buffer from `pos`, queueing the matches the bytes read so
+    /// far decide.
+    fn scan_buffer(&mut self) {
+        let limit = self.limit();
+        let mut scanner = self.dfa.as_ref()
+            .map(|dfa| pikevm::PikeScanner::with_cache(self.regex.bytecode_slice(), &self.buf, dfa));
+        loop {
+            let start = if self.last_was_empty {
+                // Step over one char, once it is complete
+                if self.pos >= self.buf.len() || (!self.eof && self.pos + SLACK > self.buf.len()) {
+                    return;
+                }
+                self.pos + utf8_char_len(&self.buf, self.pos)
+            } else {
+                self.pos
+            };
+            let found = match scanner.as_mut() {
+                Some(scanner) => scanner.find_next(start),
+                None => self.regex.find_at_bytes(&self.buf, start).map(|m| (m.start, m.end)),
+            };
+            match found {
+                Some((s, e)) if s < limit => {
+                    self.queue.push_back(StreamMatch { start: self.offset + s as u64, end: self.offset + e as u64 });
+                    self.pos = e;
+                    self.last_was_empty = s == e;
+                }
+                _ => {
+                    self.pos = start;
+                    self.last_was_empty = false;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// Drop what can no longer take part in a match and read the next chunk.
+    fn refill(&mut self) -> io::Result<()> {
+        if let Some(e) = self.error.take() {
+            return Err(e);
+        }
+        // No match starts before the limit
+        let limit = self.limit();
+        while self.pos < limit {
+            self.pos += utf8_char_len(&self.buf, self.pos);
+        }
+        let keep_from = self.pos.saturating_sub(self.max_match_len.max(SLACK));
+        self.buf.drain(..keep_from);
+        self.offset += keep_from as u64;
+        self.pos -= keep_from;
+        self.last = (0, 0);
+
+        let kept = self.buf.len();
+        let mut filled = kept;
+        self.buf.resize(filled + self.chunk_size, 0);
+        while filled < self.buf.len() {
+            match self.reader.read(&mut self.buf[filled..]) {
+                Ok(0) => {
+                    self.eof = true;
+                    break;
+                }
+                Ok(n) => filled += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.error = Some(e);
+                    break;
+                }
+            }
+        }
+        self.buf.truncate(filled);
+        match self.error.take() {
+            Some(e) if filled == kept => Err(e),
+            e => {
+                self.error = e;
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'r, R: Read> Iterator for StreamSearcher<'r, R> {
+    type Item = io::Result<StreamMatch>;
+
+    fn next(&mut self) -> Option<io::Result<StreamMatch>> {
+        loop {
+            if let Some(m) = self.queue.pop_front() {
+                let start = (m.start - self.offset) as usize;
+                self.last = (start, start + m.len() as usize);
+                return Some(Ok(m));
+            }
+            if self.eof || self.failed {
+                return None;
+            }
+            if let Err(e) = self.refill() {
+                self.failed = true;
+                return Some(Err(e));
+            }
+            self.scan_buffer();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{self, Read};
+
+    use crate::regex::bytes::Regex;
+
+    /// Reader handing out a few bytes per call
+    struct Trickle<'a> {
+        data: &'a [u8],
+        step: usize,
+    }
+
+    impl Read for Trickle<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+            self.step = self.step % 7 + 1;
+            let n = self.step.min(buf.len()).min(self.data.len());
+            buf[..n].copy_from_slice(&self.data[..n]);
+            self.data = &self.data[n..];
+            Ok(n)
+        }
+    }
+
+    #[test]
+    fn test_matches_in_memory_search() {
+        let mut hay = Vec::new();
+        for i in 0..40 {
+            hay.extend_from_slice(format!("line {} foobar word caf\u{e9} \u{1F600}x ", i * 37).as_bytes());
+            hay.extend_from_slice(if i % 3 == 0 { b"ab\xffab\n" } else { b"foo\n" });
+        }
+        let patterns = [r"\d+", "foo|foobar", r"\bword\b", r"(ab)\xff\1", "x*", "^line", r"\n$", r"(?<=caf.) \S+", "[^ ]+"];
+        for pattern in patterns {
+            let re = Regex::new(pattern).unwrap();
+            let expected: Vec<_> = re.find_iter(&hay).map(|m| (m.start as u64, m.end as u64)).collect();
+            for (chunk, max_len) in [(3, 8), (16, 16), (1000, 64)] {
+                let source = Trickle { data: &hay, step: 0 };
+                let found: Vec<_> = re.stream(source).chunk_size(chunk).max_match_len(max_len)
+                    .map(|m| m.map(|m| (m.start, m.end)))
+                    .collect::<io::Result<_>>()
+                    .unwrap();
+                assert_eq!(found, expected, "{} chunk={}", pattern, chunk);
+            }
+        }
+    }
+
+    #[test]
+    fn test_matched_bytes() {
+        let re = Regex::new(r"[a-z]+\d").unwrap();
+        let hay = b"..abc1..de2..f3".repeat(50);
+        let mut searcher = re.stream(&hay[..]).chunk_size(5).max_match_len(8);
+        while let Some(m) = searcher.next() {
+            let m = m.unwrap();
+            assert_eq!(searcher.matched_bytes(), &hay[m.start as usize..m.end as usize]);
+        }
+    }
+
+    #[test]
+    fn test_read_error_ends_search() {
+        struct Failing(bool);
+        impl Read for Failing {
+            fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+                if std::mem::replace(&mut self.0, true) {
+                    return Err(io::Error::new(io::ErrorKind::Other, "broken"));
+                }
+                buf[..8].copy_from_slice(b"a1 bbbb2");
+                Ok(8)
+            }
+        }
+        let re = Regex::new(r"\d").unwrap();
+        let mut searcher = re.stream(Failing(false)).chunk_size(8).max_match_len(0);
+        assert_eq!(searcher.next().unwrap().unwrap().start, 1);
+        // The held-back error comes before the undecided match at 7
+        assert!(searcher.next().unwrap().is_err());
+        assert!(searcher.next().is_none());
+    }
+}
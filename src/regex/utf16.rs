@@ -0,0 +1,252 @@
+//! UTF-16 haystack support.
+//!
+//! The engines match UTF-8, so a UTF-16 (or WTF-16) haystack is transcoded
+//! to WTF-8 and match offsets are mapped back to code unit indices, i.e.
+//! JavaScript string indices. A `Utf16Haystack` keeps the transcoding for
+//! later searches. In unicode mode surrogate pairs become one char; without
+//! it every code unit is a char of its own, so `.` matches half a pair and
+//! astral chars in the pattern are compiled to match their two surrogates.
+//! Lone surrogates are kept as their own char (WTF-8 encoded), so `.` and
+//! negated classes still match them.
+
+use std::cell::OnceCell;
+use std::sync::Arc;
+
+use super::{Match, MatchIndices};
+
+/// A UTF-16 haystack for any number of searches. It is transcoded on the
+/// first search that needs it, once for unicode-mode regexes and once for
+/// the others.
+///
+/// ```
+/// use quickjs_regex::{Regex, Utf16Haystack};
+///
+/// let text: Vec<u16> = "one two three".encode_utf16().collect();
+/// let haystack = Utf16Haystack::new(&text);
+/// let re = Regex::new(r"\w+").unwrap();
+/// let mut starts = Vec::new();
+/// let mut at = 0;
+/// while let Some(m) = re.find_in_utf16(&haystack, at) {
+///     starts.push(m.start);
+///     at = m.end;
+/// }
+/// assert_eq!(starts, [0, 4, 8]);
+/// ```
+#[derive(Debug)]
+pub struct Utf16Haystack<'t> {
+    text: &'t [u16],
+    /// Transcoded with surrogate pairs as one char, for unicode mode
+    code_points: OnceCell<Transcoded>,
+    /// Transcoded with every code unit as a char
+    code_units: OnceCell<Transcoded>,
+}
+
+impl<'t> Utf16Haystack<'t> {
+    pub fn new(text: &'t [u16]) -> Self {
+        Utf16Haystack { text, code_points: OnceCell::new(), code_units: OnceCell::new() }
+    }
+
+    /// The code units searched.
+    pub fn text(&self) -> &'t [u16] {
+        self.text
+    }
+
+    /// The haystack transcoded for a regex with or without unicode mode.
+    pub(crate) fn transcoded(&self, unicode: bool) -> &Transcoded {
+        match unicode {
+            true => self.code_points.get_or_init(|| Transcoded::new(self.text, true)),
+            false => self.code_units.get_or_init(|| Transcoded::new(self.text, false)),
+        }
+    }
+}
+
+/// A UTF-16 haystack transcoded to WTF-8, with offset maps in both directions.
+#[derive(Debug)]
+pub(crate) struct Transcoded {
+    /// WTF-8 encoding of the haystack
+    pub bytes: Vec<u8>,
+    /// Code unit index → byte offset of the char containing it
+    unit_to_byte: Vec<usize>,
+    /// Byte offset → code unit index of the char containing it
+    byte_to_unit: Vec<usize>,
+}
+
+impl Transcoded {
+    /// Transcode `text`. Surrogate pairs become one char in unicode mode
+    /// and stay two lone surrogates otherwise.
+    pub fn new(text: &[u16], unicode: bool) -> Self {
+        let mut bytes = Vec::with_capacity(text.len() + text.len() / 2);
+        let mut unit_to_byte = Vec::with_capacity(text.len() + 1);
+        let mut byte_to_unit = Vec::with_capacity(text.len() + text.len() / 2 + 1);
+
+        let mut i = 0;
+        while i < text.len() {
+            let unit = text[i] as u32;
+            let is_pair = unicode
+                && (0xD800..0xDC00).contains(&unit)
+                && i + 1 < text.len()
+                && (0xDC00..0xE000).contains(&(text[i + 1] as u32));
+            let (c, units) = if is_pair {
+                (0x10000 + ((unit - 0xD800) << 10) + (text[i + 1] as u32 - 0xDC00), 2)
+            } else {
+                (unit, 1)
+            };
+
+            let offset = bytes.len();
+            encode_wtf8(c, &mut bytes);
+            for _ in 0..units {
+                unit_to_byte.push(offset);
+            }
+            byte_to_unit.resize(bytes.len(), i);
+            i += units;
+        }
+        unit_to_byte.push(bytes.len());
+        byte_to_unit.push(text.len());
+
+        Transcoded { bytes, unit_to_byte, byte_to_unit }
+    }
+
+    /// Byte offset for a code unit index. An index in the middle of a surrogate
+    /// pair maps to the start of the pair (the code point containing it).
+    #[inline]
+    pub fn byte_offset(&self, unit: usize) -> usize {
+        self.unit_to_byte[unit]
+    }
+
+    /// Code unit index for a byte offset at a char boundary.
+    #[inline]
+    pub fn unit_offset(&self, byte: usize) -> usize {
+        self.byte_to_unit[byte]
+    }
+
+    /// Map a byte-offset match back to code unit indices.
+    #[inline]
+    pub fn to_units(&self, m: Match) -> Match {
+        Match { start: self.unit_offset(m.start), end: self.unit_offset(m.end) }
+    }
+}
+
+/// Encode a code point as UTF-8, allowing surrogates (WTF-8).
+fn encode_wtf8(c: u32, out: &mut Vec<u8>) {
+    if c < 0x80 {
+        out.push(c as u8);
+    } else if c < 0x800 {
+        out.push((0xC0 | (c >> 6)) as u8);
+        out.push((0x80 | (c & 0x3F)) as u8);
+    } else if c < 0x10000 {
+        out.push((0xE0 | (c >> 12)) as u8);
+        out.push((0x80 | ((c >> 6) & 0x3F)) as u8);
+        out.push((0x80 | (c & 0x3F)) as u8);
+    } else {
+        out.push((0xF0 | (c >> 18)) as u8);
+        out.push((0x80 | ((c >> 12) & 0x3F)) as u8);
+        out.push((0x80 | ((c >> 6) & 0x3F)) as u8);
+        out.push((0x80 | (c & 0x3F)) as u8);
+    }
+}
+
+/// Captured groups from a match on a UTF-16 haystack.
+///
+/// Offsets are UTF-16 code unit indices. Group 0 is the entire match.
+#[derive(Debug, Clone)]
+pub struct Utf16Captures {
+    /// The code units the groups span, from `offset` on
+    text: Vec<u16>,
+    /// Code unit index of `text[0]` in the haystack
+    offset: usize,
+    /// Pairs of (start, end) code unit indices for each group
+    /// None means the group didn't participate in the match
+    groups: Vec<Option<(usize, usize)>>,
+    /// Group names by group number, shared with the regex
+    names: Arc<[Option<String>]>,
+    /// Whether the regex has the `d` flag
+    has_indices: bool,
+}
+
+impl Utf16Captures {
+    pub(crate) fn new(
+        text: &[u16],
+        transcoded: &Transcoded,
+        groups: Vec<Option<(usize, usize)>>,
+        names: Arc<[Option<String>]>,
+        has_indices: bool,
+    ) -> Self {
+        let groups: Vec<_> = groups.into_iter()
+            .map(|g| g.map(|(s, e)| (transcoded.unit_offset(s), transcoded.unit_offset(e))))
+            .collect();
+        // Groups in a lookahead can lie outside group 0
+        let start = groups.iter().flatten().map(|&(s, _)| s).min().unwrap_or(0);
+        let end = groups.iter().flatten().map(|&(_, e)| e).max().unwrap_or(start);
+        Utf16Captures { text: text[start..end].to_vec(), offset: start, groups, names, has_indices }
+    }
+
+    /// Get the number of capture groups (including group 0).
+    pub fn len(&self) -> usize {
+        self.groups.len()
+    }
+
+    /// Check if there are no captures (should never be true for a valid match).
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// Get a specific capture group by index, in code units.
+    pub fn get(&self, i: usize) -> Option<Match> {
+        self.groups.get(i).and_then(|opt| {
+            opt.map(|(start, end)| Match { start, end })
+        })
+    }
+
+    /// Get the code units of a specific capture group.
+    pub fn get_utf16(&self, i: usize) -> Option<&[u16]> {
+        self.get(i).map(|m| &self.text[m.start - self.offset..m.end - self.offset])
+    }
+
+    /// Get the entire match (group 0).
+    pub fn entire_match(&self) -> Option<Match> {
+        self.get(0)
+    }
+
+    /// Iterate over all capture groups.
+    pub fn iter(&self) -> impl Iterator<Item = Option<Match>> + '_ {
+        self.groups.iter().map(|opt| {
+            opt.map(|(start, end)| Match { start, end })
+        })
+    }
+
+    /// Count the number of capture groups that actually matched (non-None).
+    pub fn count_matched(&self) -> usize {
+        self.groups.iter().filter(|g| g.is_some()).count()
+    }
+
+    /// Match indices in code units, or `None` unless the regex has the
+    /// `d` flag.
+    pub fn indices(&self) -> Option<MatchIndices> {
+        self.has_indices.then(|| MatchIndices::new(&self.groups, self.names.clone()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_transcode_offsets() {
+        // "a😀b" + lone lead surrogate
+        let text = [0x61, 0xD83D, 0xDE00, 0x62, 0xD800];
+        let t = Transcoded::new(&text, true);
+        assert_eq!(t.bytes, b"a\xf0\x9f\x98\x80b\xed\xa0\x80");
+        assert_eq!(t.byte_offset(2), 1);
+        assert_eq!(t.unit_offset(5), 3);
+        assert_eq!(t.unit_offset(t.bytes.len()), 5);
+    }
+
+    #[test]
+    fn test_pairs_split_non_unicode() {
+        let text = [0xD83D, 0xDE00];
+        let t = Transcoded::new(&text, false);
+        assert_eq!(t.bytes, b"\xed\xa0\xbd\xed\xb8\x80");
+        assert_eq!(t.byte_offset(1), 3);
+        assert_eq!(t.unit_offset(3), 1);
+    }
+}
@@ -304,6 +304,44 @@ pub fn unicode_from_utf8(buf: &[u8]) -> Option<(u32, usize)> {
     Some((c, l + 1))
 }
 
+/// Decodes the char starting at `input[pos]` for the matching engines.
+/// Returns `(codepoint, bytes_consumed)`. Encoded surrogates (WTF-8) decode to
+/// the surrogate code point. Invalid UTF-8 (stray continuation bytes, truncated
+/// or overlong sequences) is consumed one byte at a time: as U+FFFD in unicode
+/// mode, otherwise as the byte value itself.
+#[inline]
+pub fn decode_utf8_at(input: &[u8], pos: usize, unicode: bool) -> (u32, usize) {
+    let b0 = input[pos];
+    if b0 < 0x80 {
+        return (b0 as u32, 1);
+    }
+    let end = (pos + 4).min(input.len());
+    match unicode_from_utf8(&input[pos..end]) {
+        Some((c, len)) if len <= 4 && c <= 0x10FFFF => (c, len),
+        _ => (if unicode { 0xFFFD } else { b0 as u32 }, 1),
+    }
+}
+
+/// Decodes the char ending at `input[pos - 1]`, consistent with `decode_utf8_at`.
+/// Returns `(codepoint, bytes_consumed)`. `pos` must be greater than 0.
+#[inline]
+pub fn decode_utf8_before(input: &[u8], pos: usize, unicode: bool) -> (u32, usize) {
+    let last = input[pos - 1];
+    if last < 0x80 {
+        return (last as u32, 1);
+    }
+    let mut start = pos - 1;
+    while start > 0 && pos - start < 4 && (input[start] & 0xC0) == 0x80 {
+        start -= 1;
+    }
+    let (c, len) = decode_utf8_at(input, start, unicode);
+    if start + len == pos {
+        (c, len)
+    } else {
+        decode_utf8_at(input, pos - 1, unicode)
+    }
+}
+
 /// Panics with an assertion failure message (for legacy C code compatibility).
 pub fn assert_fail(assertion: &str, file: &str, line: u32, function: &str) -> ! {
     panic!(
@@ -0,0 +1,521 @@
+//! Structural checks on bytecode loaded from outside the compiler.
+//!
+//! The engines read operands through unchecked pointer reads and trust
+//! every jump, capture and register index they find, so bytecode that did
+//! not come from `compiler::compile_regex` has to pass `verify_bytecode`
+//! before any engine sees it. The checks cover what the engines rely on
+//! for memory safety: the header agrees with the length, every instruction
+//! is known and fits, jumps land on instruction starts without crossing
+//! into or out of a lookaround body, capture and register indices are in
+//! range, lookaround bodies nest and end with their match instruction,
+//! execution can't run off the end, and no loop can go round forever
+//! without consuming input. Whether the bytecode means anything is not
+//! checked.
+
+use std::collections::HashSet;
+
+use super::bitvm::instruction_size;
+use super::error::BytecodeError;
+use super::{Flags, OpCode};
+
+const HEADER_LEN: usize = 8;
+
+const KNOWN_FLAGS: u16 = Flags::GLOBAL
+    | Flags::IGNORE_CASE
+    | Flags::MULTILINE
+    | Flags::DOT_ALL
+    | Flags::UNICODE
+    | Flags::STICKY
+    | Flags::INDICES
+    | Flags::NAMED_GROUPS
+    | Flags::UNICODE_SETS;
+
+/// Check `bytecode`, header included, before handing it to an engine.
+/// `Regex::from_bytes` runs this on the bytecode it loads.
+///
+/// ```
+/// use quickjs_regex::{verify_bytecode, BytecodeError};
+///
+/// assert_eq!(verify_bytecode(b"short"), Err(BytecodeError::Length { len: 5 }));
+/// ```
+pub fn verify_bytecode(bytecode: &[u8]) -> Result<(), BytecodeError> {
+    let len = bytecode.len();
+    if len < HEADER_LEN {
+        return Err(BytecodeError::Length { len });
+    }
+    let flags = u16::from_le_bytes([bytecode[0], bytecode[1]]);
+    if flags & !KNOWN_FLAGS != 0 {
+        return Err(BytecodeError::UnknownFlags { bits: flags & !KNOWN_FLAGS });
+    }
+    let capture_count = bytecode[2];
+    let register_count = bytecode[3];
+    if capture_count == 0 {
+        return Err(BytecodeError::NoCaptures);
+    }
+    let body_len = u32::from_le_bytes([bytecode[4], bytecode[5], bytecode[6], bytecode[7]]) as usize;
+    if HEADER_LEN.checked_add(body_len) != Some(len) || body_len == 0 {
+        return Err(BytecodeError::Length { len });
+    }
+
+    let code = Code::decode(bytecode)?;
+    let operand = |pc: usize, at: usize| bytecode[pc + at];
+    let u32_at = |pc: usize, at: usize| {
+        let at = pc + at;
+        u32::from_le_bytes([bytecode[at], bytecode[at + 1], bytecode[at + 2], bytecode[at + 3]])
+    };
+    let capture = |pc: usize, index: u8| match index < capture_count {
+        true => Ok(()),
+        false => Err(BytecodeError::CaptureIndex { pc, index }),
+    };
+    let register = |pc: usize, index: u8| match index < register_count {
+        true => Ok(()),
+        false => Err(BytecodeError::RegisterIndex { pc, index }),
+    };
+
+    for (i, &pc) in code.starts.iter().enumerate() {
+        let jump = |offset_at: usize| {
+            let next = pc + instruction_size(bytecode, pc);
+            code.check_jump(i, pc, next as i64 + u32_at(pc, offset_at) as i32 as i64)
+        };
+        match OpCode::from_u8(bytecode[pc]) {
+            Some(OpCode::Goto | OpCode::SplitGotoFirst | OpCode::SplitNextFirst) => jump(1)?,
+            Some(OpCode::Lookahead | OpCode::NegativeLookahead) => jump(1)?,
+            Some(OpCode::SaveStart | OpCode::SaveEnd) => capture(pc, operand(pc, 1))?,
+            Some(OpCode::SaveReset) => {
+                let (first, last) = (operand(pc, 1), operand(pc, 2));
+                capture(pc, last)?;
+                if first > last {
+                    return Err(BytecodeError::Operand { pc });
+                }
+            }
+            Some(OpCode::Loop) => {
+                register(pc, operand(pc, 1))?;
+                jump(2)?;
+            }
+            Some(
+                OpCode::LoopSplitGotoFirst
+                | OpCode::LoopSplitNextFirst
+                | OpCode::LoopCheckAdvSplitGotoFirst
+                | OpCode::LoopCheckAdvSplitNextFirst,
+            ) => {
+                register(pc, operand(pc, 1))?;
+                jump(6)?;
+            }
+            Some(OpCode::SetI32 | OpCode::SetCharPos | OpCode::CheckAdvance) => register(pc, operand(pc, 1))?,
+            Some(
+                OpCode::BackReference
+                | OpCode::BackReferenceI
+                | OpCode::BackwardBackReference
+                | OpCode::BackwardBackReferenceI,
+            ) => {
+                let count = operand(pc, 1) as usize;
+                if count == 0 {
+                    return Err(BytecodeError::Operand { pc });
+                }
+                for &group in &bytecode[pc + 2..pc + 2 + count] {
+                    capture(pc, group)?;
+                }
+            }
+            Some(OpCode::SpanAny | OpCode::SpanDot | OpCode::SpanClass) => {
+                if u32_at(pc, 1) > u32_at(pc, 5) {
+                    return Err(BytecodeError::Operand { pc });
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let last = *code.starts.last().expect("body is not empty");
+    if !matches!(OpCode::from_u8(bytecode[last]), Some(OpCode::Match | OpCode::Goto)) {
+        return Err(BytecodeError::FallsOffEnd { pc: last });
+    }
+    check_progress(bytecode, &code)
+}
+
+/// Reject cycles a search could go round forever. Instructions that
+/// consume input bound a cycle, unless something that moves backwards is
+/// in the same component. So does a counted loop split whose register the cycle never
+/// resets with `SetI32`, and a `CheckAdvance` that every way round the
+/// cycle follows a `SetCharPos` of the same register. Plain `Loop` wraps
+/// its counter at zero and bounds nothing. Bounded instructions are taken
+/// out until none are left to take out; any cycle that remains is an error.
+fn check_progress(bytecode: &[u8], code: &Code) -> Result<(), BytecodeError> {
+    let n = code.starts.len();
+    let op = |i: usize| OpCode::from_u8(bytecode[code.starts[i]]).expect("opcodes are checked");
+    let reg = |i: usize| bytecode[code.starts[i] + 1];
+    let index = |pc: usize| code.starts.binary_search(&pc).expect("jump targets are checked");
+
+    let succ: Vec<Vec<usize>> = (0..n)
+        .map(|i| {
+            let pc = code.starts[i];
+            let target = |offset_at: usize| {
+                let at = pc + offset_at;
+                let offset = i32::from_le_bytes([bytecode[at], bytecode[at + 1], bytecode[at + 2], bytecode[at + 3]]);
+                index(((pc + instruction_size(bytecode, pc)) as i64 + offset as i64) as usize)
+            };
+            match op(i) {
+                OpCode::Goto => vec![target(1)],
+                OpCode::SplitGotoFirst | OpCode::SplitNextFirst => vec![i + 1, target(1)],
+                OpCode::Lookahead | OpCode::NegativeLookahead => vec![i + 1, target(1)],
+                OpCode::Loop => vec![i + 1, target(2)],
+                OpCode::LoopSplitGotoFirst
+                | OpCode::LoopSplitNextFirst
+                | OpCode::LoopCheckAdvSplitGotoFirst
+                | OpCode::LoopCheckAdvSplitNextFirst => vec![i + 1, target(6)],
+                OpCode::Match | OpCode::LookaheadMatch | OpCode::NegativeLookaheadMatch => Vec::new(),
+                _ => vec![i + 1],
+            }
+        })
+        .collect();
+
+    let everything = vec![true; n];
+    let comp = components(&succ, &everything);
+    let mut backwards = vec![false; n];
+    for i in 0..n {
+        if matches!(op(i), OpCode::Prev | OpCode::BackwardBackReference | OpCode::BackwardBackReferenceI) {
+            backwards[comp[i]] = true;
+        }
+    }
+    let mut live: Vec<bool> = (0..n)
+        .map(|i| !consumes(bytecode, code.starts[i], op(i)) || backwards[comp[i]])
+        .collect();
+
+    loop {
+        let comp = components(&succ, &live);
+        let resets: HashSet<(usize, u8)> = (0..n)
+            .filter(|&i| live[i] && op(i) == OpCode::SetI32)
+            .map(|i| (comp[i], reg(i)))
+            .collect();
+        let mut changed = false;
+        for i in 0..n {
+            let bounded = live[i]
+                && match op(i) {
+                    OpCode::LoopSplitGotoFirst
+                    | OpCode::LoopSplitNextFirst
+                    | OpCode::LoopCheckAdvSplitGotoFirst
+                    | OpCode::LoopCheckAdvSplitNextFirst => !resets.contains(&(comp[i], reg(i))),
+                    OpCode::CheckAdvance => {
+                        !on_cycle(&succ, i, |j| live[j] && !(op(j) == OpCode::SetCharPos && reg(j) == reg(i)))
+                    }
+                    _ => false,
+                };
+            if bounded {
+                live[i] = false;
+                changed = true;
+            }
+        }
+        if !changed {
+            let mut size = vec![0usize; n];
+            for i in (0..n).filter(|&i| live[i]) {
+                size[comp[i]] += 1;
+            }
+            return match (0..n).find(|&i| live[i] && (size[comp[i]] > 1 || succ[i].contains(&i))) {
+                Some(i) => Err(BytecodeError::EmptyLoop { pc: code.starts[i] }),
+                None => Ok(()),
+            };
+        }
+    }
+}
+
+/// Whether the instruction at `pc` moves the position every time it
+/// succeeds.
+fn consumes(bytecode: &[u8], pc: usize, op: OpCode) -> bool {
+    match op {
+        OpCode::Char
+        | OpCode::CharI
+        | OpCode::Char32
+        | OpCode::Char32I
+        | OpCode::Dot
+        | OpCode::Any
+        | OpCode::Space
+        | OpCode::NotSpace
+        | OpCode::Range
+        | OpCode::RangeI
+        | OpCode::Range32
+        | OpCode::Range32I => true,
+        OpCode::SpanAny | OpCode::SpanDot | OpCode::SpanClass => {
+            u32::from_le_bytes([bytecode[pc + 1], bytecode[pc + 2], bytecode[pc + 3], bytecode[pc + 4]]) > 0
+        }
+        _ => false,
+    }
+}
+
+/// Strongly connected components of the `live` instructions, as the index
+/// of one member per instruction; `usize::MAX` for the others.
+fn components(succ: &[Vec<usize>], live: &[bool]) -> Vec<usize> {
+    let n = succ.len();
+    // Depth-first finishing order, then sweep the reversed edges in reverse
+    let mut order = Vec::with_capacity(n);
+    let mut visited = vec![false; n];
+    let mut stack: Vec<(usize, usize)> = Vec::new();
+    for root in 0..n {
+        if !live[root] || visited[root] {
+            continue;
+        }
+        visited[root] = true;
+        stack.push((root, 0));
+        while let Some(top) = stack.last_mut() {
+            let (node, edge) = *top;
+            match succ[node].get(edge) {
+                Some(&next) => {
+                    top.1 += 1;
+                    if live[next] && !visited[next] {
+                        visited[next] = true;
+                        stack.push((next, 0));
+                    }
+                }
+                None => {
+                    order.push(node);
+                    stack.pop();
+                }
+            }
+        }
+    }
+    let mut pred = vec![Vec::new(); n];
+    for (node, nexts) in succ.iter().enumerate().filter(|&(node, _)| live[node]) {
+        for &next in nexts.iter().filter(|&&next| live[next]) {
+            pred[next].push(node);
+        }
+    }
+    let mut comp = vec![usize::MAX; n];
+    for &root in order.iter().rev() {
+        if comp[root] != usize::MAX {
+            continue;
+        }
+        comp[root] = root;
+        let mut todo = vec![root];
+        while let Some(node) = todo.pop() {
+            for &prev in &pred[node] {
+                if comp[prev] == usize::MAX {
+                    comp[prev] = root;
+                    todo.push(prev);
+                }
+            }
+        }
+    }
+    comp
+}
+
+/// Whether `from` can get back to itself through instructions `keep`
+/// allows.
+fn on_cycle(succ: &[Vec<usize>], from: usize, keep: impl Fn(usize) -> bool) -> bool {
+    let mut seen = vec![false; succ.len()];
+    let mut todo = succ[from].clone();
+    while let Some(node) = todo.pop() {
+        if node == from {
+            return true;
+        }
+        if seen[node] || !keep(node) {
+            continue;
+        }
+        seen[node] = true;
+        todo.extend_from_slice(&succ[node]);
+    }
+    false
+}
+
+/// Instruction starts of a body, with the lookaround body each one is in.
+struct Code {
+    starts: Vec<usize>,
+    /// Innermost lookaround per instruction: the index of its opening
+    /// instruction in `starts`, `usize::MAX` outside any
+    regions: Vec<usize>,
+}
+
+impl Code {
+    /// Split the body into instructions and match up lookaround bodies.
+    fn decode(bytecode: &[u8]) -> Result<Code, BytecodeError> {
+        let mut starts = Vec::new();
+        let mut regions = Vec::new();
+        // Open lookarounds: (instruction index, end of body, negative)
+        let mut open: Vec<(usize, usize, bool)> = Vec::new();
+        let mut pc = HEADER_LEN;
+        while pc < bytecode.len() {
+            while let Some(&(_, end, negative)) = open.last() {
+                if pc < end {
+                    break;
+                }
+                let closing = match negative {
+                    true => OpCode::NegativeLookaheadMatch,
+                    false => OpCode::LookaheadMatch,
+                };
+                // A body that ends inside an instruction, or without its match
+                let last_op = starts.last().map(|&last: &usize| bytecode[last]);
+                if pc > end || last_op != Some(closing as u8) {
+                    return Err(BytecodeError::Lookaround { pc });
+                }
+                open.pop();
+            }
+
+            let opcode = bytecode[pc];
+            let op = match OpCode::from_u8(opcode) {
+                Some(OpCode::Invalid) | None => return Err(BytecodeError::UnknownOpcode { pc, opcode }),
+                Some(op) => op,
+            };
+            let size = instruction_size(bytecode, pc);
+            if pc + size > bytecode.len() {
+                return Err(BytecodeError::Truncated { pc });
+            }
+            let region = open.last().map_or(usize::MAX, |&(index, _, _)| index);
+            match op {
+                OpCode::Lookahead | OpCode::NegativeLookahead => {
+                    let offset = i32::from_le_bytes([bytecode[pc + 1], bytecode[pc + 2], bytecode[pc + 3], bytecode[pc + 4]]);
+                    let end = (pc + size) as i64 + offset as i64;
+                    // The body is non-empty and inside the enclosing one
+                    let outer_end = open.last().map_or(bytecode.len(), |&(_, end, _)| end);
+                    if end <= (pc + size) as i64 || end > outer_end as i64 {
+                        return Err(BytecodeError::Lookaround { pc });
+                    }
+                    open.push((starts.len(), end as usize, op == OpCode::NegativeLookahead));
+                }
+                OpCode::LookaheadMatch | OpCode::NegativeLookaheadMatch => {
+                    let negative = op == OpCode::NegativeLookaheadMatch;
+                    if open.last().map(|&(_, _, n)| n) != Some(negative) {
+                        return Err(BytecodeError::Lookaround { pc });
+                    }
+                }
+                _ => {}
+            }
+            starts.push(pc);
+            regions.push(region);
+            pc += size;
+        }
+        // Bodies may not end at the end of the code: the continuation after
+        // a lookaround has to be an instruction
+        if let Some(&(index, _, _)) = open.last() {
+            return Err(BytecodeError::Lookaround { pc: starts[index] });
+        }
+        Ok(Code { starts, regions })
+    }
+
+    /// A jump by instruction `i` at `pc` must land on an instruction in the
+    /// same lookaround body as itself.
+    fn check_jump(&self, i: usize, pc: usize, target: i64) -> Result<(), BytecodeError> {
+        let landed = usize::try_from(target).ok().and_then(|t| self.starts.binary_search(&t).ok());
+        match landed {
+            Some(j) if self.regions[j] == self.regions[i] => Ok(()),
+            _ => Err(BytecodeError::JumpTarget { pc, target }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::regex::{verify_bytecode, BytecodeError, Regex};
+
+    fn bytecode(pattern: &str) -> Vec<u8> {
+        Regex::new(pattern).unwrap().bytecode_slice().to_vec()
+    }
+
+    /// Replace the body of `bytecode`, fixing up the header length.
+    fn with_body(mut bytecode: Vec<u8>, body: &[u8]) -> Vec<u8> {
+        bytecode.truncate(8);
+        bytecode[4..8].copy_from_slice(&(body.len() as u32).to_le_bytes());
+        bytecode.extend_from_slice(body);
+        bytecode
+    }
+
+    #[test]
+    fn test_compiled_bytecode_verifies() {
+        let patterns = [
+            "abc", r"(?i)h\u{e9}llo", r"\d{2,4}|[^a-z\s]+", "(a|b)*?c{3}", r"(\w+)\s\1", r"(?<=ab)c(?!d)",
+            r"(?=(a(?!b)))x", r"^\bfoo\B$", "(?m)^.+$", r"[\u{10000}-\u{10FFFF}]", "(?s).{2,5}x", "(a)|(b)(?:c(d))?",
+            "(?:a?){2,40}b", r"(?<=ab)(?:c\b){3,}",
+        ];
+        for pattern in patterns {
+            assert_eq!(verify_bytecode(&bytecode(pattern)), Ok(()), "{}", pattern);
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_header() {
+        let good = bytecode("a");
+        assert_eq!(verify_bytecode(&good[..5]), Err(BytecodeError::Length { len: 5 }));
+        assert_eq!(verify_bytecode(&good[..good.len() - 1]), Err(BytecodeError::Length { len: good.len() - 1 }));
+        let mut bad = good.clone();
+        bad[1] = 0x80;
+        assert_eq!(verify_bytecode(&bad), Err(BytecodeError::UnknownFlags { bits: 0x8000 }));
+        let mut bad = good;
+        bad[2] = 0;
+        assert_eq!(verify_bytecode(&bad), Err(BytecodeError::NoCaptures));
+    }
+
+    #[test]
+    fn test_rejects_bad_instructions() {
+        let base = bytecode("a");
+        // save_start 0; char 'a'; save_end 0; match
+        let good = [19, 0, 1, b'a', 0, 20, 0, 16];
+        assert_eq!(verify_bytecode(&with_body(base.clone(), &good)), Ok(()));
+        let cases: [(&[u8], BytecodeError); 9] = [
+            (&[19, 0, 99, 16], BytecodeError::UnknownOpcode { pc: 10, opcode: 99 }),
+            (&[19, 0, 1, b'a'], BytecodeError::Truncated { pc: 10 }),
+            (&[19, 1, 16], BytecodeError::CaptureIndex { pc: 8, index: 1 }),
+            (&[27, 0, 1, 0, 0, 0, 16], BytecodeError::RegisterIndex { pc: 8, index: 0 }),
+            (&[13, 1, 0, 0, 0, 16], BytecodeError::JumpTarget { pc: 8, target: 14 }),
+            (&[13, 0xf0, 0xff, 0xff, 0xff, 16], BytecodeError::JumpTarget { pc: 8, target: -3 }),
+            (&[32, 0, 16], BytecodeError::Operand { pc: 8 }),
+            (&[19, 0, 20, 0], BytecodeError::FallsOffEnd { pc: 10 }),
+            (&[17, 16], BytecodeError::Lookaround { pc: 8 }),
+        ];
+        for (body, expected) in cases {
+            assert_eq!(verify_bytecode(&with_body(base.clone(), body)), Err(expected), "{:?}", body);
+        }
+    }
+
+    #[test]
+    fn test_rejects_bad_lookaround() {
+        let base = bytecode("a");
+        // lookahead +4 { char 'a'; lookahead_match }; match
+        assert_eq!(verify_bytecode(&with_body(base.clone(), &[40, 4, 0, 0, 0, 1, b'a', 0, 17, 16])), Ok(()));
+        let cases: [&[u8]; 4] = [
+            // Body ends with the other kind of match
+            &[40, 4, 0, 0, 0, 1, b'a', 0, 18, 16],
+            // Body ends inside an instruction
+            &[40, 2, 0, 0, 0, 1, b'a', 0, 17, 16],
+            // Body runs to the end of the code
+            &[40, 5, 0, 0, 0, 1, b'a', 0, 17, 16],
+            // Inner body runs past the outer one
+            &[40, 6, 0, 0, 0, 40, 2, 0, 0, 0, 17, 17, 16],
+        ];
+        for body in cases {
+            assert!(matches!(verify_bytecode(&with_body(base.clone(), body)), Err(BytecodeError::Lookaround { .. })), "{:?}", body);
+        }
+        // Jumping into a lookaround body from outside it
+        let into = [13, 5, 0, 0, 0, 40, 4, 0, 0, 0, 1, b'a', 0, 17, 16];
+        assert_eq!(verify_bytecode(&with_body(base, &into)), Err(BytecodeError::JumpTarget { pc: 8, target: 18 }));
+    }
+
+    #[test]
+    fn test_rejects_loops_without_progress() {
+        let mut base = bytecode("a");
+        base[3] = 1;
+        let accepted: [&[u8]; 3] = [
+            // split +8; char 'a'; goto -13; match
+            &[15, 8, 0, 0, 0, 1, b'a', 0, 13, 0xf3, 0xff, 0xff, 0xff, 16],
+            // set_char_pos 0; split +7; check_advance 0; goto -14; match
+            &[42, 0, 15, 7, 0, 0, 0, 43, 0, 13, 0xf2, 0xff, 0xff, 0xff, 16],
+            // set_i32 0 3; save_start 0; loop_split 0 0 -12; match
+            &[27, 0, 3, 0, 0, 0, 19, 0, 23, 0, 0, 0, 0, 0, 0xf4, 0xff, 0xff, 0xff, 16],
+        ];
+        for body in accepted {
+            assert_eq!(verify_bytecode(&with_body(base.clone(), body)), Ok(()), "{:?}", body);
+        }
+        let cases: [(&[u8], usize); 6] = [
+            // save_start 0; goto -5 (itself); save_end 0; match
+            (&[19, 0, 13, 0xfb, 0xff, 0xff, 0xff, 20, 0, 16], 10),
+            // split +5; goto -10; match
+            (&[15, 5, 0, 0, 0, 13, 0xf6, 0xff, 0xff, 0xff, 16], 8),
+            // set_char_pos 0 outside the loop: check_advance passes forever
+            (&[42, 0, 15, 7, 0, 0, 0, 43, 0, 13, 0xf4, 0xff, 0xff, 0xff, 16], 10),
+            // set_i32 0 3; loop_split 0 0 -16: the counter is reset every time
+            (&[27, 0, 3, 0, 0, 0, 23, 0, 0, 0, 0, 0, 0xf0, 0xff, 0xff, 0xff, 16], 8),
+            // set_i32 0 3; save_start 0; loop 0 -8: plain loop wraps at zero
+            (&[27, 0, 3, 0, 0, 0, 19, 0, 22, 0, 0xf8, 0xff, 0xff, 0xff, 16], 14),
+            // prev; char 'a'; goto -9: steps back as far as it steps forward
+            (&[44, 1, b'a', 0, 13, 0xf7, 0xff, 0xff, 0xff, 16], 8),
+        ];
+        for (body, pc) in cases {
+            assert_eq!(verify_bytecode(&with_body(base.clone(), body)), Err(BytecodeError::EmptyLoop { pc }), "{:?}", body);
+        }
+    }
+}
@@ -107,11 +107,9 @@ mod exec_basic {
     #[test]
     fn capture_groups() {
         // ECMA spec: /(aa|aabaac|ba|b|c)*/.exec("aabaac") => ["aaba", "ba"]
-        // Pike VM finds "aabaac" via aa+ba+c (three * iterations), group 1 = "c" (last).
-        // ECMA backtracker tries alternatives left-to-right: aa at pos 0, ba at pos 2,
-        // then fails at pos 4 → match = "aaba". This is a known Pike VM limitation for
-        // alternation-inside-repetition with overlapping alternatives.
-        test_exec("(aa|aabaac|ba|b|c)*", "aabaac", Some(("aabaac", 0, &["aabaac", "c"])));
+        // Alternatives are tried left-to-right: aa at pos 0, ba at pos 2, then
+        // nothing at pos 4, so the longer aa+ba+c loses to the higher priority match.
+        test_exec("(aa|aabaac|ba|b|c)*", "aabaac", Some(("aaba", 0, &["aaba", "ba"])));
     }
 
     #[test]
@@ -0,0 +1,93 @@
+use quickjs_regex::{Flags, Match, Regex, Utf16Haystack};
+
+fn utf16(s: &str) -> Vec<u16> {
+    s.encode_utf16().collect()
+}
+
+fn unicode(pattern: &str) -> Regex {
+    Regex::with_flags(pattern, Flags::from_bits(Flags::UNICODE)).unwrap()
+}
+
+/// Offsets are code unit indices, with astral chars counting as two units
+#[test]
+fn test_code_unit_indices() {
+    let text = utf16("a\u{1F600}b");
+    let re = Regex::new("b").unwrap();
+    assert_eq!(re.find_at_utf16(&text, 0), Some(Match { start: 3, end: 4 }));
+    let re = unicode(".b");
+    assert_eq!(re.find_at_utf16(&text, 0), Some(Match { start: 1, end: 4 }));
+}
+
+/// In u mode, a start in the middle of a pair searches from the pair's start
+#[test]
+fn test_start_inside_pair_unicode() {
+    let text = utf16("\u{1F600}");
+    let re = unicode(".");
+    assert_eq!(re.find_at_utf16(&text, 1), Some(Match { start: 0, end: 2 }));
+    let re = unicode("\u{1F600}");
+    assert_eq!(re.find_at_utf16(&text, 1), Some(Match { start: 0, end: 2 }));
+}
+
+/// Without u, every code unit is a char: `.` matches half a pair
+#[test]
+fn test_code_units_non_unicode() {
+    let text = utf16("\u{1F600}x");
+    let re = Regex::new(".").unwrap();
+    assert_eq!(re.find_at_utf16(&text, 1), Some(Match { start: 1, end: 2 }));
+    assert_eq!(re.find_at_utf16(&text, 0), Some(Match { start: 0, end: 1 }));
+    let text = utf16("\u{1F600}");
+    assert_eq!(Regex::new("^.$").unwrap().find_at_utf16(&text, 0), None);
+    assert_eq!(Regex::new("^..$").unwrap().find_at_utf16(&text, 0), Some(Match { start: 0, end: 2 }));
+    assert_eq!(unicode("^.$").find_at_utf16(&text, 0), Some(Match { start: 0, end: 2 }));
+}
+
+/// Without u, an astral char in the pattern is its two surrogates
+#[test]
+fn test_astral_pattern_chars_non_unicode() {
+    let text = utf16("x\u{1F600}\u{1F601}");
+    let re = Regex::new("\u{1F601}").unwrap();
+    assert_eq!(re.find_at_utf16(&text, 0), Some(Match { start: 3, end: 5 }));
+    assert_eq!(re.find("x\u{1F601}").map(|m| (m.start, m.end)), Some((1, 5)));
+    // A class holds the two code units, each matching on its own
+    let re = Regex::new("[\u{1F600}]").unwrap();
+    assert_eq!(re.find_at_utf16(&text, 0), Some(Match { start: 1, end: 2 }));
+    assert_eq!(re.find_at_utf16(&text, 2), Some(Match { start: 2, end: 3 }));
+    let re = Regex::new("(?<=\u{1F600})\u{1F601}").unwrap();
+    assert_eq!(re.find_at_utf16(&text, 0), Some(Match { start: 3, end: 5 }));
+}
+
+/// A haystack is transcoded once and serves every search over it
+#[test]
+fn test_haystack_reused() {
+    let text = utf16("a\u{1F600} b\u{1F600} c");
+    let haystack = Utf16Haystack::new(&text);
+    let re = Regex::new("\\w\u{1F600}").unwrap();
+    assert_eq!(re.find_in_utf16(&haystack, 0), Some(Match { start: 0, end: 3 }));
+    assert_eq!(re.find_in_utf16(&haystack, 3), Some(Match { start: 4, end: 7 }));
+    let re = unicode("(\\w)\u{1F600}");
+    let caps = re.captures_in_utf16(&haystack, 1).unwrap();
+    assert_eq!(caps.get(1), Some(Match { start: 4, end: 5 }));
+    assert_eq!(caps.get_utf16(0), Some(&text[4..7]));
+}
+
+/// Lone surrogates (WTF-16) are single chars
+#[test]
+fn test_lone_surrogates() {
+    let text = [0x61, 0xD800, 0x62, 0xDC00];
+    let re = Regex::new("a.b").unwrap();
+    assert_eq!(re.find_at_utf16(&text, 0), Some(Match { start: 0, end: 3 }));
+    let re = unicode(r"\S$");
+    assert_eq!(re.find_at_utf16(&text, 0), Some(Match { start: 3, end: 4 }));
+    assert_eq!(re.find_at_utf16(&text, 5), None);
+}
+
+#[test]
+fn test_captures_utf16() {
+    let text = utf16("\u{e9}\u{1F600} user@host");
+    let re = Regex::new(r"(\w+)@(\w+)").unwrap();
+    let caps = re.captures_at_utf16(&text, 0).unwrap();
+    assert_eq!(caps.get(0), Some(Match { start: 4, end: 13 }));
+    assert_eq!(caps.get_utf16(1), Some(&utf16("user")[..]));
+    assert_eq!(caps.get(2), Some(Match { start: 9, end: 13 }));
+    assert!(re.captures_at_utf16(&text, 10).is_none());
+}